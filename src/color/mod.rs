@@ -0,0 +1,433 @@
+use std::fmt::Display;
+
+mod named;
+mod theme;
+
+pub use theme::{Theme, ThemeError};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ColorError {
+    pub input: String,
+    pub type_: ColorErrorType,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ColorErrorType {
+    InvalidHex,
+    UnknownName,
+    InvalidFunction,
+    InvalidArgumentCount(usize),
+    InvalidComponent(String),
+}
+
+pub type ColorResult<T> = Result<T, ColorError>;
+
+type Ce = ColorErrorType;
+
+
+#[allow(dead_code)]
+impl Color {
+    pub const BLACK: Color = Color::rgb(0,        0,        0);
+    pub const WHITE: Color = Color::rgb(u8::MAX,  u8::MAX,  u8::MAX);
+
+    pub fn new() -> Color {
+        Color {r: 0, g: 0, b: 0, a: 0}
+    }
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: u8::MAX }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// Parses any of the supported color notations:
+    ///
+    /// - `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`
+    /// - CSS named colors (`rebeccapurple`, `transparent`)
+    /// - `rgb(r, g, b)` / `rgba(r, g, b, a)`
+    /// - `hsl(h, s%, l%)` / `hsla(h, s%, l%, a)`
+    pub fn parse(input: &str) -> ColorResult<Color> {
+        let trimmed = input.trim();
+
+        if let Some(hex) = trimmed.strip_prefix('#') {
+            return Self::from_hex(hex).ok_or_else(|| error(input, Ce::InvalidHex));
+        }
+
+        if trimmed.contains('(') {
+            return parse_function(input, trimmed);
+        }
+
+        named::lookup(trimmed).ok_or_else(|| error(input, Ce::UnknownName))
+    }
+
+    /// Parses the digits of a hex color, without the leading `#`
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let nibble = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok();
+        let byte   = |i: usize| u8::from_str_radix(&hex[i..i+2], 16).ok();
+
+        // a single hex digit `f` is shorthand for `ff`
+        let short  = |i: usize| nibble(i).map(|n| n * 17);
+
+        match hex.len() {
+            3 => Some(Color::rgba(short(0)?, short(1)?, short(2)?, u8::MAX)),
+            4 => Some(Color::rgba(short(0)?, short(1)?, short(2)?, short(3)?)),
+            6 => Some(Color::rgba(byte (0)?, byte (2)?, byte (4)?, u8::MAX)),
+            8 => Some(Color::rgba(byte (0)?, byte (2)?, byte (4)?, byte (6)?)),
+            _ => None,
+        }
+    }
+
+    /// `hue` is in degrees, `saturation`, `lightness` and `alpha` are in the range `0.0..=1.0`
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Color {
+        let h = hue.rem_euclid(360.0) / 360.0;
+        let s = saturation.clamp(0.0, 1.0);
+        let l = lightness .clamp(0.0, 1.0);
+
+        // https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+        let channel = |n: f64| {
+            let k = (n + h * 12.0) % 12.0;
+            let a = s * l.min(1.0 - l);
+
+            l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+
+        Color::rgba(
+            to_byte(channel(0.0)),
+            to_byte(channel(8.0)),
+            to_byte(channel(4.0)),
+            to_byte(alpha),
+        )
+    }
+
+    /// Returns `(hue, saturation, lightness)`, with hue in degrees
+    pub fn to_hsl(self) -> (f64, f64, f64) {
+        let r = self.r as f64 / 255.0;
+        let g = self.g as f64 / 255.0;
+        let b = self.b as f64 / 255.0;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+
+        let lightness = (max + min) / 2.0;
+        let delta     =  max - min;
+
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+
+        let hue = if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        }
+        else if max == g {
+            (b - r) / delta + 2.0
+        }
+        else {
+            (r - g) / delta + 4.0
+        };
+
+        (hue * 60.0, saturation, lightness)
+    }
+
+    /// Increases the HSL lightness by `amount` (`0.0..=1.0`)
+    pub fn lighten(self, amount: f64) -> Color {
+        let (h, s, l) = self.to_hsl();
+
+        Color::from_hsl(h, s, l + amount, self.alpha())
+    }
+
+    /// Decreases the HSL lightness by `amount` (`0.0..=1.0`)
+    pub fn darken(self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    pub fn with_alpha(self, alpha: f64) -> Color {
+        Color {
+            a: to_byte(alpha),
+            ..self
+        }
+    }
+
+    pub fn alpha(self) -> f64 {
+        self.a as f64 / 255.0
+    }
+
+    pub fn to_hex(self) -> String {
+        if self.a == u8::MAX {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        }
+        else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// Composites `src` over this color, scaling the source alpha by `coverage`
+    pub fn blend(&mut self, src: Color, coverage: f64) {
+        let src_a = src.alpha() * coverage.clamp(0.0, 1.0);
+        let dst_a = self.alpha();
+
+        let out_a = src_a + dst_a * (1.0 - src_a);
+
+        if out_a <= 0.0 {
+            *self = Color::new();
+            return;
+        }
+
+        let mix = |s: u8, d: u8| {
+            let s = s as f64 / 255.0;
+            let d = d as f64 / 255.0;
+
+            to_byte((s * src_a + d * dst_a * (1.0 - src_a)) / out_a)
+        };
+
+        *self = Color::rgba(
+            mix(src.r, self.r),
+            mix(src.g, self.g),
+            mix(src.b, self.b),
+            to_byte(out_a),
+        );
+    }
+
+    pub fn set_black(&mut self) {
+        self.r = 0;
+        self.g = 0;
+        self.b = 0;
+        self.a = u8::MAX;
+    }
+
+    pub fn set_white(&mut self) {
+        self.r = u8::MAX;
+        self.g = u8::MAX;
+        self.b = u8::MAX;
+        self.a = u8::MAX;
+    }
+
+    pub fn set_white_alpha(&mut self, alpha: f64) {
+        self.set_white();
+
+        let alpha = alpha.clamp(0.0, 1.0);
+        let alpha = 1.0 - alpha;
+
+        let val = (alpha * u8::MAX as f64) as u8;
+
+        self.a = val;
+    }
+
+    pub fn into_vec(&self) -> Vec<u8> {
+        vec![
+            self.r,
+            self.g,
+            self.b,
+            self.a,
+        ]
+    }
+}
+
+
+impl Into<Vec<u8>> for Color {
+    fn into(self) -> Vec<u8> {
+        self.into_vec()
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Display for ColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.type_ {
+            Ce::InvalidHex               => write!(f, "Invalid hex color '{}'",  self.input),
+            Ce::UnknownName              => write!(f, "Unknown color name '{}'", self.input),
+            Ce::InvalidFunction          => write!(f, "Invalid color function '{}', expected rgb(), rgba(), hsl() or hsla()", self.input),
+            Ce::InvalidArgumentCount(n)  => write!(f, "Wrong number of arguments ({}) in '{}'", n, self.input),
+            Ce::InvalidComponent(comp)   => write!(f, "Invalid color component '{}' in '{}'", comp, self.input),
+        }
+    }
+}
+
+
+fn error(input: &str, type_: ColorErrorType) -> ColorError {
+    ColorError {
+        input: input.to_owned(),
+        type_,
+    }
+}
+
+fn to_byte(val: f64) -> u8 {
+    (val.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// rgb(), rgba(), hsl(), hsla()
+// Accepts both the legacy comma syntax `rgb(1, 2, 3)`
+// and the space syntax `rgb(1 2 3 / 50%)`
+fn parse_function(input: &str, trimmed: &str) -> ColorResult<Color> {
+    let invalid = || error(input, Ce::InvalidFunction);
+
+    let (name, rest) = trimmed.split_once('(').ok_or_else(invalid)?;
+    let args         = rest.strip_suffix(')').ok_or_else(invalid)?;
+
+    let args: Vec<&str> = args
+        .split(|ch: char| ch == ',' || ch == '/' || ch.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect()
+    ;
+
+    let alpha = match args.len() {
+        3 => 1.0,
+        4 => parse_alpha(input, args[3])?,
+        n => Err(error(input, Ce::InvalidArgumentCount(n)))?,
+    };
+
+    match name.trim().to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => {
+            let r = parse_channel(input, args[0])?;
+            let g = parse_channel(input, args[1])?;
+            let b = parse_channel(input, args[2])?;
+
+            Ok(Color::rgba(r, g, b, to_byte(alpha)))
+        },
+        "hsl" | "hsla" => {
+            let h = parse_hue       (input, args[0])?;
+            let s = parse_percentage(input, args[1])?;
+            let l = parse_percentage(input, args[2])?;
+
+            Ok(Color::from_hsl(h, s, l, alpha))
+        },
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_number(input: &str, arg: &str) -> ColorResult<f64> {
+    arg.parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .ok_or_else(|| error(input, Ce::InvalidComponent(arg.to_owned())))
+}
+
+// `0..=255`, or `0%..=100%`
+fn parse_channel(input: &str, arg: &str) -> ColorResult<u8> {
+    match arg.strip_suffix('%') {
+        Some(pct) => Ok(to_byte(parse_number(input, pct)? / 100.0)),
+        None      => Ok(parse_number(input, arg)?.clamp(0.0, 255.0).round() as u8),
+    }
+}
+
+// `0.0..=1.0`, or `0%..=100%`
+fn parse_alpha(input: &str, arg: &str) -> ColorResult<f64> {
+    match arg.strip_suffix('%') {
+        Some(pct) => Ok(parse_number(input, pct)? / 100.0),
+        None      => Ok(parse_number(input, arg)?),
+    }
+}
+
+fn parse_percentage(input: &str, arg: &str) -> ColorResult<f64> {
+    let pct = arg.strip_suffix('%').ok_or_else(|| {
+        error(input, Ce::InvalidComponent(arg.to_owned()))
+    })?;
+
+    Ok(parse_number(input, pct)? / 100.0)
+}
+
+fn parse_hue(input: &str, arg: &str) -> ColorResult<f64> {
+    parse_number(input, arg.strip_suffix("deg").unwrap_or(arg))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex() {
+        assert_eq!(Color::parse("#336699"),   Ok(Color::rgb (0x33, 0x66, 0x99)));
+        assert_eq!(Color::parse("#33669980"), Ok(Color::rgba(0x33, 0x66, 0x99, 0x80)));
+        assert_eq!(Color::parse("#369"),      Ok(Color::rgb (0x33, 0x66, 0x99)));
+        assert_eq!(Color::parse("#3698"),     Ok(Color::rgba(0x33, 0x66, 0x99, 0x88)));
+
+        assert!(Color::parse("#3366").is_ok());
+        assert!(Color::parse("#33669").is_err());
+        assert!(Color::parse("#zzzzzz").is_err());
+    }
+
+    #[test]
+    fn named() {
+        assert_eq!(Color::parse("rebeccapurple"), Ok(Color::rgb(102, 51, 153)));
+        assert_eq!(Color::parse("White"),         Ok(Color::WHITE));
+        assert_eq!(Color::parse("transparent"),   Ok(Color::rgba(0, 0, 0, 0)));
+
+        let err = Color::parse("blurple").unwrap_err();
+        assert_eq!(err.type_, ColorErrorType::UnknownName);
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(Color::parse("rgb(51, 102, 153)"),       Ok(Color::rgb (51, 102, 153)));
+        assert_eq!(Color::parse("rgba(51, 102, 153, 0.5)"), Ok(Color::rgba(51, 102, 153, 128)));
+        assert_eq!(Color::parse("rgb(51 102 153 / 50%)"),   Ok(Color::rgba(51, 102, 153, 128)));
+        assert_eq!(Color::parse("rgb(100%, 0%, 0%)"),       Ok(Color::rgb (255, 0, 0)));
+
+        assert_eq!(Color::parse("hsl(0, 100%, 50%)"),       Ok(Color::rgb (255, 0, 0)));
+        assert_eq!(Color::parse("hsl(120deg 100% 25%)"),    Ok(Color::rgb (0, 128, 0)));
+        assert_eq!(Color::parse("hsla(210, 50%, 40%, 1)"),  Ok(Color::rgb (51, 102, 153)));
+
+        assert!(Color::parse("rgb(1, 2)")       .is_err());
+        assert!(Color::parse("rgb(a, b, c)")    .is_err());
+        assert!(Color::parse("hsl(0, 100, 50)") .is_err());
+        assert!(Color::parse("cmyk(0, 0, 0, 0)").is_err());
+    }
+
+    #[test]
+    fn hsl_round_trip() {
+        let color     = Color::rgb(0x33, 0x66, 0x99);
+        let (h, s, l) = color.to_hsl();
+
+        assert_eq!(Color::from_hsl(h, s, l, 1.0), color);
+    }
+
+    #[test]
+    fn lighten_darken() {
+        let color = Color::rgb(0x33, 0x66, 0x99);
+
+        let (_, _, base)    = color.to_hsl();
+        let (_, _, lighter) = color.lighten(0.2).to_hsl();
+        let (_, _, darker)  = color.darken (0.2).to_hsl();
+
+        assert!((lighter - (base + 0.2)).abs() < 0.01);
+        assert!((darker  - (base - 0.2)).abs() < 0.01);
+
+        assert_eq!(color.lighten(1.0), Color::WHITE);
+        assert_eq!(color.darken (1.0), Color::BLACK);
+    }
+
+    #[test]
+    fn blend() {
+        let mut pixel = Color::new();
+        pixel.blend(Color::WHITE, 0.5);
+
+        assert_eq!(pixel, Color::rgba(255, 255, 255, 128));
+
+        let mut pixel = Color::BLACK;
+        pixel.blend(Color::WHITE, 1.0);
+
+        assert_eq!(pixel, Color::WHITE);
+    }
+}
@@ -0,0 +1,311 @@
+use std::{fmt::Display, fs, path::Path};
+
+use super::{Color, ColorError, ColorResult};
+
+
+/// A named palette that diagrams are drawn with.
+///
+/// Node colors may refer to the theme slots by name (`"accent"`, `"palette.2"`),
+/// so that switching the theme restyles the whole diagram
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name:       String,
+    pub background: Color,
+    pub foreground: Color,
+    pub fill:       Color,
+    pub stroke:     Color,
+    pub accent:     Color,
+    pub palette:    Vec<Color>,
+}
+
+#[derive(Debug)]
+pub struct ThemeError {
+    pub line:  usize,
+    pub type_: ThemeErrorType,
+}
+
+#[derive(Debug)]
+pub enum ThemeErrorType {
+    IOError(String),
+    UnknownTheme(String),
+    UnknownKey(String),
+    MissingSeparator,
+    InvalidColor(ColorError),
+}
+
+type Te = ThemeErrorType;
+
+static BUILTIN_THEMES: &[&str] = &["light", "dark", "high-contrast"];
+
+
+impl Theme {
+
+    pub fn light() -> Theme {
+        Theme {
+            name:       "light".to_owned(),
+            background: Color::rgb(0xff, 0xff, 0xff),
+            foreground: Color::rgb(0x24, 0x29, 0x2f),
+            fill:       Color::rgb(0xf6, 0xf8, 0xfa),
+            stroke:     Color::rgb(0x57, 0x60, 0x6a),
+            accent:     Color::rgb(0x09, 0x69, 0xda),
+            palette:    vec![
+                Color::rgb(0x09, 0x69, 0xda),
+                Color::rgb(0x1a, 0x7f, 0x37),
+                Color::rgb(0x9a, 0x67, 0x00),
+                Color::rgb(0xcf, 0x22, 0x2e),
+                Color::rgb(0x82, 0x50, 0xdf),
+                Color::rgb(0x1b, 0x7c, 0x83),
+            ],
+        }
+    }
+
+    pub fn dark() -> Theme {
+        Theme {
+            name:       "dark".to_owned(),
+            background: Color::rgb(0x0d, 0x11, 0x17),
+            foreground: Color::rgb(0xe6, 0xed, 0xf3),
+            fill:       Color::rgb(0x16, 0x1b, 0x22),
+            stroke:     Color::rgb(0x8b, 0x94, 0x9e),
+            accent:     Color::rgb(0x2f, 0x81, 0xf7),
+            palette:    vec![
+                Color::rgb(0x2f, 0x81, 0xf7),
+                Color::rgb(0x3f, 0xb9, 0x50),
+                Color::rgb(0xd2, 0x99, 0x22),
+                Color::rgb(0xf8, 0x51, 0x49),
+                Color::rgb(0xa3, 0x71, 0xf7),
+                Color::rgb(0x39, 0xc5, 0xcf),
+            ],
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name:       "high-contrast".to_owned(),
+            background: Color::BLACK,
+            foreground: Color::WHITE,
+            fill:       Color::BLACK,
+            stroke:     Color::WHITE,
+            accent:     Color::rgb(0xff, 0xff, 0x00),
+            palette:    vec![
+                Color::rgb(0xff, 0xff, 0x00),
+                Color::rgb(0x00, 0xff, 0xff),
+                Color::rgb(0xff, 0x00, 0xff),
+                Color::rgb(0x00, 0xff, 0x00),
+                Color::rgb(0xff, 0x80, 0x00),
+                Color::WHITE,
+            ],
+        }
+    }
+
+    pub fn builtin_names() -> &'static [&'static str] {
+        BUILTIN_THEMES
+    }
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        match name.to_ascii_lowercase().as_str() {
+            "light"                                => Some(Self::light()),
+            "dark"                                 => Some(Self::dark()),
+            "high-contrast" | "high_contrast"      => Some(Self::high_contrast()),
+            _                                      => None,
+        }
+    }
+
+    /// Selects a builtin theme by name, otherwise loads a theme file relative to `base_dir`.
+    /// This lets a team check a single theme file into a repo and share it across diagrams
+    pub fn find(name_or_path: &str, base_dir: &Path) -> Result<Theme, ThemeError> {
+        if let Some(theme) = Self::by_name(name_or_path) {
+            return Ok(theme);
+        }
+
+        let path = base_dir.join(name_or_path);
+        if !path.is_file() {
+            return Err(ThemeError { line: 0, type_: Te::UnknownTheme(name_or_path.to_owned()) });
+        }
+
+        Self::load(&path)
+    }
+
+    pub fn load(path: &Path) -> Result<Theme, ThemeError> {
+        let source = fs::read_to_string(path).map_err(|err| ThemeError {
+            line:  0,
+            type_: Te::IOError(format!("{}: {}", path.display(), err)),
+        })?;
+
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+
+        Self::parse(&name, &source)
+    }
+
+    /// Parses a theme file, made of `key: value` lines:
+    ///
+    /// ```text
+    /// // start from a builtin theme, and override some slots
+    /// base:       dark
+    /// accent:     #ff8800
+    /// palette:    #336699, rebeccapurple, hsl(120, 50%, 50%)
+    /// ```
+    pub fn parse(name: &str, source: &str) -> Result<Theme, ThemeError> {
+        let mut theme = Self::light();
+        theme.name    = name.to_owned();
+
+        for (i, line) in source.lines().enumerate() {
+            let line_no = i + 1;
+            let err     = |type_| ThemeError { line: line_no, type_ };
+
+            let line = line.split("//").next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line.split_once(':').ok_or_else(|| err(Te::MissingSeparator))?;
+            let key          = key  .trim();
+            let value        = value.trim();
+
+            let color = |value: &str| Color::parse(value).map_err(|e| err(Te::InvalidColor(e)));
+
+            match key {
+                "base" => {
+                    let base = Self::by_name(value).ok_or_else(|| err(Te::UnknownTheme(value.to_owned())))?;
+                    theme = Theme { name: theme.name, ..base };
+                },
+                "background" => theme.background = color(value)?,
+                "foreground" => theme.foreground = color(value)?,
+                "fill"       => theme.fill       = color(value)?,
+                "stroke"     => theme.stroke     = color(value)?,
+                "accent"     => theme.accent     = color(value)?,
+                "palette"    => {
+                    theme.palette = split_colors(value)
+                        .map(color)
+                        .collect::<Result<_, _>>()?
+                    ;
+                },
+                _ => Err(err(Te::UnknownKey(key.to_owned())))?,
+            }
+        }
+
+        Ok(theme)
+    }
+
+    /// Resolves a color string, which may name a theme slot
+    pub fn resolve(&self, value: &str) -> ColorResult<Color> {
+        let value = value.trim();
+
+        let slot = match value {
+            "background" => Some(self.background),
+            "foreground" => Some(self.foreground),
+            "fill"       => Some(self.fill),
+            "stroke"     => Some(self.stroke),
+            "accent"     => Some(self.accent),
+            _            => value
+                .strip_prefix("palette.")
+                .and_then(|i| i.parse::<usize>().ok())
+                .and_then(|i| self.palette.get(i).copied()),
+        };
+
+        match slot {
+            Some(color) => Ok(color),
+            None        => Color::parse(value),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Display for ThemeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line > 0 {
+            write!(f, "Line {}: ", self.line)?;
+        }
+
+        match &self.type_ {
+            Te::IOError(msg)        => write!(f, "Unable to read theme file ({})", msg),
+            Te::UnknownTheme(name)  => write!(f, "Unknown theme '{}', expected one of {} or a theme file", name, BUILTIN_THEMES.join(", ")),
+            Te::UnknownKey(key)     => write!(f, "Unknown theme key '{}'", key),
+            Te::MissingSeparator    => write!(f, "Expect ':' between theme key and value"),
+            Te::InvalidColor(err)   => write!(f, "{}", err),
+        }
+    }
+}
+
+
+// Splits on top level commas, so `rgb(1, 2, 3), red` yields two colors
+fn split_colors(value: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut parts = vec![];
+
+    for (i, ch) in value.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&value[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    parts.push(&value[start..]);
+
+    parts.into_iter().map(str::trim).filter(|p| !p.is_empty())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        for name in Theme::builtin_names() {
+            assert_eq!(&Theme::by_name(name).unwrap().name, name);
+        }
+
+        assert!(Theme::by_name("solarized").is_none());
+    }
+
+    #[test]
+    fn resolve_slots() {
+        let theme = Theme::dark();
+
+        assert_eq!(theme.resolve("accent"),    Ok(theme.accent));
+        assert_eq!(theme.resolve("palette.1"), Ok(theme.palette[1]));
+        assert_eq!(theme.resolve("#336699"),   Ok(Color::rgb(0x33, 0x66, 0x99)));
+
+        assert!(theme.resolve("palette.99").is_err());
+    }
+
+    #[test]
+    fn parse_file() {
+        let source = crate::multi_line!(
+            "// shared team theme",
+            "base:    dark",
+            "accent:  #ff8800 // orange",
+            "palette: rgb(1, 2, 3), red",
+        );
+
+        let theme = Theme::parse("team", &source).unwrap();
+
+        assert_eq!(theme.name,       "team");
+        assert_eq!(theme.background, Theme::dark().background);
+        assert_eq!(theme.accent,     Color::rgb(0xff, 0x88, 0x00));
+        assert_eq!(theme.palette,    vec![Color::rgb(1, 2, 3), Color::rgb(255, 0, 0)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = Theme::parse("bad", "base: dark\naccent #fff").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(matches!(err.type_, ThemeErrorType::MissingSeparator));
+
+        let err = Theme::parse("bad", "border: #fff").unwrap_err();
+        assert!(matches!(err.type_, ThemeErrorType::UnknownKey(_)));
+
+        let err = Theme::parse("bad", "fill: #ffff0").unwrap_err();
+        assert!(matches!(err.type_, ThemeErrorType::InvalidColor(_)));
+    }
+}
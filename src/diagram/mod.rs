@@ -0,0 +1,330 @@
+use std::{fmt::Display, path::{Path, PathBuf}};
+
+use crate::{
+    color::{ColorError, Theme, ThemeError},
+    script::vm::graph::{Attr, GraphSnapshot},
+    shapes::{BoundingBox, Cylinder, Diamond, Ellipse, Location, Polygon, Rect, RoundedRect, ShapeType},
+};
+
+pub mod style;
+
+pub use style::{ComputedStyle, ShapeKind, Style};
+
+
+/// The renderable form of the graph built by a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagram {
+    pub theme: Theme,
+    pub nodes: Vec<Node>,
+
+    /// Files other than the script that the diagram was built from, such as theme files
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub class: String,
+    pub style: ComputedStyle,
+}
+
+#[derive(Debug)]
+pub enum DiagramError {
+    Theme(ThemeError),
+    Attribute {
+        node:  String,
+        attr:  String,
+        type_: AttrErrorType,
+    },
+}
+
+#[derive(Debug)]
+pub enum AttrErrorType {
+    InvalidType { expected: &'static str, found: String },
+    InvalidColor(ColorError),
+    InvalidSize (f64),
+    InvalidSides(f64),
+    UnknownShape(String),
+}
+
+// fraction of a grid cell taken up by a node
+static NODE_SCALE: f64 = 0.6;
+
+// corner radius of a `rounded_rect` without a `corner_radius`, as a fraction of its smaller side
+static ROUNDED_RECT_RADIUS: f64 = 0.2;
+
+// height of the caps of a cylinder, as a fraction of its height
+static CYLINDER_CAP_HEIGHT: f64 = 0.25;
+
+
+impl Diagram {
+
+    /// `base_dir` is the directory of the script, used to find theme files
+    ///
+    /// Node styles cascade from the graph, through the node's class hierarchy (root class first),
+    /// to the node itself, with anything left unset falling back to the theme
+    pub fn build(graph: &GraphSnapshot, base_dir: &Path) -> Result<Diagram, DiagramError> {
+
+        let mut files = vec![];
+
+        let theme = match graph.attrs.get("theme") {
+            None | Some(Attr::Nil) => Theme::default(),
+            Some(attr)             => {
+                let name = expect_str("graph", "theme", attr)?;
+
+                if Theme::by_name(name).is_none() {
+                    files.push(base_dir.join(name));
+                }
+
+                Theme::find(name, base_dir).map_err(DiagramError::Theme)?
+            }
+        };
+
+        let graph_style = Style::from_attrs(&theme, "graph", &graph.attrs)?;
+
+        let nodes = graph.nodes.iter()
+            .map(|node| {
+                let mut style = graph_style.clone();
+
+                for class in node.classes.iter().rev() {
+                    style = Style::from_attrs(&theme, &class.name, &class.attrs)?.inherit(&style);
+                }
+
+                let style = Style::from_attrs(&theme, node.class(), &node.attrs)?.inherit(&style);
+
+                Ok(Node {
+                    class: node.class().to_owned(),
+                    style: style.compute(&theme),
+                })
+            })
+            .collect::<Result<_, _>>()?
+        ;
+
+        Ok(Diagram {
+            theme,
+            nodes,
+            files,
+        })
+    }
+
+    /// Places the nodes on a square grid, in creation order
+    pub fn layout(&self) -> Vec<Rect> {
+        let count = self.nodes.len();
+        let cols  = (count as f64).sqrt().ceil().max(1.0) as usize;
+        let cell  = 2.0 / cols as f64;
+
+        (0..count)
+            .map(|i| {
+                let col = (i % cols) as f64;
+                let row = (i / cols) as f64;
+
+                let center = Location::new(
+                    -1.0 + cell * (col + 0.5),
+                     1.0 - cell * (row + 0.5),
+                );
+
+                let half = cell * NODE_SCALE / 2.0;
+
+                Rect::from(&BoundingBox {
+                    top_left:     Location::new(center.x - half, center.y + half),
+                    bottom_right: Location::new(center.x + half, center.y - half),
+                })
+            })
+            .collect()
+    }
+
+    /// The shape of every node, placed by [`Diagram::layout`]
+    ///
+    /// `udc_per_pixel` converts pixel sizes in styles into UDC
+    pub fn shapes(&self, udc_per_pixel: f64) -> Vec<ShapeType> {
+        self.nodes.iter()
+            .zip(self.layout())
+            .map(|(node, rect)| node.shape(rect, udc_per_pixel))
+            .collect()
+    }
+}
+
+impl Node {
+
+    /// The shape of the node, filling `rect`
+    pub fn shape(&self, rect: Rect, udc_per_pixel: f64) -> ShapeType {
+        let radius = self.style.corner_radius * udc_per_pixel;
+
+        let Rect { center, dim } = rect;
+
+        match self.style.shape {
+            ShapeKind::Rect if radius > 0.0 => ShapeType::RoundedRect(RoundedRect::new(center, dim, radius)),
+            ShapeKind::Rect                 => ShapeType::Rect(Rect::new(center, dim)),
+
+            ShapeKind::RoundedRect => {
+                let radius = match radius {
+                    0.0 => dim.width.min(dim.height) * ROUNDED_RECT_RADIUS,
+                    _   => radius,
+                };
+
+                ShapeType::RoundedRect(RoundedRect::new(center, dim, radius))
+            },
+
+            ShapeKind::Ellipse  => ShapeType::Ellipse (Ellipse ::new(center, dim)),
+            ShapeKind::Diamond  => ShapeType::Diamond (Diamond ::new(center, dim)),
+            ShapeKind::Cylinder => {
+                let cap_height = dim.height * CYLINDER_CAP_HEIGHT;
+
+                ShapeType::Cylinder(Cylinder::new(center, dim, cap_height))
+            },
+            ShapeKind::Polygon  => ShapeType::Polygon (Polygon ::regular(center, dim, self.style.sides)),
+        }
+    }
+}
+
+
+fn expect_str<'a>(node: &str, attr: &str, value: &'a Attr) -> Result<&'a str, DiagramError> {
+    value.as_str().ok_or_else(|| {
+        attr_error(node, attr, AttrErrorType::InvalidType {
+            expected: "String",
+            found:    value.display_type().to_owned(),
+        })
+    })
+}
+
+fn attr_error(node: &str, attr: &str, type_: AttrErrorType) -> DiagramError {
+    DiagramError::Attribute {
+        node: node.to_owned(),
+        attr: attr.to_owned(),
+        type_,
+    }
+}
+
+impl Display for DiagramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagramError::Theme(err)                  => write!(f, "Theme error: {}", err),
+            DiagramError::Attribute { node, attr, type_ } => {
+                write!(f, "Invalid attribute '{}.{}': ", node, attr)?;
+
+                match type_ {
+                    AttrErrorType::InvalidType { expected, found } => write!(f, "expected {}, found {}", expected, found),
+                    AttrErrorType::InvalidColor(err)               => write!(f, "{}", err),
+                    AttrErrorType::InvalidSize (size)              => write!(f, "expected a non-negative size, found {}", size),
+                    AttrErrorType::InvalidSides(sides)             => write!(f, "expected a whole number of sides, at least 3, found {}", sides),
+                    AttrErrorType::UnknownShape(name)              => write!(f, "unknown shape '{}'", name),
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::Color, script::vm::graph::{Attrs, ClassSnapshot, NodeSnapshot}};
+
+    use super::*;
+
+    fn attrs(attrs: &[(&str, Attr)]) -> Attrs {
+        attrs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    fn node(class: &str, node_attrs: &[(&str, Attr)]) -> NodeSnapshot {
+        NodeSnapshot {
+            classes: vec![ClassSnapshot { name: class.to_owned(), attrs: Attrs::new() }],
+            attrs:   attrs(node_attrs),
+        }
+    }
+
+    fn str(s: &str) -> Attr {
+        Attr::String(s.to_owned())
+    }
+
+    #[test]
+    fn theme_and_colors() {
+        let graph = GraphSnapshot {
+            attrs: [("theme".to_owned(), Attr::String("dark".to_owned()))].into(),
+            nodes: vec![
+                node("Table", &[("color", Attr::String("#336699".to_owned()))]),
+                node("Table", &[("color", Attr::String("accent" .to_owned()))]),
+                node("Table", &[]),
+            ],
+        };
+
+        let diagram = Diagram::build(&graph, Path::new(".")).unwrap();
+        let dark    = Theme::dark();
+
+        assert_eq!(diagram.theme.name,     "dark");
+        assert_eq!(diagram.nodes[0].style.fill, Color::rgb(0x33, 0x66, 0x99));
+        assert_eq!(diagram.nodes[1].style.fill, dark.accent);
+        assert_eq!(diagram.nodes[2].style.fill, dark.fill);
+    }
+
+    #[test]
+    fn style_cascade() {
+        // Table < Entity
+        let classes = vec![
+            ClassSnapshot { name: "Table" .to_owned(), attrs: attrs(&[("fill",   str("red"))]) },
+            ClassSnapshot { name: "Entity".to_owned(), attrs: attrs(&[("fill",   str("blue")), ("padding", Attr::Number(4.0))]) },
+        ];
+
+        let graph = GraphSnapshot {
+            attrs: attrs(&[("stroke", str("green")), ("padding", Attr::Number(1.0))]),
+            nodes: vec![
+                NodeSnapshot { classes: classes.clone(), attrs: Attrs::new() },
+                NodeSnapshot { classes: classes.clone(), attrs: attrs(&[("stroke", str("black"))]) },
+                NodeSnapshot { classes: classes[1..].to_vec(), attrs: Attrs::new() },
+            ],
+        };
+
+        let diagram = Diagram::build(&graph, Path::new(".")).unwrap();
+        let styles  = diagram.nodes.iter().map(|n| &n.style).collect::<Vec<_>>();
+
+        assert_eq!(styles[0].fill,    Color::rgb(255, 0, 0));
+        assert_eq!(styles[0].stroke,  Color::rgb(0, 128, 0));
+        assert_eq!(styles[0].padding, 4.0);
+
+        assert_eq!(styles[1].fill,    Color::rgb(255, 0, 0));
+        assert_eq!(styles[1].stroke,  Color::BLACK);
+
+        assert_eq!(styles[2].fill,    Color::rgb(0, 0, 255));
+        assert_eq!(styles[2].padding, 4.0);
+    }
+
+    #[test]
+    fn invalid_color() {
+        let graph = GraphSnapshot {
+            attrs: Attrs::new(),
+            nodes: vec![
+                node("Table", &[("color", Attr::Number(5.0))]),
+            ],
+        };
+
+        let err = Diagram::build(&graph, Path::new(".")).unwrap_err();
+        assert!(matches!(err, DiagramError::Attribute { type_: AttrErrorType::InvalidType { .. }, .. }));
+
+        let graph = GraphSnapshot {
+            attrs: [("theme".to_owned(), Attr::String("no_such_theme".to_owned()))].into(),
+            nodes: vec![],
+        };
+
+        let err = Diagram::build(&graph, Path::new(".")).unwrap_err();
+        assert!(matches!(err, DiagramError::Theme(_)));
+    }
+
+    #[test]
+    fn node_shapes() {
+        let graph = GraphSnapshot {
+            attrs: Attrs::new(),
+            nodes: vec![
+                node("Node", &[]),
+                node("Node", &[("corner_radius", Attr::Number(10.0))]),
+                node("Node", &[("shape", str("cylinder"))]),
+                node("Node", &[("shape", str("polygon")), ("sides", Attr::Number(5.0))]),
+            ],
+        };
+
+        let diagram = Diagram::build(&graph, Path::new(".")).unwrap();
+        let shapes  = diagram.shapes(0.002);
+
+        assert!(matches!(shapes[0], ShapeType::Rect(_)));
+        assert!(matches!(&shapes[1], ShapeType::RoundedRect(rect) if rect.radius == 10.0 * 0.002));
+        assert!(matches!(shapes[2], ShapeType::Cylinder(_)));
+        assert!(matches!(&shapes[3], ShapeType::Polygon(polygon) if polygon.vertices.len() == 5));
+    }
+}
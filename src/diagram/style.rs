@@ -0,0 +1,285 @@
+use std::fmt::Display;
+
+use crate::{
+    color::{Color, Theme},
+    diagram::{AttrErrorType, DiagramError, attr_error, expect_str},
+    script::vm::graph::{Attr, Attrs},
+};
+
+
+static DEFAULT_STROKE_WIDTH:  f64 = 2.0;
+static DEFAULT_FONT_SIZE:     f64 = 14.0;
+static DEFAULT_CORNER_RADIUS: f64 = 0.0;
+static DEFAULT_PADDING:       f64 = 8.0;
+static DEFAULT_SIDES:         usize = 6;
+
+/// A partial set of style attributes, as set on the graph, a class or a node.
+///
+/// Unset (`None`) attributes are inherited, see [`Style::inherit`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub fill:          Option<Color>,
+    pub stroke:        Option<Color>,
+    pub stroke_width:  Option<f64>,
+    pub font_size:     Option<f64>,
+    pub corner_radius: Option<f64>,
+    pub padding:       Option<f64>,
+    pub shape:         Option<ShapeKind>,
+
+    /// The number of sides of a `polygon`
+    pub sides:         Option<usize>,
+}
+
+/// A fully resolved style, with every attribute set.
+///
+/// Sizes are in pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedStyle {
+    pub fill:          Color,
+    pub stroke:        Color,
+    pub stroke_width:  f64,
+    pub font_size:     f64,
+    pub corner_radius: f64,
+    pub padding:       f64,
+    pub shape:         ShapeKind,
+    pub sides:         usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShapeKind {
+    #[default]
+    Rect,
+    RoundedRect,
+    Ellipse,
+    Diamond,
+    Cylinder,
+    Polygon,
+}
+
+
+impl Style {
+
+    /// Reads the style attributes out of the fields of a script object.
+    ///
+    /// `color` is accepted as an alias of `fill`. Fields that aren't style attributes are ignored
+    pub fn from_attrs(theme: &Theme, owner: &str, attrs: &Attrs) -> Result<Style, DiagramError> {
+
+        let fill = match color_attr(theme, owner, attrs, "fill")? {
+            Some(fill) => Some(fill),
+            None       => color_attr(theme, owner, attrs, "color")?,
+        };
+
+        Ok(Style {
+            fill,
+            stroke:        color_attr (theme, owner, attrs, "stroke")?,
+            stroke_width:  size_attr  (owner, attrs, "stroke_width")?,
+            font_size:     size_attr  (owner, attrs, "font_size")?,
+            corner_radius: size_attr  (owner, attrs, "corner_radius")?,
+            padding:       size_attr  (owner, attrs, "padding")?,
+            shape:         shape_attr (owner, attrs, "shape")?,
+            sides:         sides_attr (owner, attrs, "sides")?,
+        })
+    }
+
+    /// Fills in the attributes not set on `self` from `parent`
+    pub fn inherit(self, parent: &Style) -> Style {
+        Style {
+            fill:          self.fill         .or(parent.fill),
+            stroke:        self.stroke       .or(parent.stroke),
+            stroke_width:  self.stroke_width .or(parent.stroke_width),
+            font_size:     self.font_size    .or(parent.font_size),
+            corner_radius: self.corner_radius.or(parent.corner_radius),
+            padding:       self.padding      .or(parent.padding),
+            shape:         self.shape        .or(parent.shape),
+            sides:         self.sides        .or(parent.sides),
+        }
+    }
+
+    /// Resolves any unset attributes to the theme's defaults
+    pub fn compute(&self, theme: &Theme) -> ComputedStyle {
+        ComputedStyle {
+            fill:          self.fill         .unwrap_or(theme.fill),
+            stroke:        self.stroke       .unwrap_or(theme.stroke),
+            stroke_width:  self.stroke_width .unwrap_or(DEFAULT_STROKE_WIDTH),
+            font_size:     self.font_size    .unwrap_or(DEFAULT_FONT_SIZE),
+            corner_radius: self.corner_radius.unwrap_or(DEFAULT_CORNER_RADIUS),
+            padding:       self.padding      .unwrap_or(DEFAULT_PADDING),
+            shape:         self.shape        .unwrap_or_default(),
+            sides:         self.sides        .unwrap_or(DEFAULT_SIDES),
+        }
+    }
+}
+
+impl ShapeKind {
+    pub fn parse(name: &str) -> Option<ShapeKind> {
+        match name {
+            "rect"         => Some(ShapeKind::Rect),
+            "rounded_rect" => Some(ShapeKind::RoundedRect),
+            "ellipse"      => Some(ShapeKind::Ellipse),
+            "diamond"      => Some(ShapeKind::Diamond),
+            "cylinder"     => Some(ShapeKind::Cylinder),
+            "polygon"      => Some(ShapeKind::Polygon),
+            _              => None,
+        }
+    }
+}
+
+impl Display for ShapeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ShapeKind::Rect        => "rect",
+            ShapeKind::RoundedRect => "rounded_rect",
+            ShapeKind::Ellipse     => "ellipse",
+            ShapeKind::Diamond     => "diamond",
+            ShapeKind::Cylinder    => "cylinder",
+            ShapeKind::Polygon     => "polygon",
+        })
+    }
+}
+
+
+fn get_attr<'a>(attrs: &'a Attrs, attr: &str) -> Option<&'a Attr> {
+    attrs.get(attr).filter(|value| **value != Attr::Nil)
+}
+
+fn color_attr(theme: &Theme, owner: &str, attrs: &Attrs, attr: &str) -> Result<Option<Color>, DiagramError> {
+    let Some(value) = get_attr(attrs, attr) else {
+        return Ok(None);
+    };
+
+    let value = expect_str(owner, attr, value)?;
+
+    theme.resolve(value)
+        .map(Some)
+        .map_err(|err| attr_error(owner, attr, AttrErrorType::InvalidColor(err)))
+}
+
+fn size_attr(owner: &str, attrs: &Attrs, attr: &str) -> Result<Option<f64>, DiagramError> {
+    let Some(value) = get_attr(attrs, attr) else {
+        return Ok(None);
+    };
+
+    let size = value.as_number().ok_or_else(|| {
+        attr_error(owner, attr, AttrErrorType::InvalidType {
+            expected: "Number",
+            found:    value.display_type().to_owned(),
+        })
+    })?;
+
+    if !size.is_finite() || size < 0.0 {
+        return Err(attr_error(owner, attr, AttrErrorType::InvalidSize(size)));
+    }
+
+    Ok(Some(size))
+}
+
+fn shape_attr(owner: &str, attrs: &Attrs, attr: &str) -> Result<Option<ShapeKind>, DiagramError> {
+    let Some(value) = get_attr(attrs, attr) else {
+        return Ok(None);
+    };
+
+    let name = expect_str(owner, attr, value)?;
+
+    ShapeKind::parse(name)
+        .map(Some)
+        .ok_or_else(|| attr_error(owner, attr, AttrErrorType::UnknownShape(name.to_owned())))
+}
+
+fn sides_attr(owner: &str, attrs: &Attrs, attr: &str) -> Result<Option<usize>, DiagramError> {
+    let Some(value) = get_attr(attrs, attr) else {
+        return Ok(None);
+    };
+
+    let sides = value.as_number().ok_or_else(|| {
+        attr_error(owner, attr, AttrErrorType::InvalidType {
+            expected: "Number",
+            found:    value.display_type().to_owned(),
+        })
+    })?;
+
+    if sides.fract() != 0.0 || !(3.0..=1024.0).contains(&sides) {
+        return Err(attr_error(owner, attr, AttrErrorType::InvalidSides(sides)));
+    }
+
+    Ok(Some(sides as usize))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(attrs: &[(&str, Attr)]) -> Attrs {
+        attrs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn from_attrs() {
+        let theme = Theme::light();
+
+        let style = Style::from_attrs(&theme, "Table", &attrs(&[
+            ("color",        Attr::String("red".to_owned())),
+            ("stroke",       Attr::String("accent".to_owned())),
+            ("stroke_width", Attr::Number(3.0)),
+            ("shape",        Attr::String("diamond".to_owned())),
+            ("padding",      Attr::Nil),
+            ("name",         Attr::String("users".to_owned())),
+        ])).unwrap();
+
+        assert_eq!(style.fill,         Some(Color::rgb(255, 0, 0)));
+        assert_eq!(style.stroke,       Some(theme.accent));
+        assert_eq!(style.stroke_width, Some(3.0));
+        assert_eq!(style.shape,        Some(ShapeKind::Diamond));
+        assert_eq!(style.padding,      None);
+
+        // `fill` takes precedence over its alias
+        let style = Style::from_attrs(&theme, "Table", &attrs(&[
+            ("color", Attr::String("red" .to_owned())),
+            ("fill",  Attr::String("blue".to_owned())),
+        ])).unwrap();
+
+        assert_eq!(style.fill, Some(Color::rgb(0, 0, 255)));
+    }
+
+    #[test]
+    fn from_attrs_errors() {
+        let theme = Theme::light();
+
+        let err = Style::from_attrs(&theme, "Table", &attrs(&[("padding", Attr::Number(-1.0))])).unwrap_err();
+        assert!(matches!(err, DiagramError::Attribute { type_: AttrErrorType::InvalidSize(_), .. }));
+
+        let err = Style::from_attrs(&theme, "Table", &attrs(&[("shape", Attr::String("blob".to_owned()))])).unwrap_err();
+        assert!(matches!(err, DiagramError::Attribute { type_: AttrErrorType::UnknownShape(_), .. }));
+
+        let err = Style::from_attrs(&theme, "Table", &attrs(&[("sides", Attr::Number(2.5))])).unwrap_err();
+        assert!(matches!(err, DiagramError::Attribute { type_: AttrErrorType::InvalidSides(_), .. }));
+
+        let err = Style::from_attrs(&theme, "Table", &attrs(&[("font_size", Attr::String("big".to_owned()))])).unwrap_err();
+        assert!(matches!(err, DiagramError::Attribute { type_: AttrErrorType::InvalidType { .. }, .. }));
+    }
+
+    #[test]
+    fn inherit_and_compute() {
+        let theme = Theme::dark();
+
+        let parent = Style {
+            fill:         Some(Color::WHITE),
+            stroke_width: Some(4.0),
+            ..Style::default()
+        };
+        let child = Style {
+            fill:         Some(Color::BLACK),
+            padding:      Some(2.0),
+            ..Style::default()
+        };
+
+        let style = child.inherit(&parent).compute(&theme);
+
+        assert_eq!(style.fill,          Color::BLACK);
+        assert_eq!(style.stroke,        theme.stroke);
+        assert_eq!(style.stroke_width,  4.0);
+        assert_eq!(style.padding,       2.0);
+        assert_eq!(style.font_size,     DEFAULT_FONT_SIZE);
+        assert_eq!(style.shape,         ShapeKind::Rect);
+    }
+}
@@ -0,0 +1,21 @@
+#![allow(dead_code)]
+
+//! Diagrams as code: a scripting language for describing graphs, and a renderer for them.
+//!
+//! Host programs can run scripts with an [`Interpreter`], exchanging values with them as [`HostValue`]s
+
+pub mod shapes;
+pub mod render;
+pub mod color;
+pub mod diagram;
+pub mod shape_tree;
+pub mod script;
+pub mod graph;
+pub mod watch;
+pub mod serve;
+pub mod lsp;
+
+mod macros;
+mod utils;
+
+pub use script::{interpreter::Interpreter, vm::{Limits, object::Arity, value::HostValue}};
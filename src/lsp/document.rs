@@ -0,0 +1,358 @@
+use crate::script::{
+    ast::{Stmt, VarType},
+    check_source,
+    diagnostic::{Severity, Span},
+    resolver::Reference,
+    tokens::Token,
+    Check,
+};
+
+use super::json::Json;
+
+
+// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#symbolKind
+const SYMBOL_CLASS:    usize = 5;
+const SYMBOL_METHOD:   usize = 6;
+const SYMBOL_FUNCTION: usize = 12;
+const SYMBOL_VARIABLE: usize = 13;
+
+const COMPLETION_FUNCTION: usize = 3;
+const COMPLETION_VARIABLE: usize = 6;
+const COMPLETION_CLASS:    usize = 7;
+
+const SEVERITY_ERROR:   usize = 1;
+const SEVERITY_WARNING: usize = 2;
+const SEVERITY_INFO:    usize = 3;
+
+
+/// An open file, checked every time its text changes
+pub struct Document {
+    pub text:  String,
+    pub check: Check,
+}
+
+/// A top level declaration, for go to definition and completion
+struct Decl<'a> {
+    name: &'a Token,
+    kind: usize,
+}
+
+
+impl Document {
+
+    pub fn new(uri: &str, text: String) -> Self {
+        let check = check_source(&text, uri);
+
+        Self {
+            text,
+            check,
+        }
+    }
+
+    pub fn diagnostics(&self) -> Json {
+        let diagnostics = self.check.diagnostics.iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity {
+                    Severity::Error   => SEVERITY_ERROR,
+                    Severity::Warning => SEVERITY_WARNING,
+                    Severity::Note    => SEVERITY_INFO,
+                };
+
+                let mut message = diagnostic.message.clone();
+                for note in &diagnostic.notes {
+                    message.push_str(&format!("\nnote: {}", note));
+                }
+
+                Json::object([
+                    ("range",    self.range(diagnostic.span.unwrap_or_default())),
+                    ("severity", severity.into()),
+                    ("source",   "lightweaver".into()),
+                    ("message",  message.into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        diagnostics.into()
+    }
+
+    /// The range of the declaration of the name at `position`
+    pub fn definition(&self, position: &Json) -> Option<Json> {
+        let reference = self.reference_at(position)?;
+
+        let decl = match reference.decl {
+            Some(decl) => decl,
+            None       => self.globals().into_iter().find(|decl| decl.name.lexeme == reference.name)?.name.span,
+        };
+
+        Some(self.range(decl))
+    }
+
+    pub fn hover(&self, position: &Json) -> Option<Json> {
+        let reference = self.reference_at(position)?;
+
+        let kind = match reference.var_type {
+            VarType::Local  (_) => "local variable",
+            VarType::Upvalue(_) => "captured variable",
+            VarType::Global     => "global",
+        };
+
+        Some(Json::object([
+            ("contents", Json::object([
+                ("kind",  "plaintext".into()),
+                ("value", format!("{}: {}", reference.name, kind).into()),
+            ])),
+            ("range", self.range(reference.span)),
+        ]))
+    }
+
+    pub fn symbols(&self) -> Json {
+        let Some(ast) = &self.check.ast else {
+            return Json::Array(vec![]);
+        };
+
+        self.stmt_symbols(&ast.stmts, true).into()
+    }
+
+    /// The top level declarations, followed by `builtins`
+    pub fn completions(&self, builtins: &[String]) -> Json {
+        let mut items: Vec<_> = self.globals().into_iter()
+            .map(|decl| {
+                let kind = match decl.kind {
+                    SYMBOL_CLASS    => COMPLETION_CLASS,
+                    SYMBOL_FUNCTION => COMPLETION_FUNCTION,
+                    _               => COMPLETION_VARIABLE,
+                };
+
+                (decl.name.lexeme.as_str(), kind)
+            })
+            .collect();
+
+        for builtin in builtins {
+            if !items.iter().any(|(name, _)| name == builtin) {
+                items.push((builtin, COMPLETION_VARIABLE));
+            }
+        }
+
+        items.into_iter()
+            .map(|(name, kind)| Json::object([
+                ("label", name.into()),
+                ("kind",  kind.into()),
+            ]))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn stmt_symbols(&self, stmts: &[Stmt], top_level: bool) -> Vec<Json> {
+        stmts.iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Function(func) => Some(self.symbol(&func.name, SYMBOL_FUNCTION, func.span, self.stmt_symbols(&func.body, false))),
+
+                Stmt::Class(class) => {
+                    let methods = class.methods.iter()
+                        .map(|method| self.symbol(&method.name, SYMBOL_METHOD, method.span, vec![]))
+                        .collect();
+
+                    Some(self.symbol(&class.name, SYMBOL_CLASS, class.span, methods))
+                }
+
+                Stmt::Var(var) if top_level => Some(self.symbol(&var.name, SYMBOL_VARIABLE, var.span, vec![])),
+
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn symbol(&self, name: &Token, kind: usize, span: Span, children: Vec<Json>) -> Json {
+        Json::object([
+            ("name",           name.lexeme.as_str().into()),
+            ("kind",           kind.into()),
+            ("range",          self.range(span)),
+            ("selectionRange", self.range(name.span)),
+            ("children",       children.into()),
+        ])
+    }
+
+    fn globals(&self) -> Vec<Decl<'_>> {
+        let Some(ast) = &self.check.ast else {
+            return vec![];
+        };
+
+        ast.stmts.iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Function(func) => Some(Decl { name: &func.name,  kind: SYMBOL_FUNCTION }),
+                Stmt::Class   (class) => Some(Decl { name: &class.name, kind: SYMBOL_CLASS }),
+                Stmt::Var     (var)   => Some(Decl { name: &var.name,   kind: SYMBOL_VARIABLE }),
+                _                     => None,
+            })
+            .collect()
+    }
+
+    fn reference_at(&self, position: &Json) -> Option<&Reference> {
+        let offset = self.offset(position)?;
+
+        self.check.references.iter()
+            .find(|reference| reference.span.start <= offset && offset <= reference.span.end)
+    }
+
+    fn range(&self, span: Span) -> Json {
+        Json::object([
+            ("start", self.position(span.start)),
+            ("end",   self.position(span.end)),
+        ])
+    }
+
+    /// A byte offset as an lsp position, a 0 based line and utf-16 column
+    fn position(&self, offset: usize) -> Json {
+        let offset = offset.min(self.text.len());
+        let before = &self.text[..offset];
+
+        let line       = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let character  = before[line_start..].encode_utf16().count();
+
+        Json::object([
+            ("line",      line.into()),
+            ("character", character.into()),
+        ])
+    }
+
+    fn offset(&self, position: &Json) -> Option<usize> {
+        let line      = position.get("line").as_usize()?;
+        let character = position.get("character").as_usize()?;
+
+        let line_start = match line {
+            0 => 0,
+            _ => self.text.match_indices('\n').nth(line - 1)?.0 + 1,
+        };
+
+        let mut units = 0;
+
+        for (i, ch) in self.text[line_start..].char_indices() {
+            if units >= character || ch == '\n' {
+                return Some(line_start + i);
+            }
+            units += ch.len_utf16();
+        }
+
+        Some(self.text.len())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::multi_line;
+
+    use super::*;
+
+    fn position(line: usize, character: usize) -> Json {
+        Json::object([
+            ("line",      line.into()),
+            ("character", character.into()),
+        ])
+    }
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Json {
+        Json::object([
+            ("start", position(start.0, start.1)),
+            ("end",   position(end.0,   end.1)),
+        ])
+    }
+
+    #[test]
+    fn positions() {
+        let document = Document::new("test.lox", "var é = \"😀\";\nprint é;\n".to_owned());
+
+        let offset = document.text.find("print é").unwrap() + 6;
+
+        assert_eq!(document.position(offset),              position(1, 6));
+        assert_eq!(document.offset(&position(1, 6)),       Some(offset));
+        assert_eq!(document.position(document.text.find(';').unwrap()), position(0, 12));
+
+        // past the end of a line clamps to the end of it
+        assert_eq!(document.offset(&position(0, 100)),     document.text.find('\n'));
+        assert_eq!(document.offset(&position(5, 0)),       None);
+    }
+
+    #[test]
+    fn navigation() {
+        let source = multi_line!(
+            "var total = 0;",
+            "fun add(x) {",
+            "    var y = x;",
+            "    return total + y;",
+            "}",
+            "print add(1);",
+        );
+
+        let document = Document::new("test.lox", source);
+
+        // local
+        assert_eq!(document.definition(&position(3, 20)), Some(range((2, 8), (2, 9))));
+
+        // global
+        assert_eq!(document.definition(&position(3, 12)), Some(range((0, 4), (0, 9))));
+        assert_eq!(document.definition(&position(5, 6)),  Some(range((1, 4), (1, 7))));
+
+        assert_eq!(document.definition(&position(1, 0)), None);
+
+        let hover = document.hover(&position(3, 20)).unwrap();
+        assert_eq!(hover.at(&["contents", "value"]).as_str(), Some("y: local variable"));
+    }
+
+    #[test]
+    fn symbols() {
+        let source = multi_line!(
+            "var a = 1;",
+            "class Box {",
+            "    init() {}",
+            "}",
+            "fun f() {",
+            "    var b = 2;",
+            "    fun g() {}",
+            "    g();",
+            "    return b;",
+            "}",
+        );
+
+        let document = Document::new("test.lox", source);
+        let symbols  = document.symbols();
+
+        let names: Vec<_> = symbols.as_array().iter()
+            .map(|symbol| symbol.get("name").as_str().unwrap())
+            .collect();
+
+        assert_eq!(names, vec!["a", "Box", "f"]);
+
+        assert_eq!(symbols.as_array()[1].get("children").as_array()[0].get("name").as_str(), Some("init"));
+        assert_eq!(symbols.as_array()[2].get("children").as_array()[0].get("name").as_str(), Some("g"));
+
+        let completions = document.completions(&["clock".to_owned(), "f".to_owned()]);
+        let labels: Vec<_> = completions.as_array().iter()
+            .map(|item| item.get("label").as_str().unwrap())
+            .collect();
+
+        assert_eq!(labels, vec!["a", "Box", "f", "clock"]);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let document    = Document::new("test.lox", "fun f() {\n    var unused = 1;\n}\nprint 1 +;\n".to_owned());
+        let diagnostics = document.diagnostics();
+
+        let diagnostic = &diagnostics.as_array()[0];
+
+        assert_eq!(diagnostics.as_array().len(), 1);
+        assert_eq!(diagnostic.get("severity").as_usize(), Some(SEVERITY_ERROR));
+        assert_eq!(diagnostic.at(&["range", "start", "line"]).as_usize(), Some(3));
+
+        let document    = Document::new("test.lox", "fun f() {\n    var unused = 1;\n}\n".to_owned());
+        let diagnostics = document.diagnostics();
+
+        let diagnostic = &diagnostics.as_array()[0];
+
+        assert_eq!(diagnostic.get("severity").as_usize(), Some(SEVERITY_WARNING));
+        assert_eq!(diagnostic.get("range"), &range((1, 8), (1, 14)));
+        assert!(diagnostic.get("message").as_str().unwrap().contains("lint: allow(unused_variable)"));
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt::{Display, Write};
+
+
+/// Just enough JSON for JSON-RPC, objects keep their keys in insertion order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool  (bool),
+    Number(f64),
+    String(String),
+    Array (Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JsonError {
+    pub offset: usize,
+    pub msg:    &'static str,
+}
+
+struct Parser<'a> {
+    source:  &'a [u8],
+    current: usize,
+}
+
+type JsonResult<T> = Result<T, JsonError>;
+
+
+impl Json {
+
+    pub fn parse(source: &str) -> JsonResult<Json> {
+        let mut parser = Parser {
+            source:  source.as_bytes(),
+            current: 0,
+        };
+
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+
+        match parser.current == parser.source.len() {
+            true  => Ok(value),
+            false => Err(parser.error("Unexpected trailing characters")),
+        }
+    }
+
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+        Json::Object(
+            fields.into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect()
+        )
+    }
+
+    /// The value of `key`, or null if this isn't an object or doesn't have the key
+    pub fn get(&self, key: &str) -> &Json {
+        let Json::Object(fields) = self else {
+            return &Json::Null;
+        };
+
+        fields.iter()
+            .find(|(k, _)| k == key)
+            .map_or(&Json::Null, |(_, value)| value)
+    }
+
+    /// Follows a path of keys, `json.at(&["params", "textDocument", "uri"])`
+    pub fn at(&self, path: &[&str]) -> &Json {
+        path.iter().fold(self, |json, key| json.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _               => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _                                                 => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(values) => values,
+            _                   => &[],
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+}
+
+impl From<bool>   for Json { fn from(b: bool)   -> Self { Json::Bool  (b) } }
+impl From<f64>    for Json { fn from(n: f64)    -> Self { Json::Number(n) } }
+impl From<usize>  for Json { fn from(n: usize)  -> Self { Json::Number(n as f64) } }
+impl From<&str>   for Json { fn from(s: &str)   -> Self { Json::String(s.to_owned()) } }
+impl From<String> for Json { fn from(s: String) -> Self { Json::String(s) } }
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Self {
+        Json::Array(values)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null      => write!(f, "null"),
+            Json::Bool  (b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+
+            Json::Array(values) => {
+                write!(f, "[")?;
+
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+
+                write!(f, "]")
+            }
+
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+
+    for ch in s.chars() {
+        match ch {
+            '"'  => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch                       => f.write_char(ch)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+
+impl Parser<'_> {
+
+    fn parse_value(&mut self) -> JsonResult<Json> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b't') => self.parse_literal("true",  Json::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Json::Bool(false)),
+            Some(b'n') => self.parse_literal("null",  Json::Null),
+
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+
+            Some(_) => Err(self.error("Unexpected character")),
+            None    => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self) -> JsonResult<Json> {
+        self.expect(b'{')?;
+
+        let mut fields = vec![];
+
+        self.skip_whitespace();
+        if self.match_(b'}') {
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+            self.expect(b':')?;
+
+            fields.push((key, self.parse_value()?));
+
+            self.skip_whitespace();
+            if self.match_(b'}') {
+                return Ok(Json::Object(fields));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn parse_array(&mut self) -> JsonResult<Json> {
+        self.expect(b'[')?;
+
+        let mut values = vec![];
+
+        self.skip_whitespace();
+        if self.match_(b']') {
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            if self.match_(b']') {
+                return Ok(Json::Array(values));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn parse_string(&mut self) -> JsonResult<String> {
+        self.expect(b'"')?;
+
+        let mut bytes = vec![];
+
+        loop {
+            let Some(byte) = self.advance() else {
+                return Err(self.error("Unterminated string"));
+            };
+
+            match byte {
+                b'"'  => break,
+                b'\\' => {
+                    let escaped = match self.advance() {
+                        Some(b'"')  => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/')  => '/',
+                        Some(b'b')  => '\u{8}',
+                        Some(b'f')  => '\u{c}',
+                        Some(b'n')  => '\n',
+                        Some(b'r')  => '\r',
+                        Some(b't')  => '\t',
+                        Some(b'u')  => self.parse_unicode_escape()?,
+
+                        _ => return Err(self.error("Invalid escape")),
+                    };
+
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error("Invalid utf-8 in string"))
+    }
+
+    // the 4 hex digits after `\u`, combining utf-16 surrogate pairs
+    fn parse_unicode_escape(&mut self) -> JsonResult<char> {
+        let high = self.parse_hex4()?;
+
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+
+        if !(self.match_(b'\\') && self.match_(b'u')) {
+            return Err(self.error("Unpaired surrogate"));
+        }
+
+        let low = self.parse_hex4()?;
+        let ch  = 0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+
+        char::from_u32(ch).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> JsonResult<u32> {
+        let end    = (self.current + 4).min(self.source.len());
+        let digits = std::str::from_utf8(&self.source[self.current..end]).unwrap_or("");
+
+        let value = u32::from_str_radix(digits, 16).map_err(|_| self.error("Invalid unicode escape"))?;
+        self.current = end;
+
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> JsonResult<Json> {
+        let start = self.current;
+
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.current += 1;
+        }
+
+        std::str::from_utf8(&self.source[start..self.current])
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| self.error("Invalid number"))
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> JsonResult<Json> {
+        match self.source[self.current..].starts_with(literal.as_bytes()) {
+            true  => {
+                self.current += literal.len();
+                Ok(value)
+            }
+            false => Err(self.error("Unexpected character")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.current += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.current).copied()
+    }
+
+    fn advance(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.current += 1;
+
+        Some(byte)
+    }
+
+    fn match_(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+
+        if matched {
+            self.current += 1;
+        }
+
+        matched
+    }
+
+    fn expect(&mut self, byte: u8) -> JsonResult<()> {
+        match self.match_(byte) {
+            true  => Ok(()),
+            false => Err(self.error("Unexpected character")),
+        }
+    }
+
+    fn error(&self, msg: &'static str) -> JsonError {
+        JsonError {
+            offset: self.current,
+            msg,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let source = r#"{"jsonrpc":"2.0","id":1,"params":{"text":"a \"b\"\n\tc","list":[true,false,null,-1.5,2e3]}}"#;
+
+        let json = Json::parse(source).unwrap();
+
+        assert_eq!(json.get("id").as_usize(),                    Some(1));
+        assert_eq!(json.at(&["params", "text"]).as_str(),         Some("a \"b\"\n\tc"));
+        assert_eq!(json.at(&["params", "list"]).as_array().len(), 5);
+        assert_eq!(json.at(&["params", "list"]).as_array()[4],   Json::Number(2000.0));
+        assert!   (json.at(&["params", "missing", "key"]).is_null());
+
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+    }
+
+    #[test]
+    fn unicode() {
+        let json = Json::parse(r#" "é😀 é" "#).unwrap();
+        assert_eq!(json.as_str(), Some("é😀 é"));
+
+        assert_eq!(Json::from("\u{1}").to_string(), r#""\u0001""#);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(Json::parse(r#"{"a": }"#)   .is_err());
+        assert!(Json::parse(r#"[1, 2"#)     .is_err());
+        assert!(Json::parse(r#""abc"#)      .is_err());
+        assert!(Json::parse(r#"{} extra"#)  .is_err());
+    }
+}
@@ -0,0 +1,341 @@
+mod document;
+mod json;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use document::Document;
+use json::Json;
+
+use crate::script::vm::{ArenaRoot, Root, Vm};
+
+
+// https://www.jsonrpc.org/specification#error_object
+const PARSE_ERROR:      isize = -32700;
+const INVALID_REQUEST:  isize = -32600;
+const METHOD_NOT_FOUND: isize = -32601;
+
+// the whole document is sent on every change
+const SYNC_FULL: usize = 1;
+
+
+/// A language server, speaking json-rpc with `Content-Length` framing
+struct Server<W: Write> {
+    out:       W,
+    documents: HashMap<String, Document>,
+    builtins:  Vec<String>,
+    shutdown:  bool,
+}
+
+
+/// Runs the language server on stdin and stdout, until the client sends `exit`
+pub fn run_stdio() -> ! {
+    let stdin  = io::stdin().lock();
+    let stdout = io::stdout().lock();
+
+    let shutdown = match serve(stdin, stdout) {
+        Ok (shutdown) => shutdown,
+        Err(err)      => {
+            eprintln!("[lsp] {}", err);
+            false
+        }
+    };
+
+    // exit without a shutdown request first is an error
+    std::process::exit(if shutdown { 0 } else { 1 })
+}
+
+/// Handles messages until `exit` or the end of input, returning whether `shutdown` was requested
+fn serve(mut input: impl BufRead, out: impl Write) -> io::Result<bool> {
+
+    let mut server = Server::new(out);
+
+    while let Some(message) = read_message(&mut input)? {
+
+        let message = match Json::parse(&message) {
+            Ok (message) => message,
+            Err(err)     => {
+                server.error(Json::Null, PARSE_ERROR, &format!("{} at byte {}", err.msg, err.offset))?;
+                continue;
+            }
+        };
+
+        if message.get("method").as_str() == Some("exit") {
+            break;
+        }
+
+        server.handle(&message)?;
+    }
+
+    Ok(server.shutdown)
+}
+
+/// Reads the headers and body of one message, or `None` at the end of input
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message without a Content-Length header"));
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "message body isn't valid utf-8"))
+}
+
+fn write_message(out: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    out.flush()
+}
+
+
+impl<W: Write> Server<W> {
+
+    fn new(out: W) -> Self {
+        let mut vm = Vm::init(ArenaRoot::new(|_ctx| Root::new()));
+
+        Self {
+            out,
+            documents: HashMap::new(),
+            builtins:  vm.root().mutate(|_ctx, root| root.global_names()),
+            shutdown:  false,
+        }
+    }
+
+    fn handle(&mut self, message: &Json) -> io::Result<()> {
+
+        let id     = message.get("id").clone();
+        let params = message.get("params");
+
+        let Some(method) = message.get("method").as_str() else {
+            // a response to a request we never send
+            return match id.is_null() {
+                true  => self.error(Json::Null, INVALID_REQUEST, "Message without a method"),
+                false => Ok(()),
+            };
+        };
+
+        let uri = params.at(&["textDocument", "uri"]).as_str().unwrap_or("");
+
+        let result = match method {
+            "initialize" => capabilities(),
+            "shutdown"   => {
+                self.shutdown = true;
+                Json::Null
+            }
+
+            "textDocument/didOpen" => {
+                let text = params.at(&["textDocument", "text"]).as_str().unwrap_or("");
+                return self.update(uri, text.to_owned());
+            }
+            "textDocument/didChange" => {
+                // with full sync, the last change is the whole document
+                let Some(change) = params.get("contentChanges").as_array().last() else {
+                    return Ok(());
+                };
+
+                let text = change.get("text").as_str().unwrap_or("");
+                return self.update(uri, text.to_owned());
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return self.publish(uri, Json::Array(vec![]));
+            }
+
+            "textDocument/definition" => self.with_document(uri, |document| {
+                document.definition(params.get("position"))
+                    .map(|range| Json::object([
+                        ("uri",   uri.into()),
+                        ("range", range),
+                    ]))
+                    .unwrap_or(Json::Null)
+            }),
+            "textDocument/hover" => self.with_document(uri, |document| {
+                document.hover(params.get("position")).unwrap_or(Json::Null)
+            }),
+            "textDocument/documentSymbol" => self.with_document(uri, Document::symbols),
+            "textDocument/completion"     => {
+                let builtins = self.builtins.clone();
+                self.with_document(uri, |document| document.completions(&builtins))
+            }
+
+            // notifications we don't handle are ignored, unknown requests are errors
+            _ if id.is_null() => return Ok(()),
+            _                 => return self.error(id, METHOD_NOT_FOUND, &format!("Unknown method '{}'", method)),
+        };
+
+        // the result of a notification isn't sent
+        if id.is_null() {
+            return Ok(());
+        }
+
+        write_message(&mut self.out, &Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id",      id),
+            ("result",  result),
+        ]))
+    }
+
+    fn with_document(&self, uri: &str, f: impl FnOnce(&Document) -> Json) -> Json {
+        self.documents.get(uri).map_or(Json::Null, f)
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> io::Result<()> {
+        let document    = Document::new(uri, text);
+        let diagnostics = document.diagnostics();
+
+        self.documents.insert(uri.to_owned(), document);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Json) -> io::Result<()> {
+        write_message(&mut self.out, &Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("method",  "textDocument/publishDiagnostics".into()),
+            ("params",  Json::object([
+                ("uri",         uri.into()),
+                ("diagnostics", diagnostics),
+            ])),
+        ]))
+    }
+
+    fn error(&mut self, id: Json, code: isize, message: &str) -> io::Result<()> {
+        write_message(&mut self.out, &Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id",      id),
+            ("error",   Json::object([
+                ("code",    Json::Number(code as f64)),
+                ("message", message.into()),
+            ])),
+        ]))
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object([
+        ("capabilities", Json::object([
+            ("textDocumentSync",       SYNC_FULL.into()),
+            ("definitionProvider",     true.into()),
+            ("hoverProvider",          true.into()),
+            ("documentSymbolProvider", true.into()),
+            ("completionProvider",     Json::object([])),
+        ])),
+        ("serverInfo", Json::object([
+            ("name",    "lightweaver".into()),
+            ("version", env!("CARGO_PKG_VERSION").into()),
+        ])),
+    ])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(messages: &[&str]) -> Vec<u8> {
+        messages.iter()
+            .flat_map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message).into_bytes())
+            .collect()
+    }
+
+    // runs a session, returning whether it shut down cleanly and the messages sent back
+    fn session(messages: &[&str]) -> (bool, Vec<Json>) {
+        let input   = frame(messages);
+        let mut out = vec![];
+
+        let shutdown = serve(input.as_slice(), &mut out).unwrap();
+
+        let mut reader   = out.as_slice();
+        let mut messages = vec![];
+
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(Json::parse(&message).unwrap());
+        }
+
+        (shutdown, messages)
+    }
+
+    #[test]
+    fn lifecycle() {
+        let (shutdown, messages) = session(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"workspace/symbol","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#,
+        ]);
+
+        assert!(shutdown);
+        assert_eq!(messages.len(), 3);
+
+        assert_eq!(messages[0].get("id").as_usize(), Some(1));
+        assert_eq!(messages[0].at(&["result", "capabilities", "textDocumentSync"]).as_usize(), Some(SYNC_FULL));
+
+        assert_eq!(messages[1].get("id").as_usize(), Some(2));
+        assert_eq!(messages[1].at(&["error", "code"]), &Json::Number(METHOD_NOT_FOUND as f64));
+
+        assert_eq!(messages[2].get("id").as_usize(), Some(3));
+        assert!   (messages[2].get("result").is_null());
+    }
+
+    #[test]
+    fn documents() {
+        let (shutdown, messages) = session(&[
+            r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///a.lox","languageId":"lox","version":1,"text":"print 1 +;"}}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.lox","version":2},"contentChanges":[{"text":"var a = 1;\nprint a;\n"}]}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":1,"character":6}}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/completion","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":2,"character":0}}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didClose","params":{"textDocument":{"uri":"file:///a.lox"}}}"#,
+        ]);
+
+        assert!(!shutdown);
+        assert_eq!(messages.len(), 5);
+
+        let diagnostics = |message: &Json| message.at(&["params", "diagnostics"]).as_array().len();
+
+        assert_eq!(messages[0].get("method").as_str(), Some("textDocument/publishDiagnostics"));
+        assert_eq!(diagnostics(&messages[0]), 1);
+        assert_eq!(diagnostics(&messages[1]), 0);
+
+        assert_eq!(messages[2].at(&["result", "uri"]).as_str(),                    Some("file:///a.lox"));
+        assert_eq!(messages[2].at(&["result", "range", "start", "character"]).as_usize(), Some(4));
+
+        let labels: Vec<_> = messages[3].get("result").as_array().iter()
+            .map(|item| item.get("label").as_str().unwrap())
+            .collect();
+
+        assert_eq!(labels[0], "a");
+        assert!(labels.contains(&"clock"));
+        assert!(labels.contains(&"graph"));
+
+        assert_eq!(diagnostics(&messages[4]), 0);
+    }
+}
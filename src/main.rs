@@ -1,24 +1,45 @@
 #![allow(dead_code)]
 
-use render::DataBuff;
-// use script::{ast::display::AstDisplayOpts, parser::Parser, scanner::Scanner};
-use shape_tree::ShapeTree;
-use shapes::{BoundingBox, Line, Location, Rect, ShapeType};
-use std::{fs::File, io::BufWriter, path::Path};
-
-mod shapes;
-mod render;
-mod color;
-mod shape_tree;
-mod script;
-mod graph;
-mod macros;
-mod utils;
+use lightweaver::{
+    lsp,
+    render::DataBuff,
+    Limits,
+    script,
+    serve,
+    // script::{ast::display::AstDisplayOpts, parser::Parser, scanner::Scanner},
+    shape_tree::ShapeTree,
+    shapes::{BoundingBox, Line, Location, Rect, ShapeType},
+    watch,
+};
+use std::{fs::{self, File}, io::BufWriter, path::Path, process, time::Duration};
+
+
+static DEFAULT_SCRIPT: &str = "./test_scripts/test.lox";
+static DEFAULT_OUT:    &str = "./out/diagram.png";
+static DEFAULT_PORT:   u16  = 8080;
 
 
 pub fn main() {
 
-    lox();
+    let (flags, args): (Vec<String>, Vec<String>) = std::env::args().partition(|a| a.starts_with("--max-"));
+    let arg    = |i: usize, default: &'static str| Path::new(args.get(i).map_or(default, |a| a.as_str()));
+    let limits = parse_limits(&flags);
+
+    match args.get(1).map(|a| a.as_str()) {
+        Some("repl")  => script::repl::run_repl(limits),
+        Some("lsp")   => lsp::run_stdio(),
+        Some("fmt")   => script::fmt::run_fmt(&args[2..]),
+        Some("watch") => watch::watch(arg(2, DEFAULT_SCRIPT), arg(3, DEFAULT_OUT), limits),
+        Some("serve") => {
+            let port = args.get(3).and_then(|port| port.parse().ok()).unwrap_or(DEFAULT_PORT);
+
+            if let Err(err) = serve::serve(arg(2, DEFAULT_SCRIPT), port, limits) {
+                eprintln!("Unable to start the preview server: {}", err);
+                process::exit(1);
+            }
+        }
+        _             => lox(limits),
+    }
 
     // test_png();
     // test_shape_tree();
@@ -28,32 +49,48 @@ pub fn main() {
 }
 
 
-fn write_png(buff: &DataBuff) {
-
-    let path = Path::new("./out/test.png");
-    let file = File::create(path).unwrap();
-
-    let ref mut w = BufWriter::new(file);
-
-    let mut encoder = png::Encoder::new(w, buff.width as u32, buff.height as u32);
-
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth ::Eight);
-    encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2));
-    encoder.set_source_chromaticities(png::SourceChromaticities::new(
-        (0.31270, 0.32900),
-        (0.64000, 0.33000),
-        (0.30000, 0.60000),
-        (0.15000, 0.06000)
-    ));
+/// Reads the `--max-instructions=N`, `--max-time=SECS`, `--max-depth=N` and
+/// `--max-heap=MB` flags over the default limits, where 0 means unlimited
+fn parse_limits(flags: &[String]) -> Limits {
+    let mut limits = Limits::default();
+
+    for flag in flags {
+        let Some((name, value)) = flag.split_once('=') else {
+            eprintln!("Expected a value for '{}', as in '{}=0'", flag, flag);
+            process::exit(1);
+        };
+
+        let Ok(value) = value.parse::<u64>() else {
+            eprintln!("Expected a whole number for '{}' but got '{}'", name, value);
+            process::exit(1);
+        };
+
+        let limit = (value > 0).then_some(value);
+
+        match name {
+            "--max-instructions" => limits.instructions = limit,
+            "--max-time"         => limits.time         = limit.map(Duration::from_secs),
+            "--max-depth"        => limits.call_depth   = limit.map_or(usize::MAX, |depth| depth as usize),
+            "--max-heap"         => limits.heap         = limit.map(|mb| mb as usize * 1024 * 1024),
+            _                    => {
+                eprintln!("Unknown limit '{}'", name);
+                process::exit(1);
+            }
+        }
+    }
 
-    let mut writer = encoder.write_header().unwrap();
+    limits
+}
 
-    let data: Vec<u8> = buff.data.iter().flat_map(|x| x.into_vec()).collect();
+fn write_png(buff: &DataBuff, path: &Path) {
 
-    writer.write_image_data(&data).unwrap();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap();
+    }
 
+    let file = File::create(path).unwrap();
 
+    buff.encode_png(BufWriter::new(file)).unwrap();
 }
 
 fn test_png() {
@@ -77,7 +114,7 @@ fn test_png() {
     buff.render_rect(&square);
     buff.render_line(&line);
 
-    write_png(&buff);
+    write_png(&buff, Path::new("./out/test.png"));
 }
 
 fn test_shape_tree() {
@@ -115,9 +152,20 @@ fn test_shape_tree() {
 // }
 
 
-fn lox() {
-    let path = Path::new("./test_scripts/test.lox");
-    script::run_file(path);
+fn lox(limits: Limits) {
+    let path    = Path::new(DEFAULT_SCRIPT);
+
+    let (diagram, report) = script::run_file(path, limits);
+    eprint!("{}", report);
+
+    let Some(diagram) = diagram else {
+        process::exit(1);
+    };
+
+    let mut buff = DataBuff::new(1000, 1000);
+    buff.render_diagram(&diagram);
+
+    write_png(&buff, Path::new(DEFAULT_OUT));
 }
 
 
@@ -1,4 +1,13 @@
-use crate::{color::Color, shapes::{GetBounding, Line, Location, Rect}};
+use std::io::Write;
+
+use crate::{
+    color::Color,
+    diagram::Diagram,
+    shapes::{BoundingBox, Cylinder, Ellipse, GetBounding, Line, Location, Rect, RoundedRect, ShapeType},
+};
+
+
+static DEFAULT_THICKNESS: f64 = 2.0;
 
 
 pub struct DataBuff {
@@ -60,7 +69,93 @@ impl DataBuff {
         (y * self.width) + x
     }
 
+    pub fn encode_png(&self, w: impl Write) -> Result<(), png::EncodingError> {
+
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth ::Eight);
+        encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2));
+        encoder.set_source_chromaticities(png::SourceChromaticities::new(
+            (0.31270, 0.32900),
+            (0.64000, 0.33000),
+            (0.30000, 0.60000),
+            (0.15000, 0.06000)
+        ));
+
+        let mut writer = encoder.write_header()?;
+
+        let data: Vec<u8> = self.data.iter().flat_map(|x| x.into_vec()).collect();
+
+        writer.write_image_data(&data)
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.data.fill(color);
+    }
+
+    pub fn render_diagram(&mut self, diagram: &Diagram) {
+        self.clear(diagram.theme.background);
+
+        let udc_per_pixel = 2.0 / self.width as f64;
+
+        for (node, shape) in diagram.nodes.iter().zip(diagram.shapes(udc_per_pixel)) {
+            self.fill_shape  (&shape, node.style.fill);
+            self.stroke_shape(&shape, node.style.stroke, node.style.stroke_width);
+        }
+    }
+
+    pub fn fill_shape(&mut self, shape: &ShapeType, color: Color) {
+        match shape {
+            ShapeType::Rect       (rect)     => self.fill_rect(rect, color),
+            ShapeType::Line       (_)        => {},
+            ShapeType::Triangle   (triangle) => self.fill_polygon(&triangle.to_vec(), color),
+            ShapeType::Ellipse    (ellipse)  => self.fill_ellipse(ellipse, color),
+            ShapeType::RoundedRect(rect)     => self.fill_rounded_rect(rect, color),
+            ShapeType::Diamond    (diamond)  => self.fill_polygon(&diamond.vertices(), color),
+            ShapeType::Cylinder   (cylinder) => self.fill_cylinder(cylinder, color),
+            ShapeType::Polygon    (polygon)  => self.fill_polygon(&polygon.vertices, color),
+        }
+    }
+
+    pub fn stroke_shape(&mut self, shape: &ShapeType, color: Color, thickness: f64) {
+        match shape {
+            ShapeType::Rect       (rect)     => self.stroke_rect(rect, color, thickness),
+            ShapeType::Line       (line)     => self.stroke_line(line, color, thickness),
+            ShapeType::Triangle   (triangle) => self.stroke_polygon(&triangle.to_vec(), color, thickness),
+            ShapeType::Ellipse    (ellipse)  => self.stroke_ellipse(ellipse, color, thickness),
+            ShapeType::RoundedRect(rect)     => self.stroke_rounded_rect(rect, color, thickness),
+            ShapeType::Diamond    (diamond)  => self.stroke_polygon(&diamond.vertices(), color, thickness),
+            ShapeType::Cylinder   (cylinder) => self.stroke_cylinder(cylinder, color, thickness),
+            ShapeType::Polygon    (polygon)  => self.stroke_polygon(&polygon.vertices, color, thickness),
+        }
+    }
+
+    pub fn fill_rect(&mut self, rect: &Rect, color: Color) {
+        let bounding = rect.bounding_box();
+
+        let top_left     = PixelLoc::from_udc(&bounding.top_left,     self.width, self.height);
+        let bottom_right = PixelLoc::from_udc(&bounding.bottom_right, self.width, self.height);
+
+        let start_y = (top_left.y     as usize).clamp(0, self.height);
+        let end_y   = (bottom_right.y as usize).clamp(0, self.height);
+
+        let start_x = (top_left.x     as usize).clamp(0, self.width);
+        let end_x   = (bottom_right.x as usize).clamp(0, self.width);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let ind = self.index(x, y);
+                self.data[ind].blend(color, 1.0);
+            }
+        }
+    }
+
     pub fn render_rect(&mut self, rect: &Rect) {
+        self.stroke_rect(rect, Color::WHITE, DEFAULT_THICKNESS);
+    }
+
+    pub fn stroke_rect(&mut self, rect: &Rect, color: Color, thickness: f64) {
         let bounding = rect.bounding_box();
 
         let top    = Line::new(bounding.top_left.clone(), bounding.top_right());
@@ -68,16 +163,72 @@ impl DataBuff {
         let left   = Line::new(bounding.top_left.clone(), bounding.bottom_left());
         let right  = Line::new(bounding.top_right(),      bounding.bottom_right.clone());
 
-        self.render_line(&top);
-        self.render_line(&bottom);
-        self.render_line(&left);
-        self.render_line(&right);
+        self.stroke_line(&top,    color, thickness);
+        self.stroke_line(&bottom, color, thickness);
+        self.stroke_line(&left,   color, thickness);
+        self.stroke_line(&right,  color, thickness);
+    }
+
+
+    pub fn fill_ellipse(&mut self, ellipse: &Ellipse, color: Color) {
+        let sdf = self.ellipse_sdf(ellipse);
+        self.fill_sdf(&ellipse.bounding_box(), color, sdf);
+    }
+
+    pub fn stroke_ellipse(&mut self, ellipse: &Ellipse, color: Color, thickness: f64) {
+        let sdf = self.ellipse_sdf(ellipse);
+        self.stroke_sdf(&ellipse.bounding_box(), color, thickness, sdf);
+    }
+
+    pub fn fill_rounded_rect(&mut self, rect: &RoundedRect, color: Color) {
+        let sdf = self.rounded_rect_sdf(rect);
+        self.fill_sdf(&rect.bounding_box(), color, sdf);
+    }
+
+    pub fn stroke_rounded_rect(&mut self, rect: &RoundedRect, color: Color, thickness: f64) {
+        let sdf = self.rounded_rect_sdf(rect);
+        self.stroke_sdf(&rect.bounding_box(), color, thickness, sdf);
+    }
+
+    pub fn fill_polygon(&mut self, vertices: &[Location], color: Color) {
+        let (bounding, sdf) = self.polygon_sdf(vertices);
+        self.fill_sdf(&bounding, color, sdf);
+    }
+
+    pub fn stroke_polygon(&mut self, vertices: &[Location], color: Color, thickness: f64) {
+        let (bounding, sdf) = self.polygon_sdf(vertices);
+        self.stroke_sdf(&bounding, color, thickness, sdf);
+    }
+
+    pub fn fill_cylinder(&mut self, cylinder: &Cylinder, color: Color) {
+        let sdf = self.cylinder_sdf(cylinder);
+        self.fill_sdf(&cylinder.bounding_box(), color, sdf);
     }
 
+    pub fn stroke_cylinder(&mut self, cylinder: &Cylinder, color: Color, thickness: f64) {
+        let sdf = self.cylinder_sdf(cylinder);
+        self.stroke_sdf(&cylinder.bounding_box(), color, thickness, sdf);
+
+        // the front rim of the top cap, the back half is hidden behind the fill
+        let top_cap = cylinder.top_cap();
+        let center  = PixelLoc::from_udc(&top_cap.center, self.width, self.height);
+        let rim     = self.ellipse_sdf(&top_cap);
+
+        self.stroke_sdf(&top_cap.bounding_box(), color, thickness, |pixel| {
+            if pixel.y < center.y { f64::INFINITY } else { rim(pixel) }
+        });
+    }
 
     pub fn render_line(&mut self, line: &Line) {
-        
-        let thickness = 2.0;
+        self.stroke_line(line, Color::WHITE, DEFAULT_THICKNESS);
+    }
+
+    pub fn stroke_line(&mut self, line: &Line, color: Color, thickness: f64) {
+
+        if thickness <= 0.0 {
+            return;
+        }
+
         let bounding  = line.bounding_box();
 
         let top_left     = PixelLoc::from_udc(&bounding.top_left,     self.width, self.height);
@@ -106,11 +257,173 @@ impl DataBuff {
                 let val = dist / thickness;
                 let ind = self.index(x, y);
 
-                self.data[ind].set_white_alpha(val);
+                self.data[ind].blend(color, 1.0 - val);
+
+            }
+        }
+    }
+}
+
+// Signed distance rasterization
+//
+// Shapes other than rects and lines are described by a signed distance function,
+// giving the distance in pixels from a pixel to the outline of the shape (negative inside).
+// Coverage falls off over one pixel at the edge, which gives cheap anti-aliasing.
+impl DataBuff {
+
+    fn fill_sdf(&mut self, bounding: &BoundingBox, color: Color, sdf: impl Fn(&PixelLoc) -> f64) {
+        self.rasterize(bounding, 1.0, color, |pixel| {
+            (0.5 - sdf(pixel)).clamp(0.0, 1.0)
+        });
+    }
+
+    fn stroke_sdf(&mut self, bounding: &BoundingBox, color: Color, thickness: f64, sdf: impl Fn(&PixelLoc) -> f64) {
+        if thickness <= 0.0 {
+            return;
+        }
+
+        let half = thickness / 2.0;
+
+        self.rasterize(bounding, half + 1.0, color, |pixel| {
+            (half + 0.5 - sdf(pixel).abs()).clamp(0.0, 1.0)
+        });
+    }
+
+    /// Blends `color` into every pixel within `margin` pixels of `bounding`, by the given coverage
+    fn rasterize(&mut self, bounding: &BoundingBox, margin: f64, color: Color, coverage: impl Fn(&PixelLoc) -> f64) {
+        let top_left     = PixelLoc::from_udc(&bounding.top_left,     self.width, self.height);
+        let bottom_right = PixelLoc::from_udc(&bounding.bottom_right, self.width, self.height);
+
+        let start_y = (top_left.y     - margin).max(0.0) as usize;
+        let end_y   = ((bottom_right.y + margin).max(0.0) as usize).min(self.height);
+
+        let start_x = (top_left.x     - margin).max(0.0) as usize;
+        let end_x   = ((bottom_right.x + margin).max(0.0) as usize).min(self.width);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+
+                // sample the center of the pixel
+                let pixel    = PixelLoc { x: x as f64 + 0.5, y: y as f64 + 0.5 };
+                let coverage = coverage(&pixel);
+
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                let ind = self.index(x, y);
+                self.data[ind].blend(color, coverage);
+            }
+        }
+    }
+
+    fn to_pixels(&self, dim_x: f64, dim_y: f64) -> (f64, f64) {
+        (dim_x * self.width as f64 / 2.0, dim_y * self.height as f64 / 2.0)
+    }
+
+    fn ellipse_sdf(&self, ellipse: &Ellipse) -> impl Fn(&PixelLoc) -> f64 {
+        let center   = PixelLoc::from_udc(&ellipse.center, self.width, self.height);
+        let (rx, ry) = ellipse.radii();
+        let (rx, ry) = self.to_pixels(rx, ry);
 
+        move |pixel| ellipse_distance(pixel.x - center.x, pixel.y - center.y, rx, ry)
+    }
+
+    fn rounded_rect_sdf(&self, rect: &RoundedRect) -> impl Fn(&PixelLoc) -> f64 {
+        let center     = PixelLoc::from_udc(&rect.center, self.width, self.height);
+        let (hw, hh)   = self.to_pixels(rect.dim.width / 2.0, rect.dim.height / 2.0);
+        let (r, _)     = self.to_pixels(rect.clamped_radius(), 0.0);
+        let r          = r.min(hw).min(hh);
+
+        move |pixel| rounded_rect_distance(pixel.x - center.x, pixel.y - center.y, hw, hh, r)
+    }
+
+    fn polygon_sdf(&self, vertices: &[Location]) -> (BoundingBox, impl Fn(&PixelLoc) -> f64) {
+        let x = vertices.iter().map(|v| v.x).collect();
+        let y = vertices.iter().map(|v| v.y).collect();
+
+        let bounding = BoundingBox::from((&x, &y));
+        let vertices = vertices.iter()
+            .map(|v| PixelLoc::from_udc(v, self.width, self.height))
+            .collect::<Vec<_>>()
+        ;
+
+        (bounding, move |pixel: &PixelLoc| polygon_distance(pixel, &vertices))
+    }
+
+    fn cylinder_sdf(&self, cylinder: &Cylinder) -> impl Fn(&PixelLoc) -> f64 {
+        let body   = RoundedRect::from_rect(cylinder.body(), 0.0);
+        let body   = self.rounded_rect_sdf(&body);
+        let top    = self.ellipse_sdf(&cylinder.top_cap());
+        let bottom = self.ellipse_sdf(&cylinder.bottom_cap());
+
+        // union
+        move |pixel| body(pixel).min(top(pixel)).min(bottom(pixel))
+    }
+}
+
+// Approximate distance to an ellipse centered at the origin
+// https://iquilezles.org/articles/ellipsedist/
+fn ellipse_distance(x: f64, y: f64, rx: f64, ry: f64) -> f64 {
+    if rx <= 0.0 || ry <= 0.0 {
+        return f64::INFINITY;
+    }
+
+    let k0 = ((x / rx).powi(2)        + (y / ry).powi(2))       .sqrt();
+    let k1 = ((x / (rx * rx)).powi(2) + (y / (ry * ry)).powi(2)).sqrt();
+
+    if k1 == 0.0 {
+        return -rx.min(ry);
+    }
+
+    k0 * (k0 - 1.0) / k1
+}
+
+// Distance to a rect centered at the origin, with half dimensions `hw`, `hh` and corner radius `r`
+fn rounded_rect_distance(x: f64, y: f64, hw: f64, hh: f64, r: f64) -> f64 {
+    let qx = x.abs() - (hw - r);
+    let qy = y.abs() - (hh - r);
+
+    let outside = qx.max(0.0).hypot(qy.max(0.0));
+    let inside  = qx.max(qy).min(0.0);
+
+    outside + inside - r
+}
+
+fn polygon_distance(pixel: &PixelLoc, vertices: &[PixelLoc]) -> f64 {
+    let mut dist   = f64::INFINITY;
+    let mut inside = false;
+
+    for i in 0..vertices.len() {
+        let a = &vertices[i];
+        let b = &vertices[(i + 1) % vertices.len()];
+
+        dist = dist.min(segment_distance(pixel, a, b));
+
+        // even-odd crossing test
+        if (a.y > pixel.y) != (b.y > pixel.y) {
+            let cross_x = a.x + (pixel.y - a.y) * (b.x - a.x) / (b.y - a.y);
+
+            if pixel.x < cross_x {
+                inside = !inside;
             }
         }
     }
+
+    if inside { -dist } else { dist }
+}
+
+fn segment_distance(pixel: &PixelLoc, a: &PixelLoc, b: &PixelLoc) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len_sq   = dx * dx + dy * dy;
+
+    let t = if len_sq == 0.0 {
+        0.0
+    } else {
+        (((pixel.x - a.x) * dx + (pixel.y - a.y) * dy) / len_sq).clamp(0.0, 1.0)
+    };
+
+    (pixel.x - (a.x + t * dx)).hypot(pixel.y - (a.y + t * dy))
 }
 
 // https://en.wikipedia.org/wiki/Distance_from_a_point_to_a_line#Line_defined_by_two_points
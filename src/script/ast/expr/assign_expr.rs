@@ -1,22 +1,44 @@
-use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, Variable, WalkArgs}};
+use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, BinaryOperator, CompileArgs, DisplayArgs, Variable, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 
 use super::Expr;
 
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Assign {
-    pub target: Variable,
-    pub value:  Box::<Expr>,
+    pub target:   Variable,
+    pub operator: Token, // `=`, or a compound assignment like `+=`
+    pub value:    Box::<Expr>,
+    pub span:     Span,
 }
 
 
 impl Assign {
-    pub fn new(target: Variable, value: Expr) -> Expr {
+    /// A compound assignment (`x += 1`) is assigned the binary expression it stands for (`x = x + 1`),
+    /// reading the variable like any other
+    pub fn new(target: Variable, operator: Token, value: Expr) -> Expr {
+        let span = target.span.to(value.span());
+
+        let value = match operator.compound_operator() {
+            Some(binary) => BinaryOperator::new(target.clone().as_expr(), binary, value),
+            None         => value,
+        };
+
         Expr::Assign(Self {
             target,
+            operator,
             value: Box::new(value),
+            span,
         })
     }
+
+    /// The value as it was written, the right hand side of a compound assignment
+    pub fn written_value(&self) -> &Expr {
+        match (self.operator.compound_operator(), &*self.value) {
+            (Some(_), Expr::Binary(binary)) => &binary.right,
+            _                               => &self.value,
+        }
+    }
 }
 
 
@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::{Token, TokenType}};
+use crate::script::diagnostic::Span;
 
 use super::Expr;
 
@@ -8,6 +9,7 @@ pub struct BinaryOperator {
     pub operator: Token,
     pub right:    Box<Expr>,
     pub type_:    BinaryOpType,
+    pub span:     Span,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -23,12 +25,22 @@ pub enum BinaryOpType {
     Subtract,
     Multiply,
     Divide,
+    IntDivide,
+    Modulo,
+    Power,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 impl BinaryOperator {
     pub fn new(left: Expr, operator: Token, right: Expr) -> Expr {
         Expr::Binary(Self {
             type_:    get_type(&operator),
+            span:     left.span().to(right.span()),
             left:     Box::new(left),
             operator,
             right:    Box::new(right),
@@ -62,6 +74,12 @@ impl AstNode for BinaryOperator {
     }
 }
 
+impl BinaryOpType {
+    pub fn from_token(token: &Token) -> Self {
+        get_type(token)
+    }
+}
+
 fn get_type(token: &Token) -> BinaryOpType {
     type T = TokenType;
     match token.type_ {
@@ -77,6 +95,15 @@ fn get_type(token: &Token) -> BinaryOpType {
         T::Minus        => BinaryOpType::Subtract,
         T::Star         => BinaryOpType::Multiply,
         T::Slash        => BinaryOpType::Divide,
+        T::TildeSlash   => BinaryOpType::IntDivide,
+        T::Percent      => BinaryOpType::Modulo,
+        T::Star2        => BinaryOpType::Power,
+
+        T::Ampersand      => BinaryOpType::BitAnd,
+        T::Pipe           => BinaryOpType::BitOr,
+        T::Caret          => BinaryOpType::BitXor,
+        T::LessLess       => BinaryOpType::ShiftLeft,
+        T::GreaterGreater => BinaryOpType::ShiftRight,
         _                 => panic!("Unknown token type ({}) for binary operator", token.type_),
     }
 }
@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 
 use super::Expr;
 
@@ -7,6 +8,15 @@ pub struct Call {
     pub callee: Box<Expr>,
     pub paren:  Token,
     pub args:   Box<Vec<Expr>>,
+    pub named:  Vec<NamedArg>,
+    pub span:   Span,
+}
+
+/// `name: value`, an argument passed to the parameter called `name`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct NamedArg {
+    pub name:  Token,
+    pub value: Expr,
 }
 
 
@@ -15,11 +25,14 @@ impl Call {
         callee: Expr,
         paren:  Token,
         args:   Vec<Expr>,
+        named:  Vec<NamedArg>,
     ) -> Expr {
         Expr::Call(Self {
+            span:   callee.span().to(paren.span),
             callee: Box::new(callee),
             paren,
             args:   Box::new(args),
+            named,
         })
     }
 }
@@ -31,7 +44,8 @@ impl AstNode for Call {
             "Callee: ".to_owned(),
         ];
 
-        fields.extend(self.args.iter().map(|_| "Arg:    ".to_owned()));
+        fields.extend(self.args .iter().map(|_|   "Arg:    ".to_owned()));
+        fields.extend(self.named.iter().map(|arg| format!("{}: ", arg.name.lexeme)));
 
         AstDisplay {
             depth:   args.depth,
@@ -48,7 +62,8 @@ impl AstNode for Call {
         let mut results = vec![];
 
         results.push(self.callee.as_ast());
-        results.extend(self.args.iter().map(Expr::as_ast));
+        results.extend(self.args .iter().map(Expr::as_ast));
+        results.extend(self.named.iter().map(|arg| arg.value.as_ast()));
 
         results
     }
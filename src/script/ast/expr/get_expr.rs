@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 
 use super::Expr;
 
@@ -6,12 +7,14 @@ use super::Expr;
 pub struct Get {
     pub expr: Box<Expr>,
     pub name: Token,
+    pub span: Span,
 }
 
 
 impl Get {
     pub fn new(expr: Expr, name: Token) -> Expr {
         Expr::Get(Self {
+            span: expr.span().to(name.span),
             expr: Box::new(expr),
             name,
         })
@@ -1,18 +1,22 @@
 use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Grouping {
-    pub expr: Box<Expr>
+    pub expr: Box<Expr>,
+    pub span: Span,
 }
 
 
 impl Grouping {
     pub fn new(
         expr: Expr,
+        span: Span,
     ) -> Expr {
         Expr::Grouping(Self {
-            expr: Box::new(expr)
+            expr: Box::new(expr),
+            span,
         })
     }
 }
@@ -0,0 +1,40 @@
+use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
+
+use super::Expr;
+
+/// `"Table {name} ({count} rows)"`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Interpolation {
+    /// String literals and the interpolated expressions between them, starting and ending with a literal
+    pub parts: Vec<Expr>,
+    pub span:  Span,
+}
+
+
+impl Interpolation {
+    pub fn new(parts: Vec<Expr>, span: Span) -> Expr {
+        Expr::Interpolation(Self {
+            parts,
+            span,
+        })
+    }
+}
+
+impl AstNode for Interpolation {
+    fn display(&self, args: DisplayArgs) -> AstDisplay {
+        AstDisplay {
+            depth:   args.depth,
+            primary: "Interpolation".to_owned(),
+            labels:  None,
+        }
+    }
+
+    fn compile(&self, _: CompileArgs) -> crate::script::ast::ByteCode {
+        todo!()
+    }
+
+    fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
+        self.parts.iter().map(Expr::as_ast).collect()
+    }
+}
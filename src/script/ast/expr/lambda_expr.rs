@@ -0,0 +1,46 @@
+use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, FunctionStmt, Stmt, WalkArgs};
+use crate::script::diagnostic::Span;
+
+use super::Expr;
+
+/// A function expression, `fun (x) { ... }` or `(x) => expr`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Lambda {
+    pub func:  FunctionStmt,
+    pub arrow: bool,
+    pub span:  Span,
+}
+
+
+impl Lambda {
+    /// The body of an arrow lambda is a single `return` of its expression
+    pub fn new(func: FunctionStmt, arrow: bool) -> Expr {
+        Expr::Lambda(Self {
+            span: func.span,
+            func,
+            arrow,
+        })
+    }
+}
+
+impl AstNode for Lambda {
+    fn display(&self, args: DisplayArgs) -> AstDisplay {
+        let params: Vec<_> = self.func.params.iter()
+            .map(|param| param.name.lexeme.as_str())
+            .collect();
+
+        AstDisplay {
+            depth:   args.depth,
+            primary: format!("Lambda ({})", params.join(", ")),
+            labels:  None,
+        }
+    }
+
+    fn compile(&self, _: CompileArgs) -> crate::script::ast::ByteCode {
+        todo!()
+    }
+
+    fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
+        self.func.body.iter().map(Stmt::as_ast).collect()
+    }
+}
@@ -1,10 +1,12 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::{Token, TokenType}};
+use crate::script::diagnostic::Span;
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Literal {
     pub value: Token,
     pub type_: LiteralType,
+    pub span:  Span,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -20,6 +22,7 @@ impl Literal {
     pub fn new(value: Token) -> Expr {
         Expr::Literal(Self {
             type_: get_type(&value),
+            span:  value.span,
             value,
         })
     }
@@ -49,11 +52,12 @@ impl AstNode for Literal {
 fn get_type(token: &Token) -> LiteralType {
     type T = TokenType;
     match token.type_ {
-        T::Number => LiteralType::Number,
-        T::True   => LiteralType::True,
-        T::False  => LiteralType::False,
-        T::String => LiteralType::String,
-        T::Nil    => LiteralType::Nil,
-        _           => panic!("Unknown token type ({}) for literal", token.type_),
+        T::Number        => LiteralType::Number,
+        T::True          => LiteralType::True,
+        T::False         => LiteralType::False,
+        T::String        => LiteralType::String,
+        T::Interpolation => LiteralType::String,
+        T::Nil           => LiteralType::Nil,
+        _                => panic!("Unknown token type ({}) for literal", token.type_),
     }
 }
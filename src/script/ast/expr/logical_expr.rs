@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::{Token, TokenType}};
+use crate::script::diagnostic::Span;
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -7,18 +8,21 @@ pub struct Logical {
     pub operator: Token,
     pub right:    Box<Expr>,
     pub type_:    LogicalType,
+    pub span:     Span,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum LogicalType {
     And,
     Or,
+    Coalesce, // `??`, the right value if the left is nil
 }
 
 impl Logical {
     pub fn new(left: Expr, operator: Token, right: Expr) -> Expr {
         Expr::Logical(Self {
             type_:    get_type(&operator),
+            span:     left.span().to(right.span()),
             left:     Box::new(left),
             operator,
             right:    Box::new(right),
@@ -57,8 +61,9 @@ impl AstNode for Logical {
 fn get_type(token: &Token) -> LogicalType {
     type Tt = TokenType;
     match token.type_ {
-        Tt::And => LogicalType::And,
-        Tt::Or  => LogicalType::Or,
-        _       => panic!("Unknown token type ({}) for logical operator", token.type_),
+        Tt::And              => LogicalType::And,
+        Tt::Or               => LogicalType::Or,
+        Tt::QuestionQuestion => LogicalType::Coalesce,
+        _                    => panic!("Unknown token type ({}) for logical operator", token.type_),
     }
 }
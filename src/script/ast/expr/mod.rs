@@ -1,49 +1,60 @@
 use crate::script::{ast::{AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}};
 use ast_macro::AstTryFrom;
 
+use crate::script::diagnostic::Span;
+
 mod assign_expr;
 mod binary_expr;
 mod call_expr;
 mod get_expr;
 mod grouping_expr;
+mod interpolation_expr;
+mod lambda_expr;
 mod literal_expr;
 mod logical_expr;
 mod set_expr;
 mod super_expr;
+mod ternary_expr;
 mod this_expr;
 mod unary_expr;
 mod variable_expr;
 
-pub use assign_expr   ::*;
-pub use binary_expr   ::*;
-pub use call_expr     ::*;
-pub use get_expr      ::*;
-pub use grouping_expr ::*;
-pub use literal_expr  ::*;
-pub use logical_expr  ::*;
-pub use set_expr      ::*;
-pub use super_expr    ::*;
-pub use this_expr     ::*;
-pub use unary_expr    ::*;
-pub use variable_expr ::*;
+pub use assign_expr        ::*;
+pub use binary_expr        ::*;
+pub use call_expr          ::*;
+pub use get_expr           ::*;
+pub use grouping_expr      ::*;
+pub use interpolation_expr ::*;
+pub use lambda_expr        ::*;
+pub use literal_expr       ::*;
+pub use logical_expr       ::*;
+pub use set_expr           ::*;
+pub use super_expr         ::*;
+pub use ternary_expr       ::*;
+pub use this_expr          ::*;
+pub use unary_expr         ::*;
+pub use variable_expr      ::*;
 
 use super::AstDisplay;
 
 
 #[derive(Debug, Eq, PartialEq, Clone, AstTryFrom)]
 pub enum Expr {
-    Assign   (Assign),
-    Binary   (BinaryOperator),
-    Call     (Call),
-    Get      (Get),
-    Grouping (Grouping),
-    Literal  (Literal),
-    Logical  (Logical),
-    Set      (Set),
-    Super    (Super),
-    This     (This),
-    Unary    (UnaryOperator),
-    Variable (Variable),
+    Assign        (Assign),
+    Binary        (BinaryOperator),
+    Call          (Call),
+    Get           (Get),
+    Grouping      (Grouping),
+    Interpolation (Interpolation),
+    Lambda        (Lambda),
+    Literal       (Literal),
+    Logical       (Logical),
+    Set           (Set),
+    Super         (Super),
+    Ternary       (Ternary),
+    This          (This),
+    Unary         (UnaryOperator),
+    Variable      (Variable),
 }
 
 impl AstNode for Expr {
@@ -63,18 +74,42 @@ impl AstNode for Expr {
 impl Expr {
     pub fn as_ast(&self) -> Box<&dyn AstNode> {
         match self {
-            Expr::Assign   (expr) => Box::new(expr),
-            Expr::Binary   (expr) => Box::new(expr),
-            Expr::Call     (expr) => Box::new(expr),
-            Expr::Get      (expr) => Box::new(expr),
-            Expr::Grouping (expr) => Box::new(expr),
-            Expr::Literal  (expr) => Box::new(expr),
-            Expr::Logical  (expr) => Box::new(expr),
-            Expr::Set      (expr) => Box::new(expr),
-            Expr::Super    (expr) => Box::new(expr),
-            Expr::This     (expr) => Box::new(expr),
-            Expr::Unary    (expr) => Box::new(expr),
-            Expr::Variable (expr) => Box::new(expr),
+            Expr::Assign        (expr) => Box::new(expr),
+            Expr::Binary        (expr) => Box::new(expr),
+            Expr::Call          (expr) => Box::new(expr),
+            Expr::Get           (expr) => Box::new(expr),
+            Expr::Grouping      (expr) => Box::new(expr),
+            Expr::Interpolation (expr) => Box::new(expr),
+            Expr::Lambda        (expr) => Box::new(expr),
+            Expr::Literal       (expr) => Box::new(expr),
+            Expr::Logical       (expr) => Box::new(expr),
+            Expr::Set           (expr) => Box::new(expr),
+            Expr::Super         (expr) => Box::new(expr),
+            Expr::Ternary       (expr) => Box::new(expr),
+            Expr::This          (expr) => Box::new(expr),
+            Expr::Unary         (expr) => Box::new(expr),
+            Expr::Variable      (expr) => Box::new(expr),
+        }
+    }
+
+    /// The source the expression was parsed from
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Assign        (expr) => expr.span,
+            Expr::Binary        (expr) => expr.span,
+            Expr::Call          (expr) => expr.span,
+            Expr::Get           (expr) => expr.span,
+            Expr::Grouping      (expr) => expr.span,
+            Expr::Interpolation (expr) => expr.span,
+            Expr::Lambda        (expr) => expr.span,
+            Expr::Literal       (expr) => expr.span,
+            Expr::Logical       (expr) => expr.span,
+            Expr::Set           (expr) => expr.span,
+            Expr::Super         (expr) => expr.span,
+            Expr::Ternary       (expr) => expr.span,
+            Expr::This          (expr) => expr.span,
+            Expr::Unary         (expr) => expr.span,
+            Expr::Variable      (expr) => expr.span,
         }
     }
 }
@@ -1,20 +1,30 @@
-use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, BinaryOpType, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Set {
-    pub target: Box<Expr>,
-    pub name:   Token,
-    pub value:  Box<Expr>
+    pub target:   Box<Expr>,
+    pub name:     Token,
+    pub operator: Token, // `=`, or a compound assignment like `+=`
+    pub value:    Box<Expr>,
+    pub span:     Span,
+
+    /// The operator of a compound assignment, which is applied to the property's current value.
+    /// Unlike a variable's, it isn't rewritten as a binary expression, so the target is only evaluated once
+    pub compound: Option<BinaryOpType>,
 }
 
 impl Set {
-    pub fn new(target: Expr, name: Token, value: Expr) -> Expr {
+    pub fn new(target: Expr, name: Token, operator: Token, value: Expr) -> Expr {
         Expr::Set(Self {
-            target: Box::new(target),
+            span:     target.span().to(value.span()),
+            compound: operator.compound_operator().map(|binary| BinaryOpType::from_token(&binary)),
+            target:   Box::new(target),
             name,
-            value: Box::new(value),
+            operator,
+            value:    Box::new(value),
         })
     }
 }
@@ -1,10 +1,12 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Super {
     pub keyword: Token,
     pub method:  Token,
+    pub span:    Span,
 }
 
 
@@ -15,6 +17,7 @@ impl Super {
 
     ) -> Expr {
         Expr::Super(Self {
+            span: keyword.span.to(method.span),
             keyword,
             method,
         })
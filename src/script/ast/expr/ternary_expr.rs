@@ -0,0 +1,51 @@
+use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
+
+use super::Expr;
+
+/// `condition ? then_branch : else_branch`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Ternary {
+    pub condition:   Box<Expr>,
+    pub then_branch: Box<Expr>,
+    pub else_branch: Box<Expr>,
+    pub span:        Span,
+}
+
+
+impl Ternary {
+    pub fn new(condition: Expr, then_branch: Expr, else_branch: Expr) -> Expr {
+        Expr::Ternary(Self {
+            span:        condition.span().to(else_branch.span()),
+            condition:   Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        })
+    }
+}
+
+impl AstNode for Ternary {
+    fn display(&self, args: DisplayArgs) -> AstDisplay {
+        AstDisplay {
+            depth:   args.depth,
+            primary: "Ternary".to_owned(),
+            labels:  Some(vec![
+                "Condition: ".to_owned(),
+                "Then:      ".to_owned(),
+                "Else:      ".to_owned(),
+            ]),
+        }
+    }
+
+    fn compile(&self, _: CompileArgs) -> crate::script::ast::ByteCode {
+        todo!()
+    }
+
+    fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
+        vec![
+            self.condition  .as_ast(),
+            self.then_branch.as_ast(),
+            self.else_branch.as_ast(),
+        ]
+    }
+}
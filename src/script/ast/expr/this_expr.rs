@@ -1,9 +1,11 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct This {
     pub keyword: Token,
+    pub span:    Span,
 }
 
 
@@ -12,6 +14,7 @@ impl This {
         keyword: Token,
     ) -> Expr {
         Expr::This(Self {
+            span: keyword.span,
             keyword,
         })
     }
@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::{Token, TokenType}};
+use crate::script::diagnostic::Span;
 
 use super::Expr;
 
@@ -7,6 +8,7 @@ pub struct UnaryOperator {
     pub operator: Token,
     pub right:    Box<Expr>,
     pub type_:    UnaryOpType,
+    pub span:     Span,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -20,6 +22,7 @@ impl UnaryOperator {
     pub fn new(operator: Token, right: Expr) -> Expr {
         Expr::Unary(Self {
             type_: get_type(&operator),
+            span:  operator.span.to(right.span()),
             operator,
             right: Box::new(right),
         })
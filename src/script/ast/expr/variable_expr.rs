@@ -1,16 +1,19 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, VarType, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 use super::Expr;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Variable {
     pub name:     Token,
     pub var_type: VarType,
+    pub span:     Span,
 }
 
 
 impl Variable {
     pub fn new(name: Token) -> Variable {
         Self {
+            span:     name.span,
             name,
             var_type: VarType::Global,
         }
@@ -1,4 +1,5 @@
 use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
 use super::*;
 
 
@@ -6,13 +7,15 @@ use super::*;
 pub struct Block {
     pub stmts:  Box::<Vec<Stmt>>,
     pub locals: usize,
+    pub span:   Span,
 }
 
 impl Block {
-    pub fn new(stmts: Vec<Stmt>) -> Stmt {
+    pub fn new(stmts: Vec<Stmt>, span: Span) -> Stmt {
         Stmt::Block(Block {
             stmts:  Box::new(stmts),
             locals: 0,
+            span,
         })
     }
 }
@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, VarDeclType, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 use crate::script::{ast::Variable};
 
 use super::{FunctionStmt, Stmt};
@@ -9,19 +10,22 @@ pub struct Class {
     pub superclass: Option<Variable>,
     pub methods:    Box<Vec<FunctionStmt>>,
     pub var_type:   VarDeclType,
+    pub span:       Span,
 }
 
 impl Class {
     pub fn new(
         name:       Token,
         superclass: Option<Variable>,
-        methods:    Vec<FunctionStmt>
+        methods:    Vec<FunctionStmt>,
+        span:       Span,
     ) -> Stmt {
         Stmt::Class(Self {
             name,
             superclass,
             methods:  Box::new(methods),
             var_type: VarDeclType::Global,
+            span,
         })
     }
 }
@@ -30,12 +34,18 @@ impl AstNode for Class {
     fn display(&self, args: DisplayArgs) -> AstDisplay {
         let msg = format!("Class ({})", self.name.lexeme);
 
+        let mut labels: Vec<String> = vec![];
+
+        if self.superclass.is_some() {
+            labels.push("Superclass: ".to_owned());
+        }
+
+        labels.extend(self.methods.iter().map(|_| "Method: ".to_owned()));
+
         AstDisplay {
             depth:   args.depth,
             primary: msg,
-            labels:  Some(
-                self.methods.iter().map(|_| "Method: ".to_owned()).collect()
-            ),
+            labels:  Some(labels),
         }
     }
 
@@ -1,4 +1,5 @@
 use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
 use crate::script::ast::Expr;
 
 use super::Stmt;
@@ -6,12 +7,14 @@ use super::Stmt;
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ExpressionStmt {
     pub expr: Expr,
+    pub span: Span,
 }
 
 impl ExpressionStmt {
-    pub fn new(expr: Expr) -> Stmt {
+    pub fn new(expr: Expr, span: Span) -> Stmt {
         Stmt::Expression(Self {
             expr,
+            span,
         })
     }
 }
@@ -1,4 +1,6 @@
-use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, VarDeclType, WalkArgs}, tokens::Token};
+use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, VarDeclType, WalkArgs}, tokens::Token, vm::chunk::Capture};
+use crate::script::diagnostic::Span;
+use crate::script::ast::Expr;
 
 use super::Stmt;
 
@@ -10,35 +12,57 @@ pub struct FunctionStmt {
     pub locals:    usize,
     pub body:      Box<Vec<Stmt>>,
     pub var_type:  VarDeclType,
+    pub span:      Span,
+
+    // the reserved first stack slot, holding the closure being called
+    pub slot_type: VarDeclType,
+    pub captures:  Vec<Capture>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct FunctionParam {
     pub name:     Token,
     pub var_type: VarDeclType,
+
+    /// Evaluated when the caller doesn't pass the parameter
+    pub default:  Option<Expr>,
+
+    /// `...rest`, the arguments after the other parameters as a list
+    pub rest:     bool,
 }
 
 impl FunctionStmt {
     pub fn new(
         name:   Token,
-        params: Vec<Token>,
+        params: Vec<FunctionParam>,
         body:   Vec<Stmt>,
+        span:   Span,
     ) -> Self {
         Self {
             name,
-            params:   params.into_iter().map(|p| FunctionParam::new(p)).collect(),
-            body:     Box::new(body),
-            var_type: VarDeclType::Global,
-            locals:   0,
+            params,
+            body:      Box::new(body),
+            var_type:  VarDeclType::Global,
+            locals:    0,
+            span,
+            slot_type: VarDeclType::Local,
+            captures:  vec![],
         }
     }
+
+    /// Whether the last parameter collects the rest of the arguments
+    pub fn has_rest(&self) -> bool {
+        self.params.last().is_some_and(|param| param.rest)
+    }
 }
 
 impl FunctionParam {
-    fn new(name: Token) -> Self {
+    pub fn new(name: Token, default: Option<Expr>, rest: bool) -> Self {
         Self {
             name,
             var_type: VarDeclType::Local,
+            default,
+            rest,
         }
     }
 }
@@ -59,6 +83,10 @@ impl AstNode for FunctionStmt {
     }
 
     fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
-        self.body.iter().map(Stmt::as_ast).collect()
+        let defaults = self.params.iter().filter_map(|param| param.default.as_ref());
+
+        defaults.map(Expr::as_ast)
+            .chain(self.body.iter().map(Stmt::as_ast))
+            .collect()
     }
 }
@@ -1,4 +1,5 @@
 use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
 use crate::script::ast::Expr;
 
 use super::Stmt;
@@ -8,18 +9,21 @@ pub struct IfStmt {
     pub condition:   Expr,
     pub then_branch: Box<Stmt>,
     pub else_branch: Option<Box<Stmt>>,
+    pub span:        Span,
 }
 
 impl IfStmt {
     pub fn new(
         condition:   Expr,
         then_branch: Stmt,
-        else_branch: Option<Stmt>
+        else_branch: Option<Stmt>,
+        span:        Span,
     ) -> Stmt {
         Stmt::If(Self {
             condition,
             then_branch: Box::new(then_branch),
             else_branch: else_branch.map(|x| Box::new(x)),
+            span,
         })
 
     }
@@ -0,0 +1,57 @@
+use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
+use crate::script::vm::chunk::ModuleIndex;
+
+use super::Stmt;
+
+
+/// `import "path" as name;` or `import { a, b } from "path";`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImportStmt {
+    pub path:   Token,          // the string literal, relative to the importing file
+    pub alias:  Option<Token>,
+    pub names:  Vec<Token>,
+
+    /// Set when the module is loaded
+    pub module: Option<ModuleIndex>,
+    pub span:   Span,
+}
+
+impl ImportStmt {
+    pub fn new(path: Token, alias: Option<Token>, names: Vec<Token>, span: Span) -> Stmt {
+        Stmt::Import(Self {
+            path,
+            alias,
+            names,
+            module: None,
+            span,
+        })
+    }
+}
+
+impl AstNode for ImportStmt {
+    fn display(&self, args: DisplayArgs) -> AstDisplay {
+        let names: Vec<_> = self.names.iter()
+            .map(|name| name.lexeme.as_str())
+            .collect();
+
+        let msg = match &self.alias {
+            Some(alias) => format!("Import ({:?} as {})",  self.path.lexeme, alias.lexeme),
+            None        => format!("Import ({:?}: {})",    self.path.lexeme, names.join(", ")),
+        };
+
+        AstDisplay {
+            depth:   args.depth,
+            primary: msg,
+            labels:  None,
+        }
+    }
+
+    fn compile(&self, _: CompileArgs) -> crate::script::ast::ByteCode {
+        todo!()
+    }
+
+    fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
+        vec![]
+    }
+}
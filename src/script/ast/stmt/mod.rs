@@ -6,19 +6,26 @@ mod function_stmt;
 mod block_stmt;
 mod class_stmt;
 mod if_stmt;
+mod import_stmt;
 mod print_stmt;
 mod return_stmt;
+mod throw_stmt;
+mod try_stmt;
 mod var_stmt;
 mod while_stmt;
 
 use ast_macro::AstTryFrom;
+use crate::script::diagnostic::Span;
 pub use expr_stmt     ::*;
 pub use function_stmt ::*;
 pub use block_stmt    ::*;
 pub use class_stmt    ::*;
 pub use if_stmt       ::*;
+pub use import_stmt   ::*;
 pub use print_stmt    ::*;
 pub use return_stmt   ::*;
+pub use throw_stmt    ::*;
+pub use try_stmt      ::*;
 pub use var_stmt      ::*;
 pub use while_stmt    ::*;
 
@@ -31,8 +38,11 @@ pub enum Stmt {
     Expression(ExpressionStmt),
     Function  (FunctionStmt),
     If        (IfStmt),
+    Import    (ImportStmt),
     Print     (PrintStmt),
     Return    (ReturnStmt),
+    Throw     (ThrowStmt),
+    Try       (TryStmt),
     Var       (VarStmt),
     While     (WhileStmt),
 }
@@ -59,10 +69,31 @@ impl Stmt {
             Stmt::Expression (stmt) => Box::new(stmt),
             Stmt::Function   (stmt) => Box::new(stmt),
             Stmt::If         (stmt) => Box::new(stmt),
+            Stmt::Import     (stmt) => Box::new(stmt),
             Stmt::Print      (stmt) => Box::new(stmt),
             Stmt::Return     (stmt) => Box::new(stmt),
+            Stmt::Throw      (stmt) => Box::new(stmt),
+            Stmt::Try        (stmt) => Box::new(stmt),
             Stmt::Var        (stmt) => Box::new(stmt),
             Stmt::While      (stmt) => Box::new(stmt),
         }
     }
+
+    /// The source the statement was parsed from
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block      (stmt) => stmt.span,
+            Stmt::Class      (stmt) => stmt.span,
+            Stmt::Expression (stmt) => stmt.span,
+            Stmt::Function   (stmt) => stmt.span,
+            Stmt::If         (stmt) => stmt.span,
+            Stmt::Import     (stmt) => stmt.span,
+            Stmt::Print      (stmt) => stmt.span,
+            Stmt::Return     (stmt) => stmt.span,
+            Stmt::Throw      (stmt) => stmt.span,
+            Stmt::Try        (stmt) => stmt.span,
+            Stmt::Var        (stmt) => stmt.span,
+            Stmt::While      (stmt) => stmt.span,
+        }
+    }
 }
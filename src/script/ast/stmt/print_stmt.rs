@@ -1,4 +1,5 @@
 use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
 use crate::script::ast::Expr;
 
 use super::Stmt;
@@ -7,12 +8,14 @@ use super::Stmt;
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct PrintStmt {
     pub expr: Expr,
+    pub span: Span,
 }
 
 impl PrintStmt {
-    pub fn new(expr: Expr) -> Stmt {
+    pub fn new(expr: Expr, span: Span) -> Stmt {
         Stmt::Print(Self {
             expr,
+            span,
         })
     }
 }
@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 use crate::script::{ast::Expr};
 
 use super::Stmt;
@@ -8,13 +9,15 @@ use super::Stmt;
 pub struct ReturnStmt {
     pub keyword: Token,
     pub value:   Option<Expr>,
+    pub span:    Span,
 }
 
 impl ReturnStmt {
-    pub fn new(keyword: Token, value: Option<Expr>) -> Stmt {
+    pub fn new(keyword: Token, value: Option<Expr>, span: Span) -> Stmt {
         Stmt::Return(Self {
             keyword,
             value,
+            span,
         })
     }
 }
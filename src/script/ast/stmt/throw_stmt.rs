@@ -0,0 +1,41 @@
+use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
+use crate::script::ast::Expr;
+
+use super::Stmt;
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ThrowStmt {
+    pub keyword: Token,
+    pub value:   Expr,
+    pub span:    Span,
+}
+
+impl ThrowStmt {
+    pub fn new(keyword: Token, value: Expr, span: Span) -> Stmt {
+        Stmt::Throw(Self {
+            keyword,
+            value,
+            span,
+        })
+    }
+}
+
+impl AstNode for ThrowStmt {
+    fn display(&self, args: DisplayArgs) -> AstDisplay {
+        AstDisplay {
+            depth:   args.depth,
+            primary: "Throw Stmt".to_owned(),
+            labels:  None,
+        }
+    }
+
+    fn compile(&self, _: CompileArgs) -> crate::script::ast::ByteCode {
+        todo!()
+    }
+
+    fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
+        vec![self.value.as_ast()]
+    }
+}
@@ -0,0 +1,51 @@
+use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, VarDeclType, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
+
+use super::{Block, Stmt};
+
+
+/// `try { ... } catch (name) { ... }`, the handler runs with `name` holding the thrown value
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TryStmt {
+    pub body:     Block,
+    pub name:     Token,
+    pub var_type: VarDeclType,
+    pub handler:  Block,
+    pub span:     Span,
+}
+
+impl TryStmt {
+    pub fn new(body: Block, name: Token, handler: Block, span: Span) -> Stmt {
+        Stmt::Try(Self {
+            body,
+            name,
+            var_type: VarDeclType::Local,
+            handler,
+            span,
+        })
+    }
+}
+
+impl AstNode for TryStmt {
+    fn display(&self, args: DisplayArgs) -> AstDisplay {
+        AstDisplay {
+            depth:   args.depth,
+            primary: format!("Try Stmt ({})", self.name.lexeme),
+            labels:  Some(vec![
+                "Body:    ".to_owned(),
+                "Handler: ".to_owned(),
+            ]),
+        }
+    }
+
+    fn compile(&self, _: CompileArgs) -> crate::script::ast::ByteCode {
+        todo!()
+    }
+
+    fn walk   (&self, _: WalkArgs)    -> AstNodeList<'_> {
+        vec![
+            Box::new(&self.body),
+            Box::new(&self.handler),
+        ]
+    }
+}
@@ -1,4 +1,5 @@
 use crate::script::{ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, VarDeclType, WalkArgs}, tokens::Token};
+use crate::script::diagnostic::Span;
 use crate::script::{ast::Expr};
 
 use super::Stmt;
@@ -8,15 +9,17 @@ pub struct VarStmt {
     pub name:        Token,
     pub initializer: Option<Expr>,
     pub var_type:    VarDeclType,
+    pub span:        Span,
 }
 
 
 impl VarStmt {
-    pub fn new(name: Token, initializer: Option<Expr>) -> Stmt {
+    pub fn new(name: Token, initializer: Option<Expr>, span: Span) -> Stmt {
         Stmt::Var(VarStmt {
             name,
             initializer,
             var_type: VarDeclType::Global,
+            span,
         })
     }
 }
@@ -1,4 +1,5 @@
 use crate::script::ast::{AstDisplay, AstNode, AstNodeList, CompileArgs, DisplayArgs, WalkArgs};
+use crate::script::diagnostic::Span;
 use crate::script::ast::Expr;
 
 use super::Stmt;
@@ -7,13 +8,15 @@ use super::Stmt;
 pub struct WhileStmt {
     pub condition: Expr,
     pub body:      Box<Stmt>,
+    pub span:      Span,
 }
 
 impl WhileStmt {
-    pub fn new(condition: Expr, body: Stmt) -> Stmt {
+    pub fn new(condition: Expr, body: Stmt, span: Span) -> Stmt {
         Stmt::While(Self {
             condition,
             body: Box::new(body),
+            span,
         })
     }
 }
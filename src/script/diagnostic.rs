@@ -0,0 +1,332 @@
+use std::{fmt::{Display, Write}, path::PathBuf};
+
+
+/// A message about a script, optionally pointing at the offending source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file:     String,
+    pub span:     Option<Span>,
+    pub severity: Severity,
+    pub message:  String,
+    pub notes:    Vec<String>,
+}
+
+/// Byte offsets into the source, `end` being exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end:   usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+/// A batch of diagnostics, along with the source they refer to
+#[derive(Debug, Clone)]
+pub struct DiagnosticReport {
+    pub source:      String,
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// The file and source of diagnostics in other files, such as imported scripts
+    pub sources:     Vec<(String, String)>,
+
+    /// The files the run read, or tried to, including the script. A change to any of them can change the result
+    pub files:       Vec<PathBuf>,
+}
+
+
+impl Diagnostic {
+    pub fn new(severity: Severity, file: &str, message: String) -> Self {
+        Self {
+            file:     file.to_owned(),
+            span:     None,
+            severity,
+            message,
+            notes:    vec![],
+        }
+    }
+
+    pub fn error(file: &str, message: String) -> Self {
+        Self::new(Severity::Error, file, message)
+    }
+
+    pub fn warning(file: &str, message: String) -> Self {
+        Self::new(Severity::Warning, file, message)
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Renders the diagnostic, underlining the span in the line of `source` it starts on
+    ///
+    /// ```text
+    /// error: Expect ';' after expression
+    ///  --> test.lox:3:10
+    ///   |
+    /// 3 | print x +
+    ///   |          ^
+    ///   = note: ...
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "{}: {}", self.severity, self.message).unwrap();
+
+        let Some(span) = self.span else {
+            writeln!(out, " --> {}", self.file).unwrap();
+
+            for note in self.notes.iter() {
+                writeln!(out, "  = note: {}", note).unwrap();
+            }
+
+            return out;
+        };
+
+        let start        = span.start.min(source.len());
+        let (line, col)  = line_col(source, start);
+        let line_range   = line_range(source, start);
+        let text         = &source[line_range.start .. line_range.end];
+
+        // underline up to the end of the first line of the span, and at least one character
+        // (a span can start on the line break, past the end of the text of a CRLF line)
+        let underline_end = span.end.min(line_range.end).max(start);
+        let underline_len = source[start .. underline_end].chars().count().max(1);
+
+        let gutter = line.to_string().len();
+        let pad    = " ".repeat(gutter);
+
+        writeln!(out, "{pad}--> {}:{}:{}", self.file, line, col).unwrap();
+        writeln!(out, "{pad} |").unwrap();
+        writeln!(out, "{line} | {}", text.replace('\t', " ")).unwrap();
+        writeln!(out, "{pad} | {}{}", " ".repeat(col - 1), "^".repeat(underline_len)).unwrap();
+
+        for note in self.notes.iter() {
+            writeln!(out, "{pad} = note: {}", note).unwrap();
+        }
+
+        out
+    }
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    /// The whole of `line` (1 based), excluding the line break
+    pub fn line(source: &str, line: usize) -> Self {
+        let start = line_offset(source, line);
+        let range = line_range(source, start);
+
+        Self::new(range.start, range.end)
+    }
+
+    /// The `len` characters ending at `col` (the number of characters from the start of the line), on `line`
+    pub fn line_col(source: &str, line: usize, col: usize, len: usize) -> Self {
+        let line_start = line_offset(source, line);
+        let text       = &source[line_start .. line_range(source, line_start).end];
+
+        let byte_at = |col: usize| {
+            text.char_indices()
+                .nth(col)
+                .map(|(i, _)| i)
+                .unwrap_or(text.len())
+            + line_start
+        };
+
+        Self::new(byte_at(col.saturating_sub(len)), byte_at(col))
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl DiagnosticReport {
+    pub fn new(source: String, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            source,
+            diagnostics,
+            sources: vec![],
+            files:   vec![],
+        }
+    }
+
+    pub fn with_sources(mut self, sources: Vec<(String, String)>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// The source a diagnostic points into
+    pub fn source_of(&self, diagnostic: &Diagnostic) -> &str {
+        self.sources.iter()
+            .find(|(file, _)| *file == diagnostic.file)
+            .map_or(&self.source, |(_, source)| source)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+}
+
+
+/// The line and column (both 1 based, column in characters) of a byte offset
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+
+    let line       = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let col        = source[line_start..offset].chars().count() + 1;
+
+    (line, col)
+}
+
+// the byte offset of the start of a (1 based) line
+fn line_offset(source: &str, line: usize) -> usize {
+    if line <= 1 {
+        return 0;
+    }
+
+    source.match_indices('\n')
+        .nth(line - 2)
+        .map(|(i, _)| i + 1)
+        .unwrap_or(source.len())
+}
+
+// the line containing the byte offset, excluding the line break
+fn line_range(source: &str, offset: usize) -> std::ops::Range<usize> {
+    let start = source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let end   = source[offset..].find('\n').map(|i| i + offset).unwrap_or(source.len());
+
+    let end = match source[start..end].ends_with('\r') {
+        true  => end - 1,
+        false => end,
+    };
+
+    start..end
+}
+
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Severity::Note    => "note",
+            Severity::Warning => "warning",
+            Severity::Error   => "error",
+        })
+    }
+}
+
+impl Display for DiagnosticReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in self.diagnostics.iter() {
+            writeln!(f, "{}", diagnostic.render(self.source_of(diagnostic)))?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::multi_line;
+
+    use super::*;
+
+    #[test]
+    fn render() {
+        let source = multi_line!(
+            "var a = 1;",
+            "print a +;",
+        );
+
+        let diagnostic = Diagnostic::error("test.lox", "Expect expression".to_owned())
+            .with_span(Span::new(20, 21))
+            .with_note("found ';'".to_owned())
+        ;
+
+        let expected = multi_line!(
+            "error: Expect expression",
+            " --> test.lox:2:10",
+            "  |",
+            "2 | print a +;",
+            "  |          ^",
+            "  = note: found ';'",
+            "",
+        );
+
+        assert_eq!(diagnostic.render(&source), expected);
+    }
+
+    #[test]
+    fn render_crlf() {
+        let source = "print 1 +\r\n";
+
+        // at the `\n`, as for an error at the end of the file
+        let diagnostic = Diagnostic::error("test.lox", "Expect expression".to_owned())
+            .with_span(Span::new(10, 11));
+
+        assert_eq!(diagnostic.render(source), multi_line!(
+            "error: Expect expression",
+            " --> test.lox:1:11",
+            "  |",
+            "1 | print 1 +",
+            "  |           ^",
+            "",
+        ));
+
+        // at the `\r`
+        let diagnostic = Diagnostic::error("test.lox", "Expect expression".to_owned())
+            .with_span(Span::new(9, 11));
+
+        assert!(diagnostic.render(source).ends_with("1 | print 1 +\n  |          ^\n"));
+    }
+
+    #[test]
+    fn render_without_span() {
+        let diagnostic = Diagnostic::error("test.lox", "Unable to read source file".to_owned());
+
+        assert_eq!(diagnostic.render(""), "error: Unable to read source file\n --> test.lox\n");
+    }
+
+    #[test]
+    fn spans() {
+        let source = multi_line!(
+            "var a = 1;",
+            "  print \"é\" + b;",
+        );
+
+        assert_eq!(Span::line(&source, 1), Span::new(0, 10));
+        assert_eq!(Span::line(&source, 2), Span::new(11, 28));
+
+        // `b`, which ends at column 15 (in characters)
+        let span = Span::line_col(&source, 2, 15, 1);
+        assert_eq!(&source[span.start .. span.end], "b");
+
+        assert_eq!(line_col(&source, span.start), (2, 15));
+    }
+}
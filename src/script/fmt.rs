@@ -0,0 +1,678 @@
+use std::{fs, path::Path, process};
+
+use crate::script::{
+    ast::{Class, Expr, FunctionStmt, IfStmt, LiteralType, ReturnStmt, Stmt, TryStmt, WhileStmt},
+    diagnostic::Span,
+    parser::parse_ast,
+    scanner::{scan_comments, scan_tokens, Comment},
+    RunError,
+};
+
+
+static INDENT: &str = "    ";
+
+type Re = RunError;
+
+/// Prints an ast back out as source, in the canonical style
+///
+/// Comments are kept, a comment inside an expression is moved to the line after its statement,
+/// and runs of blank lines between statements are collapsed into one
+struct Formatter<'a> {
+    source:   &'a str,
+    comments: Vec<Comment>,
+    next:     usize,         // the first comment not printed yet
+    out:      String,
+    depth:    usize,
+    last_end: Option<usize>, // the end of the last statement or comment printed in the current block
+}
+
+/// A `for` loop, which the parser desugars into a `while`
+struct ForLoop<'a> {
+    init:      Option<&'a Stmt>,
+    condition: Option<&'a Expr>,
+    increment: Option<&'a Expr>,
+    body:      &'a Stmt,
+}
+
+
+/// Formats the script files in `args`, or checks that they're formatted with `--check`, then exits
+///
+/// Exits with 1 if a file couldn't be read or parsed, or with `--check` if a file isn't formatted
+pub fn run_fmt(args: &[String]) -> ! {
+
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<_> = args.iter()
+        .filter(|arg| *arg != "--check")
+        .map(Path::new)
+        .collect();
+
+    let mut ok = true;
+
+    for path in paths {
+        let file   = path.display().to_string();
+        let source = fs::read_to_string(path);
+
+        let Ok(source) = source else {
+            eprint!("{}", Re::IOError.report(&file, String::new()));
+            ok = false;
+            continue;
+        };
+
+        let formatted = match format_source(&source) {
+            Ok (formatted) => formatted,
+            Err(err)       => {
+                eprint!("{}", err.report(&file, source));
+                ok = false;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("{} is not formatted", file);
+            ok = false;
+        }
+        else if let Err(err) = fs::write(path, formatted) {
+            eprintln!("Unable to write {}: {}", file, err);
+            ok = false;
+        }
+        else {
+            println!("formatted {}", file);
+        }
+    }
+
+    process::exit(if ok { 0 } else { 1 })
+}
+
+/// The canonical formatting of `source`, which must scan and parse
+pub fn format_source(source: &str) -> Result<String, RunError> {
+
+    let tokens = scan_tokens(source).map_err(Re::ScannerError)?;
+    let ast    = parse_ast(tokens)   .map_err(Re::ParserError)?;
+
+    let mut formatter = Formatter {
+        source,
+        comments: scan_comments(source),
+        next:     0,
+        out:      String::new(),
+        depth:    0,
+        last_end: None,
+    };
+
+    for stmt in &ast.stmts {
+        formatter.stmt(stmt);
+    }
+    formatter.comments_before(usize::MAX);
+
+    Ok(formatter.out)
+}
+
+impl Formatter<'_> {
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        let span = stmt.span();
+
+        self.comments_before(span.start);
+        self.separate(span.start);
+
+        if let Some(for_loop) = self.for_loop(stmt) {
+            let head = self.for_head(&for_loop);
+            self.clause(head, for_loop.body);
+        }
+        else if let Some(line) = self.simple(stmt) {
+            self.line(&line);
+        }
+        else {
+            match stmt {
+                Stmt::Block   (block) => self.block("", &block.stmts, block.span),
+                Stmt::If      (if_)   => self.if_stmt("", if_),
+                Stmt::While   (while_) => {
+                    let head = format!("while ({})", self.expr(&while_.condition));
+                    self.clause(head, &while_.body);
+                }
+                Stmt::Function(func)  => self.function("fun ", func),
+                Stmt::Class   (class) => self.class(class),
+                Stmt::Try     (try_)  => self.try_stmt(try_),
+
+                _ => unreachable!("simple statements are printed on one line"),
+            }
+        }
+
+        self.last_end = Some(span.end);
+        self.trailing_comment(span.end);
+    }
+
+    // a statement that fits on one line
+    fn simple(&self, stmt: &Stmt) -> Option<String> {
+        let line = match stmt {
+            Stmt::Expression(stmt) => format!("{};", self.expr(&stmt.expr)),
+            Stmt::Print     (stmt) => format!("print {};", self.expr(&stmt.expr)),
+            Stmt::Throw     (stmt) => format!("throw {};", self.expr(&stmt.value)),
+
+            Stmt::Return(stmt) => match &stmt.value {
+                Some(value) => format!("return {};", self.expr(value)),
+                None        => "return;".to_owned(),
+            },
+
+            Stmt::Var(stmt) => match &stmt.initializer {
+                Some(value) => format!("var {} = {};", stmt.name.lexeme, self.expr(value)),
+                None        => format!("var {};", stmt.name.lexeme),
+            },
+
+            Stmt::Import(stmt) => {
+                let path = &self.source[stmt.path.span.start..stmt.path.span.end];
+
+                match &stmt.alias {
+                    Some(alias) => format!("import {} as {};", path, alias.lexeme),
+                    None        => {
+                        let names: Vec<_> = stmt.names.iter().map(|name| name.lexeme.as_str()).collect();
+
+                        format!("import {{ {} }} from {};", names.join(", "), path)
+                    }
+                }
+            }
+
+            _ => return None,
+        };
+
+        Some(line)
+    }
+
+    /// `head` followed by a body, a block or simple statement goes on the same line as `head`
+    ///
+    /// Returns whether the body was a block
+    fn clause(&mut self, head: String, body: &Stmt) -> bool {
+
+        if let (Stmt::Block(block), None) = (body, self.for_loop(body)) {
+            self.block(&head, &block.stmts, block.span);
+            return true;
+        }
+
+        match self.simple(body) {
+            Some(line) => self.line(&format!("{} {}", head, line)),
+            None       => {
+                self.line(&head);
+
+                self.depth   += 1;
+                self.last_end = None;
+                self.stmt(body);
+                self.depth   -= 1;
+            }
+        }
+
+        false
+    }
+
+    fn block(&mut self, head: &str, stmts: &[Stmt], span: Span) {
+        let open = match head.is_empty() {
+            true  => "{".to_owned(),
+            false => format!("{} {{", head),
+        };
+
+        let has_comments = self.comments.get(self.next).is_some_and(|comment| comment.span.start < span.end);
+
+        if stmts.is_empty() && !has_comments {
+            self.line(&format!("{}}}", open));
+            return;
+        }
+
+        self.line(&open);
+
+        self.depth   += 1;
+        self.last_end = None;
+
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+        self.comments_before(span.end);
+
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn if_stmt(&mut self, prefix: &str, if_: &IfStmt) {
+        let head  = format!("{}if ({})", prefix, self.expr(&if_.condition));
+        let block = self.clause(head, &if_.then_branch);
+
+        let Some(else_branch) = &if_.else_branch else {
+            return;
+        };
+
+        // `} else` when the then branch was a block, otherwise `else` on its own line
+        let prefix = match block {
+            true  => {
+                self.reopen();
+                "} else"
+            }
+            false => "else",
+        };
+
+        match else_branch.as_ref() {
+            Stmt::If(if_) => self.if_stmt(&format!("{} ", prefix), if_),
+            else_branch   => { self.clause(prefix.to_owned(), else_branch); }
+        }
+    }
+
+    // `} catch (name) {` continues the line that closes the try block, which may be `try {}`
+    fn try_stmt(&mut self, try_: &TryStmt) {
+        self.block("try", &try_.body.stmts, try_.body.span);
+
+        self.out.pop();
+        let start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        let last  = self.out.split_off(start);
+
+        let head = format!("{} catch ({})", last.trim(), try_.name.lexeme);
+        self.block(&head, &try_.handler.stmts, try_.handler.span);
+    }
+
+    fn function(&mut self, prefix: &str, func: &FunctionStmt) {
+        let head = format!("{}{}({})", prefix, func.name.lexeme, self.params(func));
+        self.block(&head, &func.body, func.span);
+    }
+
+    // a function expression's body is printed by a formatter of its own, continuing the current line
+    fn lambda(&self, func: &FunctionStmt) -> String {
+        let mut formatter = Formatter {
+            source:   self.source,
+            comments: vec![],
+            next:     0,
+            out:      String::new(),
+            depth:    self.depth,
+            last_end: None,
+        };
+
+        formatter.block(&format!("fun ({})", self.params(func)), &func.body, func.span);
+        formatter.out.trim().to_owned()
+    }
+
+    fn params(&self, func: &FunctionStmt) -> String {
+        let params: Vec<_> = func.params.iter()
+            .map(|param| match (&param.default, param.rest) {
+                (Some(default), _) => format!("{} = {}", param.name.lexeme, self.expr(default)),
+                (None,       true) => format!("...{}", param.name.lexeme),
+                (None,      false) => param.name.lexeme.clone(),
+            })
+            .collect();
+
+        params.join(", ")
+    }
+
+    fn class(&mut self, class: &Class) {
+        let head = match &class.superclass {
+            Some(superclass) => format!("class {} < {}", class.name.lexeme, superclass.name.lexeme),
+            None             => format!("class {}", class.name.lexeme),
+        };
+
+        let has_comments = self.comments.get(self.next).is_some_and(|comment| comment.span.start < class.span.end);
+
+        if class.methods.is_empty() && !has_comments {
+            self.line(&format!("{} {{}}", head));
+            return;
+        }
+
+        self.line(&format!("{} {{", head));
+
+        self.depth   += 1;
+        self.last_end = None;
+
+        for method in class.methods.iter() {
+            self.comments_before(method.span.start);
+            self.separate(method.span.start);
+
+            self.function("", method);
+
+            self.last_end = Some(method.span.end);
+            self.trailing_comment(method.span.end);
+        }
+        self.comments_before(class.span.end);
+
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    /// Recognizes the statements the parser desugars a `for` loop into,
+    /// `{ init; while (condition) { body; increment; } }`
+    fn for_loop<'s>(&self, stmt: &'s Stmt) -> Option<ForLoop<'s>> {
+
+        let (init, while_) = match stmt {
+            Stmt::Block(block) if self.is_for(block.span) => match block.stmts.as_slice() {
+                [init, Stmt::While(while_)] if while_.span == block.span => (Some(init), while_),
+                _                                                         => return None,
+            },
+            Stmt::While(while_) if self.is_for(while_.span) => (None, while_),
+            _                                                => return None,
+        };
+
+        Some(ForLoop {
+            init,
+            condition: Self::for_condition(while_),
+            increment: Self::for_increment(while_),
+            body:      match while_.body.as_ref() {
+                Stmt::Block(block) if block.span == while_.span && Self::for_increment(while_).is_some() => &block.stmts[0],
+                body                                                                                   => body,
+            },
+        })
+    }
+
+    // a missing condition is replaced by `true`, with the span of the `for` keyword
+    fn for_condition(while_: &WhileStmt) -> Option<&Expr> {
+        match &while_.condition {
+            Expr::Literal(literal) if literal.type_ == LiteralType::True && literal.value.span.start == while_.span.start => None,
+            condition                                                                                                  => Some(condition),
+        }
+    }
+
+    // the increment is appended to the body, in a block with the span of the whole loop
+    fn for_increment(while_: &WhileStmt) -> Option<&Expr> {
+        match while_.body.as_ref() {
+            Stmt::Block(block) if block.span == while_.span => match block.stmts.as_slice() {
+                [_, Stmt::Expression(increment)] => Some(&increment.expr),
+                _                                => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn is_for(&self, span: Span) -> bool {
+        self.source[span.start..].starts_with("for")
+    }
+
+    fn for_head(&self, for_loop: &ForLoop) -> String {
+        let mut head = "for (".to_owned();
+
+        match for_loop.init.and_then(|init| self.simple(init)) {
+            Some(init) => head.push_str(&init),
+            None       => head.push(';'),
+        }
+
+        if let Some(condition) = for_loop.condition {
+            head.push_str(&format!(" {}", self.expr(condition)));
+        }
+        head.push(';');
+
+        if let Some(increment) = for_loop.increment {
+            head.push_str(&format!(" {}", self.expr(increment)));
+        }
+
+        head + ")"
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::Assign  (expr) => format!("{} {} {}", expr.target.name.lexeme, expr.operator.lexeme, self.expr(expr.written_value())),
+            Expr::Binary  (expr) => format!("{} {} {}", self.expr(&expr.left), expr.operator.lexeme, self.expr(&expr.right)),
+            Expr::Logical (expr) => format!("{} {} {}", self.expr(&expr.left), expr.operator.lexeme, self.expr(&expr.right)),
+            Expr::Unary   (expr) => format!("{}{}", expr.operator.lexeme, self.expr(&expr.right)),
+            Expr::Grouping(expr) => format!("({})", self.expr(&expr.expr)),
+            Expr::Get     (expr) => format!("{}.{}", self.expr(&expr.expr), expr.name.lexeme),
+            Expr::Set     (expr) => format!("{}.{} {} {}", self.expr(&expr.target), expr.name.lexeme, expr.operator.lexeme, self.expr(&expr.value)),
+            Expr::Ternary (expr) => format!("{} ? {} : {}", self.expr(&expr.condition), self.expr(&expr.then_branch), self.expr(&expr.else_branch)),
+            Expr::Super   (expr) => format!("super.{}", expr.method.lexeme),
+            Expr::This    (_)    => "this".to_owned(),
+            Expr::Variable(expr) => expr.name.lexeme.clone(),
+
+            // an arrow lambda's body is the `return` of its expression
+            Expr::Lambda(expr) => match (expr.arrow, expr.func.body.first()) {
+                (true, Some(Stmt::Return(ReturnStmt { value: Some(value), .. }))) => {
+                    format!("({}) => {}", self.params(&expr.func), self.expr(value))
+                }
+                _ => self.lambda(&expr.func),
+            },
+
+            Expr::Call(expr) => {
+                let named = expr.named.iter()
+                    .map(|arg| format!("{}: {}", arg.name.lexeme, self.expr(&arg.value)));
+
+                let args: Vec<_> = expr.args.iter()
+                    .map(|arg| self.expr(arg))
+                    .chain(named)
+                    .collect();
+
+                format!("{}({})", self.expr(&expr.callee), args.join(", "))
+            }
+
+            // the literals around each expression include its braces
+            Expr::Interpolation(expr) => {
+                expr.parts.iter()
+                    .map(|part| self.expr(part))
+                    .collect()
+            }
+
+            // as written, so numbers and strings keep their spelling
+            Expr::Literal(expr) => {
+                let span = expr.value.span;
+                self.source[span.start..span.end].to_owned()
+            }
+        }
+    }
+
+    /// Prints the comments that start before `offset`, each on its own line
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next).filter(|comment| comment.span.start < offset) {
+            let (start, end) = (comment.span.start, comment.span.end);
+            let line         = format!("//{}", comment.text.trim_end());
+
+            self.separate(start);
+            self.line(&line);
+
+            self.last_end = Some(end);
+            self.next    += 1;
+        }
+    }
+
+    /// Moves a comment on the same line as the end of a statement onto the end of the printed line
+    fn trailing_comment(&mut self, end: usize) {
+        let Some(comment) = self.comments.get(self.next) else {
+            return;
+        };
+
+        if comment.span.start < end || self.source[end..comment.span.start].contains('\n') {
+            return;
+        }
+
+        let line = format!(" //{}\n", comment.text.trim_end());
+
+        self.out.pop();
+        self.out.push_str(&line);
+
+        self.last_end = Some(comment.span.end);
+        self.next    += 1;
+    }
+
+    /// Keeps one blank line where the source had at least one, between the last thing printed and `offset`
+    fn separate(&mut self, offset: usize) {
+        let Some(last_end) = self.last_end else {
+            return;
+        };
+
+        let between = &self.source[last_end..offset.max(last_end)];
+
+        if between.matches('\n').count() > 1 {
+            self.out.push('\n');
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    // removes the last printed line, a closing `}`, so that it can be continued by `} else`
+    fn reopen(&mut self) {
+        self.out.pop();
+
+        let start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        self.out.truncate(start);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::multi_line;
+
+    use super::*;
+
+    fn lox_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+
+            match path.is_dir() {
+                true  => lox_files(&path, files),
+                false => if path.extension().is_some_and(|ext| ext == "lox") {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    fn format(source: &str) -> String {
+        format_source(source).ok().expect("source should parse")
+    }
+
+    // without the empty statements, which the formatter drops
+    fn lexemes(source: &str) -> Vec<String> {
+        let mut lexemes: Vec<String> = vec![];
+
+        for token in scan_tokens(source).unwrap() {
+            let empty = token.lexeme == ";" && lexemes.last().is_some_and(|last| [";", "{", "}"].contains(&last.as_str()));
+
+            if !empty {
+                lexemes.push(token.lexeme);
+            }
+        }
+
+        lexemes
+    }
+
+    fn comments(source: &str) -> Vec<String> {
+        scan_comments(source)
+            .into_iter()
+            .map(|comment| comment.text.trim_end().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn canonical() {
+        let source = multi_line!(
+            "// a comment",
+            "import  \"lib.lox\"  as lib;",
+            "import {x,y}from \"lib.lox\";",
+            "var   a=1;   // trailing",
+            "",
+            "",
+            "",
+            "fun add(x,y){return x+y;}",
+            "class A<B{",
+            "  init(){this.x=-(1+2)*3;}",
+            "",
+            "  get(){ return super.get(); }",
+            "}",
+            "class C{}",
+            "if(a>1){print \"big\";}else if(!a)print \"none\";else{",
+            "// nothing",
+            "}",
+            "for(var i=0;i<3;i=i+1)print i;",
+            "for(;;){}",
+            "while(a and b or c)a=a-1;",
+            "{ fun f(){} }",
+            "fun opt(a,b=1,...c){}",
+            "opt(1,c:2);",
+            "try{throw \"x\";}catch(e){print e;}",
+            "try{}catch(_e){}",
+            "var g=fun(x){return x;};",
+            "{ print map((a,b)=>a+b, fun(){print 1;}); }",
+        );
+
+        let expected = multi_line!(
+            "// a comment",
+            "import \"lib.lox\" as lib;",
+            "import { x, y } from \"lib.lox\";",
+            "var a = 1; // trailing",
+            "",
+            "fun add(x, y) {",
+            "    return x + y;",
+            "}",
+            "class A < B {",
+            "    init() {",
+            "        this.x = -(1 + 2) * 3;",
+            "    }",
+            "",
+            "    get() {",
+            "        return super.get();",
+            "    }",
+            "}",
+            "class C {}",
+            "if (a > 1) {",
+            "    print \"big\";",
+            "} else if (!a) print \"none\";",
+            "else {",
+            "    // nothing",
+            "}",
+            "for (var i = 0; i < 3; i = i + 1) print i;",
+            "for (;;) {}",
+            "while (a and b or c) a = a - 1;",
+            "{",
+            "    fun f() {}",
+            "}",
+            "fun opt(a, b = 1, ...c) {}",
+            "opt(1, c: 2);",
+            "try {",
+            "    throw \"x\";",
+            "} catch (e) {",
+            "    print e;",
+            "}",
+            "try {} catch (_e) {}",
+            "var g = fun (x) {",
+            "    return x;",
+            "};",
+            "{",
+            "    print map((a, b) => a + b, fun () {",
+            "        print 1;",
+            "    });",
+            "}",
+        );
+
+        assert_eq!(format(&source), expected + "\n");
+    }
+
+    #[test]
+    fn idempotent() {
+        let mut files = vec![];
+        lox_files(Path::new("./test_scripts"), &mut files);
+
+        let mut formatted_files = 0;
+
+        for file in files {
+            let source = fs::read_to_string(&file).unwrap();
+
+            // some fixtures are expected to fail to parse
+            let Ok(formatted) = format_source(&source) else {
+                continue;
+            };
+
+            formatted_files += 1;
+
+            assert_eq!(format(&formatted), formatted, "{} isn't stable", file.display());
+
+            // only whitespace and the position of comments change
+            assert_eq!(lexemes (&formatted), lexemes (&source), "{} changed tokens",   file.display());
+            assert_eq!(comments(&formatted), comments(&source), "{} changed comments", file.display());
+        }
+
+        assert!(formatted_files > 10);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(format_source("print 1 +;").is_err());
+        assert!(format_source("print \"abc").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use std::{fs, path::Path};
+
+use crate::{
+    diagram::{Diagram, DiagramError},
+    script::{
+        diagnostic::DiagnosticReport,
+        module::Loader,
+        parser::parse_ast,
+        resolver::resolve,
+        scanner::scan_tokens,
+        vm::{
+            compiler::compile,
+            native::NativeCtx,
+            object::{Arity, NativeFn, ObjPtr},
+            value::{HostValue, Value},
+            ArenaRoot, Limits, Root, RuntimeError, Vm,
+        },
+        RunError,
+    },
+};
+
+
+/// Runs scripts for a host program.
+/// The globals, classes and graph of every script loaded are kept, until the interpreter is dropped
+///
+/// ```
+/// use std::{cell::Cell, rc::Rc};
+/// use lightweaver::{Arity, HostValue, Interpreter};
+///
+/// let mut interpreter = Interpreter::new();
+///
+/// let calls = Rc::new(Cell::new(0));
+/// let count = calls.clone();
+///
+/// interpreter.register("double", Arity::Fixed(1), move |args| {
+///     count.set(count.get() + 1);
+///
+///     let x = f64::try_from(args[0].clone())?;
+///     Ok((x * 2.0).into())
+/// });
+///
+/// interpreter.load("area.lox", "fun area(w, h) { return double(w) * h; }").unwrap();
+///
+/// let area = interpreter.call("area", &[2.into(), 3.into()]).unwrap();
+///
+/// assert_eq!(area, HostValue::Number(12.0));
+/// assert_eq!(calls.get(), 1);
+/// ```
+pub struct Interpreter {
+    vm:     Vm,
+    loader: Loader,
+}
+
+type Re = RunError;
+
+
+impl Interpreter {
+
+    pub fn new() -> Self {
+        let mut vm = Vm::init(ArenaRoot::new(|_ctx| Root::new()));
+
+        vm.root().mutate_root(|_ctx, root| root.set_trace(false));
+
+        Self {
+            vm,
+            loader: Loader::new("<host>", None),
+        }
+    }
+
+    /// Runs a script, its imports are relative to the directory of `file`
+    pub fn load(&mut self, file: &str, source: &str) -> Result<(), DiagnosticReport> {
+        self.try_load(file, source).map_err(|err| {
+            let diagnostics = err.to_diagnostics(file, source);
+
+            DiagnosticReport::new(source.to_owned(), diagnostics).with_sources(err.sources())
+        })
+    }
+
+    pub fn load_file(&mut self, path: &Path) -> Result<(), DiagnosticReport> {
+        let file = path.display().to_string();
+
+        let Ok(source) = fs::read_to_string(path) else {
+            return Err(Re::IOError.report(&file, String::new()));
+        };
+
+        self.load(&file, &source)
+    }
+
+    /// Defines a native function, visible from every script.
+    /// `func` may capture state of the host program, use a `Cell` or `RefCell` to change it.
+    /// An `Err` becomes a runtime error in the calling script
+    pub fn register<F>(&mut self, name: &str, arity: Arity, func: F)
+        where F: Fn(&[HostValue]) -> Result<HostValue, String> + 'static
+    {
+        let func = NativeFn::new(arity, move |native: &mut NativeCtx, args: &[Value]| {
+            let args: Vec<_> = args.iter().map(Value::to_host).collect();
+
+            func(&args)
+                .and_then(|result| native.from_host(&result))
+                .map_err (|msg|    native.error(msg))
+        });
+
+        self.vm.root().mutate_root(|ctx, root| {
+            let obj = ObjPtr::new_native_fn(name.to_owned(), func, ctx);
+
+            root.define_builtin(name, Value::new_obj(obj));
+        });
+    }
+
+    /// Calls a global function (or class) of the loaded scripts, or a builtin
+    pub fn call(&mut self, name: &str, args: &[HostValue]) -> Result<HostValue, RuntimeError> {
+        self.vm.call(name, args)
+    }
+
+    /// The value of a global of the loaded scripts, or of a builtin
+    pub fn get(&mut self, name: &str) -> Option<HostValue> {
+        self.vm.root().mutate(|_ctx, root| {
+            root.get_global(name).map(|value| value.to_host())
+        })
+    }
+
+    /// Bounds on each script loaded (along with the imports it runs) and each call, `Limits::default()` unless set
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.vm.root().mutate_root(|_ctx, root| root.set_limits(limits));
+    }
+
+    /// Whether to keep what scripts print (as well as writing it to stdout), until `take_output`
+    pub fn capture_output(&mut self, capture: bool) {
+        self.vm.root().mutate_root(|_ctx, root| root.set_capture_out(capture));
+    }
+
+    pub fn take_output(&mut self) -> Vec<String> {
+        self.vm.root().mutate_root(|_ctx, root| root.take_out())
+    }
+
+    /// The diagram of the graph built so far, theme files are relative to `base_dir`
+    pub fn diagram(&mut self, base_dir: &Path) -> Result<Diagram, DiagramError> {
+        let graph = self.vm.root().mutate(|_ctx, root| root.graph());
+
+        Diagram::build(&graph, base_dir)
+    }
+
+    fn try_load(&mut self, file: &str, source: &str) -> Result<(), RunError> {
+
+        let tokens  = scan_tokens(source).map_err(Re::ScannerError)?;
+        let mut ast = parse_ast(tokens)  .map_err(Re::ParserError)?;
+
+        resolve(&mut ast).map_err(Re::ResolveError)?;
+
+        let dir = Path::new(file).parent().unwrap_or(Path::new("."));
+
+        self.loader.load_imports(&mut ast, dir)?;
+
+        self.vm.start_budget();
+        self.loader.run_pending(&mut self.vm)?;
+
+        let index = self.vm.root().mutate_root(|ctx, root| {
+            let index = root.function_count();
+
+            root.set_main_name(file.to_owned());
+            compile(ast, root, ctx).map(|_| index)
+        })
+        .map_err(Re::CompileError)?;
+
+        self.vm.load_script(index);
+        self.vm.run().map_err(|err| self.loader.runtime_error(err))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+    use crate::multi_line;
+
+    use super::*;
+
+    fn interpreter(source: &str) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.capture_output(true);
+        interpreter.load("test.lox", source).unwrap();
+
+        interpreter
+    }
+
+    #[test]
+    fn calls() {
+        let mut interpreter = interpreter(&multi_line!(
+            "var greeting = \"hello\";",
+            "fun greet(name) {",
+            "    return greeting + \" \" + name;",
+            "}",
+            "fun nothing() {}",
+            "class Box {}",
+        ));
+
+        assert_eq!(interpreter.call("greet",   &["world".into()]).unwrap(), HostValue::String("hello world".to_owned()));
+        assert_eq!(interpreter.call("nothing", &[]).unwrap(),               HostValue::Nil);
+        assert_eq!(interpreter.call("Box",     &[]).unwrap(),               HostValue::Instance { class: "Box".to_owned(), fields: BTreeMap::new() });
+
+        // globals are kept between loads and calls
+        interpreter.load("more.lox", "greeting = \"hi\";").unwrap();
+
+        assert_eq!(interpreter.call("greet", &["there".into()]).unwrap(), HostValue::String("hi there".to_owned()));
+        assert_eq!(interpreter.get ("greeting"),                         Some("hi".into()));
+        assert_eq!(interpreter.get ("missing"),                          None);
+    }
+
+    #[test]
+    fn natives() {
+        let mut interpreter = Interpreter::new();
+
+        let seen = Rc::new(RefCell::new(vec![]));
+        let log  = seen.clone();
+
+        interpreter.register("log", Arity::Variadic(0), move |args| {
+            log.borrow_mut().extend(args.iter().map(|arg| arg.to_string()));
+            Ok(HostValue::Nil)
+        });
+        interpreter.register("fail", Arity::Fixed(0), |_| Err("host failure".to_owned()));
+
+        interpreter.load("test.lox", "log(1, \"two\", nil);\nfun f() { return fail(); }").unwrap();
+
+        assert_eq!(*seen.borrow(), vec!["1", "two", "nil"]);
+
+        // natives can be called from the host too
+        interpreter.call("log", &[true.into()]).unwrap();
+        assert_eq!(seen.borrow().last().unwrap(), "true");
+
+        let err = interpreter.call("f", &[]).unwrap_err();
+
+        assert_eq!(err.msg, "host failure");
+        assert_eq!(err.to_string(), "host failure\n[line 2] in f");
+    }
+
+    #[test]
+    fn errors() {
+        let mut interpreter = interpreter("fun add(a, b) { return a + b; }\nvar x = 1;");
+
+        assert_eq!(interpreter.call("missing", &[]).unwrap_err().msg,                   "Undefined function 'missing'");
+        assert_eq!(interpreter.call("x", &[]).unwrap_err().msg,                         "Value of type 'Number' is not callable");
+        assert_eq!(interpreter.call("add", &[1.into()]).unwrap_err().msg,               "'add' expects 2 arguments but got 1");
+        assert_eq!(interpreter.call("add", &[1.into(), ().into()]).unwrap_err().msg,    "Operands must be two numbers or two strings");
+
+        // the interpreter is still usable after an error
+        assert_eq!(interpreter.call("add", &[1.into(), 2.into()]).unwrap(), HostValue::Number(3.0));
+
+        let report = interpreter.load("bad.lox", "print 1 +;").unwrap_err();
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn limits() {
+        let mut interpreter = interpreter("fun spin() { while (true) {} }\nfun one() { return 1; }");
+        interpreter.set_limits(Limits { instructions: Some(1000), ..Limits::none() });
+
+        assert_eq!(interpreter.call("spin", &[]).unwrap_err().msg, "Exceeded the limit of 1000 instructions");
+
+        // each call gets the full budget
+        assert_eq!(interpreter.call("one", &[]).unwrap(), HostValue::Number(1.0));
+        assert_eq!(interpreter.call("one", &[]).unwrap(), HostValue::Number(1.0));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(f64   ::try_from(HostValue::from(2)),           Ok(2.0));
+        assert_eq!(bool  ::try_from(HostValue::from(true)),        Ok(true));
+        assert_eq!(String::try_from(HostValue::from("a")),         Ok("a".to_owned()));
+        assert_eq!(HostValue::from(None::<f64>),                   HostValue::Nil);
+
+        assert_eq!(f64::try_from(HostValue::from("a")), Err("Expected a Number, found 'String'".to_owned()));
+
+        let mut interpreter = interpreter("fun id(x) { return x; }");
+        let err = interpreter.call("id", &[HostValue::Object("<fn id>".to_owned())]).unwrap_err();
+
+        assert_eq!(err.msg, "Can't pass the object '<fn id>' into a script");
+    }
+
+    #[test]
+    fn lists_and_instances() {
+        let mut interpreter = interpreter(&multi_line!(
+            "class Point {}",
+            "fun point(x, y) {",
+            "    var p = Point();",
+            "    p.x = x;",
+            "    p.y = y;",
+            "    return p;",
+            "}",
+            "fun sum(point) { return point.x + point.y; }",
+            "fun count(items) { return items.len; }",
+            "fun looped() {",
+            "    var p = point(1, 2);",
+            "    p.next = p;",
+            "    return p;",
+            "}",
+            "var names = split(\"a,b\", \",\");",
+        ));
+
+        let point = HostValue::Instance {
+            class:  "Point".to_owned(),
+            fields: BTreeMap::from([("x".to_owned(), 1.into()), ("y".to_owned(), 2.into())]),
+        };
+
+        assert_eq!(interpreter.get ("names"),                                Some(HostValue::List(vec!["a".into(), "b".into()])));
+        assert_eq!(interpreter.call("point", &[1.into(), 2.into()]).unwrap(), point);
+
+        // both are new objects in the script
+        assert_eq!(interpreter.call("sum",   std::slice::from_ref(&point)).unwrap(),            HostValue::Number(3.0));
+        assert_eq!(interpreter.call("count", &[HostValue::List(vec![point, ().into()])]).unwrap(), HostValue::Number(2.0));
+
+        // an instance met again within itself is only displayed
+        let HostValue::Instance { fields, .. } = interpreter.call("looped", &[]).unwrap() else { panic!() };
+        assert_eq!(fields["next"], HostValue::Object("<Point instance>".to_owned()));
+
+        let missing = HostValue::Instance { class: "Missing".to_owned(), fields: BTreeMap::new() };
+        assert_eq!(interpreter.call("sum", &[missing]).unwrap_err().msg, "Can't pass an instance of 'Missing' into a script, there's no class 'Missing'");
+    }
+
+    #[test]
+    fn diagrams() {
+        let mut interpreter = interpreter(&multi_line!(
+            "class Table {}",
+            "var users = graph.add(Table());",
+            "var draft = Table();",
+            "users.name = \"users\";",
+            "print users.name;",
+        ));
+
+        assert_eq!(interpreter.take_output(), vec!["users"]);
+
+        let diagram = interpreter.diagram(Path::new(".")).unwrap();
+        assert_eq!(diagram.nodes.len(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::Display;
+
+use crate::script::{diagnostic::{line_col, Span}, scanner::scan_comments};
+
+
+/// A warning about code that's valid, but probably a mistake
+///
+/// Each type has a code, which a `// lint: allow(<code>, ..)` comment silences
+/// on its own line, or on the line below it when the comment stands alone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub type_: LintType,
+    pub name:  String,
+    pub span:  Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintType {
+    UnusedVariable,
+    UnusedFunction,
+    UnusedClass,
+    UnusedParameter,
+    UnreachableCode,
+    Shadowing,
+    UnusedAssignment,
+}
+
+type Lt = LintType;
+
+const DIRECTIVE: &str = "lint:";
+
+
+impl LintType {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lt::UnusedVariable   => "unused_variable",
+            Lt::UnusedFunction   => "unused_function",
+            Lt::UnusedClass      => "unused_class",
+            Lt::UnusedParameter  => "unused_parameter",
+            Lt::UnreachableCode  => "unreachable_code",
+            Lt::Shadowing        => "shadowed_variable",
+            Lt::UnusedAssignment => "unused_assignment",
+        }
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = &self.name;
+
+        match self.type_ {
+            Lt::UnusedVariable   => write!(f, "Unused variable '{}'", name),
+            Lt::UnusedFunction   => write!(f, "Unused function '{}'", name),
+            Lt::UnusedClass      => write!(f, "Unused class '{}'", name),
+            Lt::UnusedParameter  => write!(f, "Unused parameter '{}'", name),
+            Lt::UnreachableCode  => write!(f, "Unreachable code"),
+            Lt::Shadowing        => write!(f, "'{}' shadows a variable in an enclosing scope", name),
+            Lt::UnusedAssignment => write!(f, "Value assigned to '{}' is never read", name),
+        }
+    }
+}
+
+
+/// Drops the lints silenced by a comment directive
+pub fn filter_allowed(lints: Vec<Lint>, source: &str) -> Vec<Lint> {
+
+    // (line, code)
+    let allowed: Vec<(usize, String)> = scan_comments(source)
+        .into_iter()
+        .flat_map(|comment| {
+            let before = source[..comment.span.start].rsplit('\n').next().unwrap_or("");
+            let line   = match before.trim().is_empty() {
+                true  => comment.line + 1,
+                false => comment.line,
+            };
+
+            parse_directive(&comment.text)
+                .into_iter()
+                .map(move |code| (line, code))
+        })
+        .collect();
+
+    lints.into_iter()
+        .filter(|lint| {
+            let (line, _) = line_col(source, lint.span.start.min(source.len()));
+
+            !allowed.iter().any(|(l, code)| *l == line && code == lint.type_.code())
+        })
+        .collect()
+}
+
+// `lint: allow(unused_variable, shadowed_variable)`
+fn parse_directive(comment: &str) -> Vec<String> {
+    let Some(rest) = comment.trim().strip_prefix(DIRECTIVE) else {
+        return vec![];
+    };
+
+    let codes = rest.trim()
+        .strip_prefix("allow(")
+        .and_then(|rest| rest.split_once(')'))
+        .map(|(codes, _)| codes)
+        .unwrap_or("");
+
+    codes.split(',')
+        .map(|code| code.trim().to_owned())
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::multi_line;
+
+    use super::*;
+
+    fn lint(type_: LintType, source: &str, name: &str) -> Lint {
+        let start = source.find(&format!("var {}", name)).unwrap() + 4;
+
+        Lint {
+            type_,
+            name: name.to_owned(),
+            span: Span::new(start, start + name.len()),
+        }
+    }
+
+    #[test]
+    fn directives() {
+        let source = multi_line!(
+            "{",
+            "    // lint: allow(unused_variable)",
+            "    var a = 1;",
+            "    var b = 2; // lint: allow(shadowed_variable, unused_variable)",
+            "    var c = 3; // allow(unused_variable)",
+            "    var d = 4; // lint: allow(unused_assignment)",
+            "    var e = 5;",
+            "}",
+        );
+
+        let lints = vec![
+            lint(Lt::UnusedVariable, &source, "a"),
+            lint(Lt::UnusedVariable, &source, "b"),
+            lint(Lt::UnusedVariable, &source, "c"),
+            lint(Lt::UnusedVariable, &source, "d"),
+            lint(Lt::UnusedVariable, &source, "e"),
+        ];
+
+        let names: Vec<_> = filter_allowed(lints, &source)
+            .into_iter()
+            .map(|lint| lint.name)
+            .collect();
+
+        assert_eq!(names, vec!["c", "d", "e"]);
+    }
+}
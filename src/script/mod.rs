@@ -1,8 +1,9 @@
 use std::{fs, path::Path};
 
 use ast::{Ast, AstNode, DisplayArgs, WalkArgs};
-use parser::{parse_ast, ParseErrorType};
-use scanner::{scan_tokens, ScannerErrorType};
+use diagnostic::{line_col, Diagnostic, DiagnosticReport};
+use parser::parse_ast;
+use scanner::scan_tokens;
 
 
 pub mod tokens;
@@ -11,141 +12,274 @@ pub mod ast;
 pub mod parser;
 pub mod vm;
 pub mod resolver;
+pub mod lint;
+pub mod repl;
+pub mod fmt;
+pub mod module;
+pub mod interpreter;
+pub mod diagnostic;
 
 mod test;
 
-use vm::{compiler::compile, RuntimeError};
+use vm::{compiler::{compile, CompileError}, RuntimeError, TraceFrame};
 
+use crate::diagram::{Diagram, DiagramError};
 use crate::script::{
-    parser::AssignmentTarget,
-    resolver::resolve,
+    lint::{filter_allowed, Lint},
+    module::{ImportError, Loader, ModuleError},
+    resolver::{analyze, resolve, Reference},
     vm::{
-        ArenaRoot, Root
+        ArenaRoot, Limits, Root, Vm
     }
 };
 
-type ScanErrorList  = Vec<scanner::ScannerError>;
-type ParseErrorList = Vec<parser ::ParseError>;
-type RunResult      = Result<(), RunError>;
+type ScanErrorList    = Vec<scanner ::ScannerError>;
+type ParseErrorList   = Vec<parser  ::ParseError>;
+type ResolveErrorList = Vec<resolver::ResolveError>;
+
+/// How many of the innermost and outermost calls a runtime error lists, eliding those between
+static TRACE_FRAMES: usize = 10;
 
 pub enum RunError {
     IOError,
     ScannerError(ScanErrorList),
     ParserError (ParseErrorList),
-    RuntimeError(RuntimeError)
+    ResolveError(ResolveErrorList),
+    ImportError (ImportError),
+    CompileError(CompileError),
+    RuntimeError(RuntimeError),
+    DiagramError(DiagramError),
+    ModuleError (Box<ModuleError>),
 }
 
 type Re = RunError;
 
+/// The result of checking a script without running it
+pub struct Check {
+    pub ast:         Option<Ast>,
+    pub references:  Vec<Reference>,
+    pub diagnostics: Vec<Diagnostic>,
+}
 
-pub fn run_file(path: &Path) -> &str {
 
-    match (|| {
+/// Runs the script, returning the diagram (if it ran successfully) and any warnings or errors
+pub fn run_file(path: &Path, limits: Limits) -> (Option<Diagram>, DiagnosticReport) {
 
-        let source  = fs::read_to_string(path).map_err(|_|   Re::IOError)?;
+    let file   = path.display().to_string();
+    let source = fs::read_to_string(path);
 
-        let tokens  = scan_tokens(&source)    .map_err(|err| Re::ScannerError(err))?;
+    let mut files = vec![path.to_owned()];
 
-        let mut ast = parse_ast(tokens)       .map_err(|err| Re::ParserError(err))?;
-        resolve(&mut ast);
-        display_ast(&ast);
+    let Ok(source) = source else {
+        return (None, Re::IOError.report(&file, String::new()).with_files(files));
+    };
 
-        let mut root = ArenaRoot::new(|_ctx| { Root::new() });
+    let mut lints  = vec![];
+    let mut loader = Loader::new(&file, Some(path));
+    let result     = run_source(&source, path, limits, &mut lints, &mut loader);
+
+    let mut diagnostics = lint_diagnostics(&lints, &file);
+
+    let mut sources = vec![];
+
+    // a failed run still watches the imports it got to, so that fixing one reruns it
+    let diagram = match result {
+        Ok (diagram) => {
+            files.extend(diagram.files.iter().cloned());
+            Some(diagram)
+        },
+        Err(err)     => {
+            files.extend(loader.files_read().iter().cloned());
+            diagnostics.extend(err.to_diagnostics(&file, &source));
+            sources = err.sources();
+            None
+        }
+    };
 
-        root.mutate_root(|ctx, root| {
-            compile(ast, root, ctx).unwrap();
-        });
+    let report = DiagnosticReport::new(source, diagnostics)
+        .with_sources(sources)
+        .with_files  (files);
 
-        root.mutate(|_ctx, root| {
-            root.dbg_funcs();
-        });
+    (diagram, report)
+}
 
-        vm::interpret(root).map_err(|err| Re::RuntimeError(err))?;
+fn run_source(source: &str, path: &Path, limits: Limits, lints: &mut Vec<Lint>, loader: &mut Loader) -> Result<Diagram, RunError> {
 
-        Ok("test")
-    })() {
-        Err(err) => display_error(err),
+    let tokens  = scan_tokens(source)     .map_err(Re::ScannerError)?;
 
-        Ok (val) => val,
-    }
+    let mut ast = parse_ast(tokens)       .map_err(Re::ParserError)?;
+    *lints      = resolve(&mut ast)       .map_err(Re::ResolveError)?;
+    *lints      = filter_allowed(std::mem::take(lints), source);
+
+    let file     = path.display().to_string();
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    loader.load_imports(&mut ast, base_dir)?;
+
+    // watch and serve rerun scripts through here, which the debug dumps and trace would flood stdout for
+    let mut vm = Vm::init(ArenaRoot::new(|_ctx| { Root::new() }));
+    vm.root().mutate_root(|_ctx, root| {
+        root.set_trace(false);
+        root.set_limits(limits);
+    });
+    vm.start_budget();
+
+    // imported scripts run first, in the order they're imported, on the same budget
+    loader.run_pending(&mut vm)?;
+
+    let index = vm.root().mutate_root(|ctx, root| {
+        let index = root.function_count();
+
+        root.set_main_name(file);
+        compile(ast, root, ctx).map(|_| index)
+    })
+    .map_err(Re::CompileError)?;
+
+    vm.load_script(index);
+    vm.run().map_err(|err| loader.runtime_error(err))?;
+
+    let graph = vm.root().mutate(|_ctx, root| root.graph());
+
+    let mut diagram = Diagram::build(&graph, base_dir).map_err(Re::DiagramError)?;
+    diagram.files.extend(loader.paths());
+
+    Ok(diagram)
 }
 
 
-fn display_error(err: RunError) -> ! {
-    match err {
-        Re::IOError           => panic!("Unable to read source file"),
-        Re::ScannerError(err) => display_scanner_err(err),
-        Re::ParserError (err) => display_parser_err (err),
-        Re::RuntimeError(err) => display_runtime_err(err),
+/// Scans, parses and resolves a script without running it, for editor tooling
+pub fn check_source(source: &str, file: &str) -> Check {
+
+    let failed = |err: RunError| Check {
+        ast:         None,
+        references:  vec![],
+        diagnostics: err.to_diagnostics(file, source),
+    };
+
+    let tokens = match scan_tokens(source) {
+        Ok (tokens) => tokens,
+        Err(err)    => return failed(Re::ScannerError(err)),
+    };
+
+    let mut ast = match parse_ast(tokens) {
+        Ok (ast) => ast,
+        Err(err) => return failed(Re::ParserError(err)),
+    };
+
+    let resolution = analyze(&mut ast);
+
+    let mut diagnostics = lint_diagnostics(&filter_allowed(resolution.lints, source), file);
+    diagnostics.extend(resolve_diagnostics(&resolution.errors, file));
+
+    Check {
+        ast:         Some(ast),
+        references:  resolution.references,
+        diagnostics,
     }
 }
 
-fn display_scanner_err(err: ScanErrorList) -> ! {
-    type Se = ScannerErrorType;
 
-    for e in err.iter() {
-        eprint!("Compile Error: Line {} - Col {} - \n> ", e.line, e.col);
+impl RunError {
 
-        match &e.type_ {
-            Se::UnterminatedString      => eprintln!("Unterminated String"),
-            Se::UnexpectedCharacter(ch) => eprintln!("Unexpected character: '{}'", ch),
-        }
+    pub fn report(self, file: &str, source: String) -> DiagnosticReport {
+        let diagnostics = self.to_diagnostics(file, &source);
+
+        DiagnosticReport::new(source, diagnostics)
     }
 
-    panic!()
-}
+    /// The files and sources the diagnostics point into, other than the script being run
+    pub fn sources(&self) -> Vec<(String, String)> {
+        match self {
+            Re::ModuleError(err) => vec![(err.file.to_owned(), err.source.to_owned())],
+            _                    => vec![],
+        }
+    }
 
-fn display_parser_err(err: ParseErrorList) -> ! {
-    type Pe = ParseErrorType;
-
-    for e in err.iter() {
-        eprint!("Compile Error: Line {} - Col {} - \n> ", e.token.line, e.token.col);
-
-        match &e.type_ {
-            Pe::MissingClassIdentifier                  => eprintln!("Expect class name"),
-            Pe::MissingSuperclassIdentifier             => eprintln!("Expect superclass name"),
-            Pe::MissingClassOpenCurly                   => eprintln!("Expect '{{' before class body"),
-            Pe::MissingClassCloseCurly                  => eprintln!("Expect '}}' after class body"),
-            Pe::MissingFunctionIdentifier(type_)        => eprintln!("Expect {} name",             type_.to_string()),
-            Pe::MissingFunctionOpenParen (type_)        => eprintln!("Expect '(' after {} name",   type_.to_string()),
-            Pe::MissingFunctionOpenBrace (type_)        => eprintln!("Expect '}}' before {} name", type_.to_string()),
-            Pe::MissingFunctionCloseParen               => eprintln!("Expect ')' after parameters"),
-            Pe::FunctionTooManyParameters               => eprintln!("Can't have more than 255 parameters"),
-            Pe::MissingParameterIdentifier              => eprintln!("Expect parameter name"),
-            Pe::MissingVariableIdentifier               => eprintln!("Expect variable name"),
-            Pe::MissingVariableSemicolon                => eprintln!("Expect ';' after variable declaration"),
-            Pe::MissingForOpenParen                     => eprintln!("Expect '(' after 'for'"),
-            Pe::MissingForCloseParen                    => eprintln!("Expect ')' after for clauses"),
-            Pe::MissingForConditionDelimiter            => eprintln!("Expect ';' after loop condition"),
-            Pe::MissingIfOpenParen                      => eprintln!("Expect '(' after 'if'"),
-            Pe::MissingIfCloseParen                     => eprintln!("Expect ')' after if contition"),
-            Pe::MissingPrintSemicolon                   => eprintln!("Expect ';' after print"),
-            Pe::MissingReturnSemicolon                  => eprintln!("Expect ';' after return value"),
-            Pe::MissingWhileOpenParen                   => eprintln!("Expect '(' after while"),
-            Pe::MissingWhileCloseParen                  => eprintln!("Expect ')' after condition"),
-            Pe::MissingExpressionStmtSemicolon          => eprintln!("Expect ';' after expression"),
-            Pe::MissingBlockCloseBrace                  => eprintln!("Expect '}}' after block"),
-            Pe::MissingPropertyIdentifier               => eprintln!("Expect property name after '.'"),
-            Pe::MissingSuperDot                         => eprintln!("Expect '.' after super"),
-            Pe::MissingSuperPropertyIdentifier          => eprintln!("Expect superclass method name"),
-            Pe::MissingGroupingCloseParen               => eprintln!("Expect ')' after expression"),
-            Pe::MissingExpression(token)                => eprintln!("Expect expression ({})", token),
-            Pe::InvalidAssignmentTarget(target)         => {
-                type T = AssignmentTarget;
-                match target {
-                    T::Dot  => eprintln!("Invalid assignment target for '.'"),
-                    T::Expr => eprintln!("Invalid assignment target for expression"),
-                }
-            },
+    pub fn to_diagnostics(&self, file: &str, source: &str) -> Vec<Diagnostic> {
+        match self {
+            Re::IOError           => vec![Diagnostic::error(file, "Unable to read source file".to_owned())],
+            Re::ScannerError(err) => scanner_diagnostics(err, file),
+            Re::ParserError (err) => parser_diagnostics (err, file),
+            Re::ResolveError(err) => resolve_diagnostics(err, file),
+            Re::ImportError (err) => vec![Diagnostic::error(file, err.to_string()).with_span(err.token.span)],
+            Re::CompileError(err) => vec![Diagnostic::error(file, err.msg.to_owned())],
+            Re::RuntimeError(err) => vec![runtime_diagnostic(err, file, source)],
+            Re::DiagramError(err) => vec![Diagnostic::error(file, err.to_string())],
+            Re::ModuleError (err) => err.error.to_diagnostics(&err.file, &err.source),
         }
     }
+}
+
+fn scanner_diagnostics(err: &ScanErrorList, file: &str) -> Vec<Diagnostic> {
+    err.iter()
+        .map(|e| {
+            Diagnostic::error(file, e.type_.to_string())
+                .with_span(e.span)
+        })
+        .collect()
+}
+
+fn parser_diagnostics(err: &ParseErrorList, file: &str) -> Vec<Diagnostic> {
+    err.iter()
+        .map(|e| {
+            Diagnostic::error(file, e.type_.to_string())
+                .with_span(e.token.span)
+        })
+        .collect()
+}
 
-    panic!()
+fn resolve_diagnostics(err: &ResolveErrorList, file: &str) -> Vec<Diagnostic> {
+    err.iter()
+        .map(|e| {
+            Diagnostic::error(file, e.to_string())
+                .with_span(e.token.span)
+        })
+        .collect()
 }
 
+fn lint_diagnostics(lints: &[Lint], file: &str) -> Vec<Diagnostic> {
+    lints.iter()
+        .map(|lint| {
+            let code = lint.type_.code();
 
-fn display_runtime_err(err: RuntimeError) -> ! {
-    panic!("Runtime error: [line {}] {} \n{}", err.line, err.msg, err.stack_trace)
+            Diagnostic::warning(file, lint.to_string())
+                .with_span(lint.span)
+                .with_note(format!("silence with `// lint: allow({})`", code))
+        })
+        .collect()
+}
+
+fn runtime_diagnostic(err: &RuntimeError, file: &str, source: &str) -> Diagnostic {
+    let diagnostic = Diagnostic::error(file, err.msg.to_owned())
+        .with_span(err.span)
+    ;
+
+    let trace   = &err.stack_trace;
+    let elided  = trace.len().saturating_sub(2 * TRACE_FRAMES);
+    let (head, tail) = trace.split_at(trace.len().min(TRACE_FRAMES));
+    let tail    = &tail[elided..];
+
+    let note = |frame: &TraceFrame| {
+        // frames in other modules point into a different source
+        if frame.module != err.module {
+            return format!("[{}:{}] in {}", frame.file, frame.line, frame.function);
+        }
+
+        let (line, col) = line_col(source, frame.span.start.min(source.len()));
+
+        format!("[line {}, col {}] in {}", line, col, frame.function)
+    };
+
+    let diagnostic = head.iter()
+        .fold(diagnostic, |diagnostic, frame| diagnostic.with_note(note(frame)));
+
+    let diagnostic = match elided {
+        0 => diagnostic,
+        _ => diagnostic.with_note(format!("... {} more calls", elided)),
+    };
+
+    tail.iter()
+        .fold(diagnostic, |diagnostic, frame| diagnostic.with_note(note(frame)))
 }
 
 
@@ -205,3 +339,144 @@ fn display(node: Box<&dyn AstNode>, depth: usize, prefix: Option<String>) {
 fn spaces(depth: usize) -> String {
     " ".repeat(depth * 4)
 }
+
+
+#[cfg(test)]
+mod tests {
+    use crate::multi_line;
+
+    use super::*;
+
+    fn errors(source: &str) -> Vec<Diagnostic> {
+        let err = match scan_tokens(source) {
+            Err(err)   => Re::ScannerError(err),
+            Ok(tokens) => Re::ParserError(parse_ast(tokens).err().expect("expect a parse error")),
+        };
+
+        err.to_diagnostics("test.lox", source)
+    }
+
+    fn spanned<'a>(source: &'a str, diagnostic: &Diagnostic) -> &'a str {
+        let span = diagnostic.span.unwrap();
+        &source[span.start .. span.end]
+    }
+
+    #[test]
+    fn error_spans() {
+        let source = multi_line!(
+            "var a = 1;",
+            "var b = \"abc\" $;",
+        );
+
+        let diagnostics = errors(&source);
+        assert_eq!(diagnostics[0].message,               "Unexpected character: '$'");
+        assert_eq!(spanned(&source, &diagnostics[0]),    "$");
+
+        let source = multi_line!(
+            "var a = 1;",
+            "print \"a\" a;",
+        );
+
+        // parse errors point at the last token consumed
+        let diagnostics = errors(&source);
+        assert_eq!(diagnostics[0].message,               "Expect ';' after print");
+        assert_eq!(spanned(&source, &diagnostics[0]),    "\"a\"");
+        assert_eq!(diagnostics[0].span.unwrap().start,   17);
+
+        let source = "print \"{a b} rows\";";
+
+        let diagnostics = errors(source);
+        assert_eq!(diagnostics[0].message,               "Expect '}' after the interpolated expression");
+        assert_eq!(spanned(source, &diagnostics[0]),     "b");
+    }
+
+    #[test]
+    fn ast_spans() {
+        let source = "print -(a + b) * c.d;";
+
+        let ast = parse_ast(scan_tokens(source).unwrap()).unwrap();
+
+        let ast::Stmt::Print(print) = &ast.stmts[0] else { panic!("expect a print statement") };
+        assert_eq!(&source[print.span.start .. print.span.end], source);
+
+        let ast::Expr::Binary(binary) = &print.expr else { panic!("expect a binary expression") };
+        assert_eq!(&source[binary.span.start .. binary.span.end], "-(a + b) * c.d");
+
+        let left  = binary.left .span();
+        let right = binary.right.span();
+        assert_eq!(&source[left .start .. left .end], "-(a + b)");
+        assert_eq!(&source[right.start .. right.end], "c.d");
+    }
+
+    #[test]
+    fn runtime_error_spans() {
+        let source = multi_line!(
+            "fun add(a, b) {",
+            "  return a + b;",
+            "}",
+            "print add(1, nil);",
+        );
+
+        let Err(Re::RuntimeError(err)) = run_source(&source, Path::new("test.lox"), Limits::default(), &mut vec![], &mut Loader::new("test.lox", None)) else {
+            panic!("expect a runtime error")
+        };
+
+        let slice = |span: diagnostic::Span| &source[span.start .. span.end];
+
+        assert_eq!(slice(err.span), "a + b");
+
+        let frames: Vec<_> = err.stack_trace.iter()
+            .map(|frame| (frame.function.as_str(), slice(frame.span)))
+            .collect();
+
+        assert_eq!(frames, vec![("add", "a + b"), ("script", "add(1, nil)")]);
+
+        let diagnostic = runtime_diagnostic(&err, "test.lox", &source);
+        assert_eq!(diagnostic.notes, vec!["[line 2, col 10] in add", "[line 4, col 7] in script"]);
+    }
+
+    #[test]
+    fn long_stack_traces_are_elided() {
+        let source = multi_line!(
+            "fun down(n) {",
+            "  return down(n + 1);",
+            "}",
+            "down(0);",
+        );
+
+        let limits = Limits { call_depth: 50, ..Limits::default() };
+
+        let Err(Re::RuntimeError(err)) = run_source(&source, Path::new("test.lox"), limits, &mut vec![], &mut Loader::new("test.lox", None)) else {
+            panic!("expect a runtime error")
+        };
+
+        assert_eq!(err.msg,               "Stack overflow, exceeded the call depth limit of 50");
+        assert_eq!(err.stack_trace.len(), 50);
+
+        let diagnostic = runtime_diagnostic(&err, "test.lox", &source);
+        assert_eq!(diagnostic.notes.len(), 2 * TRACE_FRAMES + 1);
+        assert_eq!(diagnostic.notes[0],            "[line 2, col 10] in down");
+        assert_eq!(diagnostic.notes[TRACE_FRAMES], "... 30 more calls");
+        assert_eq!(diagnostic.notes.last().unwrap(), "[line 4, col 1] in script");
+    }
+
+    #[test]
+    fn limits_cover_imports() {
+        let run = |file: &str| {
+            let path   = Path::new("./test_scripts/unit_tests/modules").join(file);
+            let source = fs::read_to_string(&path).unwrap();
+            let limits = Limits { instructions: Some(1500), ..Limits::none() };
+
+            run_source(&source, &path, limits, &mut vec![], &mut Loader::new(file, Some(&path)))
+        };
+
+        assert!(run("spin.lox").is_ok());
+
+        // the import and the script that imports it share one budget, each fits in it alone
+        let Err(Re::RuntimeError(err)) = run("spins.lox") else {
+            panic!("expect a runtime error")
+        };
+
+        assert_eq!(err.msg, "Exceeded the limit of 1500 instructions");
+    }
+}
@@ -0,0 +1,324 @@
+use std::{collections::HashMap, fmt::Display, fs, path::{Path, PathBuf}};
+
+use crate::script::{
+    ast::{Ast, Stmt},
+    parser::parse_ast,
+    resolver::resolve,
+    scanner::scan_tokens,
+    tokens::Token,
+    vm::{chunk::ModuleIndex, compiler::compile_module, RuntimeError, Vm},
+    RunError,
+};
+
+
+/// Finds, checks and runs the scripts imported by a script.
+/// Each file is loaded once, however many scripts import it
+pub struct Loader {
+    /// The display name and source of every module, the importing script being module 0
+    files:   Vec<(String, String)>,
+    paths:   HashMap<PathBuf, ModuleIndex>,
+
+    /// The files being loaded and their display names, innermost last
+    loading: Vec<(PathBuf, String)>,
+
+    /// Every file an import has pointed at, whether or not it could be loaded
+    read:    Vec<PathBuf>,
+
+    /// Loaded but not yet run, imports before the scripts importing them.
+    /// A module is numbered once its imports are loaded, so this is in index order
+    pending: Vec<(ModuleIndex, Ast)>,
+}
+
+#[derive(Debug)]
+pub struct ImportError {
+    pub type_: ImportErrorType,
+    pub token: Token, // the path
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ImportErrorType {
+    NotFound,
+    Cycle(Vec<String>),
+}
+
+/// An error in an imported script, rather than the one being run
+pub struct ModuleError {
+    pub file:   String,
+    pub source: String,
+    pub error:  RunError,
+}
+
+type Re  = RunError;
+type Ie  = ImportErrorType;
+
+
+impl Loader {
+
+    /// `path` is the file of the importing script, if there is one
+    pub fn new(file: &str, path: Option<&Path>) -> Self {
+        let path = path
+            .and_then(|path| fs::canonicalize(path).ok())
+            .unwrap_or_default();
+
+        Self {
+            files:   vec![(file.to_owned(), String::new())],
+            paths:   HashMap::from([(path.clone(), ModuleIndex(0))]),
+            loading: vec![(path, file.to_owned())],
+            read:    vec![],
+            pending: vec![],
+        }
+    }
+
+    /// Loads the scripts imported by `ast`, relative to `dir`, and sets the module of each import
+    pub fn load_imports(&mut self, ast: &mut Ast, dir: &Path) -> Result<(), RunError> {
+        let loaded = self.files.len();
+
+        for stmt in ast.stmts.iter_mut() {
+            let Stmt::Import(import) = stmt else {
+                continue;
+            };
+
+            match self.load(&import.path, dir) {
+                Ok (module) => import.module = Some(module),
+                Err(err)    => {
+                    self.forget(loaded);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles and runs the top level code of the scripts loaded since the last call
+    pub fn run_pending(&mut self, vm: &mut Vm) -> Result<(), RunError> {
+
+        while !self.pending.is_empty() {
+            let (module, ast) = self.pending.remove(0);
+            let name          = self.files[*module].0.clone();
+
+            let index = vm.root().mutate_root(|ctx, root| {
+                let index = root.function_count();
+
+                assert_eq!(root.add_module(name), module, "expect modules to be added in the order they were loaded");
+
+                compile_module(ast, module, root, ctx).map(|_| index)
+            })
+            .map_err(|err| self.module_error(module, Re::CompileError(err)))?;
+
+            vm.load_script(index);
+            vm.run().map_err(|err| self.runtime_error(err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Attributes a runtime error to the script it occurred in
+    pub fn runtime_error(&self, err: RuntimeError) -> RunError {
+        let module = err.module;
+
+        self.module_error(module, Re::RuntimeError(err))
+    }
+
+    /// The files of the imported scripts
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = self.paths.iter()
+            .filter(|(_, module)| **module != ModuleIndex(0))
+            .collect();
+
+        paths.sort_by_key(|(_, module)| ***module);
+
+        paths.into_iter()
+            .map(|(path, _)| path.to_owned())
+            .collect()
+    }
+
+    /// The files of the imports, including those that couldn't be loaded, in the order they were first imported
+    pub fn files_read(&self) -> &[PathBuf] {
+        &self.read
+    }
+
+    fn load(&mut self, token: &Token, dir: &Path) -> Result<ModuleIndex, RunError> {
+        let error = |type_| Re::ImportError(ImportError { type_, token: token.clone() });
+
+        let path = dir.join(&token.lexeme);
+        let read = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+
+        if !self.read.contains(&read) {
+            self.read.push(read);
+        }
+
+        let Ok(canonical) = fs::canonicalize(&path) else {
+            return Err(error(Ie::NotFound));
+        };
+
+        if let Some(start) = self.loading.iter().position(|(loading, _)| *loading == canonical) {
+            let cycle = self.loading[start..].iter()
+                .chain([&self.loading[start]])
+                .map(|(_, file)| file.to_owned())
+                .collect();
+
+            return Err(error(Ie::Cycle(cycle)));
+        }
+
+        if let Some(&module) = self.paths.get(&canonical) {
+            return Ok(module);
+        }
+
+        let file   = path.display().to_string();
+        let source = fs::read_to_string(&canonical).map_err(|_| error(Ie::NotFound))?;
+
+        self.loading.push((canonical.clone(), file.clone()));
+
+        let ast = self.check(&source, &canonical);
+
+        self.loading.pop();
+
+        let module = ModuleIndex(self.files.len());
+
+        self.files.push((file, source));
+
+        let ast = ast.map_err(|err| self.module_error(module, err))?;
+
+        self.paths  .insert(canonical, module);
+        self.pending.push((module, ast));
+
+        Ok(module)
+    }
+
+    // the lints of imported scripts aren't reported, only those of the script being run
+    fn check(&mut self, source: &str, path: &Path) -> Result<Ast, RunError> {
+        let tokens  = scan_tokens(source).map_err(Re::ScannerError)?;
+        let mut ast = parse_ast(tokens)  .map_err(Re::ParserError)?;
+
+        resolve(&mut ast).map_err(Re::ResolveError)?;
+
+        self.load_imports(&mut ast, path.parent().unwrap_or(Path::new(".")))?;
+
+        Ok(ast)
+    }
+
+    // drops the modules loaded after a failed import, so the module indexes stay in step with the vm
+    fn forget(&mut self, loaded: usize) {
+        self.files  .truncate(loaded);
+        self.paths  .retain(|_, module| **module < loaded);
+        self.pending.retain(|(module, _)| **module < loaded);
+    }
+
+    fn module_error(&self, module: ModuleIndex, error: RunError) -> RunError {
+        if *module == 0 || matches!(error, Re::ModuleError(_)) {
+            return error;
+        }
+
+        let (file, source) = self.files[*module].clone();
+
+        Re::ModuleError(Box::new(ModuleError {
+            file,
+            source,
+            error,
+        }))
+    }
+}
+
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.type_ {
+            Ie::NotFound      => write!(f, "Can't find the script '{}'", self.token.lexeme),
+            Ie::Cycle (files) => write!(f, "Import cycle: {}", files.join(" -> ")),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::script::{
+        diagnostic::Diagnostic,
+        vm::{compiler::compile, ArenaRoot, Root},
+    };
+
+    use super::*;
+
+    static DIR: &str = "./test_scripts/unit_tests/modules";
+
+    // runs a fixture like `run_source`, returning what it printed
+    fn run(file: &str) -> Result<Vec<String>, RunError> {
+        let path   = Path::new(DIR).join(file);
+        let source = fs::read_to_string(&path).unwrap();
+
+        let mut ast = parse_ast(scan_tokens(&source).unwrap()).unwrap();
+        resolve(&mut ast).unwrap();
+
+        let mut loader = Loader::new(file, Some(&path));
+        loader.load_imports(&mut ast, Path::new(DIR))?;
+
+        let mut vm = Vm::init(ArenaRoot::new(|_ctx| Root::new_test()));
+        loader.run_pending(&mut vm)?;
+
+        let index = vm.root().mutate_root(|ctx, root| {
+            let index = root.function_count();
+
+            root.set_main_name(file.to_owned());
+            compile(ast, root, ctx).map(|_| index)
+        })
+        .map_err(Re::CompileError)?;
+
+        vm.load_script(index);
+        vm.run().map_err(|err| loader.runtime_error(err))?;
+
+        Ok(vm.root().mutate_root(|_ctx, root| root.take_out()))
+    }
+
+    fn diagnostics(file: &str) -> Vec<Diagnostic> {
+        let Err(err) = run(file) else {
+            panic!("expect {} to fail", file)
+        };
+
+        err.to_diagnostics(file, "")
+    }
+
+    #[test]
+    fn imports() {
+        let out = run("main.lox").ok().expect("expect main.lox to run");
+
+        assert_eq!(out, vec!["counter loaded", "shapes", "6", "cm", "main", "shapes in cm", "2"]);
+    }
+
+    #[test]
+    fn cycles() {
+        let diagnostics = diagnostics("cycle_a.lox");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!   (diagnostics[0].file.ends_with("cycle_b.lox"));
+        assert_eq!(diagnostics[0].message, format!("Import cycle: cycle_a.lox -> {DIR}/cycle_b.lox -> cycle_a.lox"));
+    }
+
+    #[test]
+    fn not_found() {
+        let diagnostics = diagnostics("not_found.lox");
+
+        assert_eq!(diagnostics[0].file,    "not_found.lox");
+        assert_eq!(diagnostics[0].message, "Can't find the script 'missing.lox'");
+        assert_eq!(diagnostics[0].span,    Some(crate::script::diagnostic::Span::new(7, 20)));
+    }
+
+    #[test]
+    fn errors_in_imports() {
+        let diagnostics = self::diagnostics("broken.lox");
+
+        assert!   (diagnostics[0].file.ends_with("lib/broken.lox"));
+        assert_eq!(diagnostics[0].message, "Expect expression, found ';'");
+
+        let diagnostics = self::diagnostics("runtime_error.lox");
+
+        assert!   (diagnostics[0].file.ends_with("lib/fail.lox"));
+        assert_eq!(diagnostics[0].message, "Operands must be two numbers or two strings");
+        assert_eq!(diagnostics[0].notes,   vec!["[line 2, col 12] in fail", "[runtime_error.lox:3] in script"]);
+
+        let diagnostics = self::diagnostics("undefined.lox");
+
+        assert_eq!(diagnostics[0].file,    "undefined.lox");
+        assert_eq!(diagnostics[0].message, format!("Module '{DIR}/lib/shapes.lox' doesn't define 'volume'"));
+    }
+}
@@ -1,8 +1,8 @@
-use std::{cell::Cell, collections::HashMap};
+use std::{cell::Cell, collections::HashMap, fmt::Display};
 
 use crate::script::ast::*;
 
-use super::tokens::{Token, TokenType};
+use super::{diagnostic::Span, tokens::{Token, TokenType}};
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
@@ -13,18 +13,15 @@ pub fn parse_ast(tokens: Vec<Token>) -> Result<Ast, Vec<ParseError>> {
     let     logger = Logger::new();
 
     let mut statements = vec![];
-    let mut errors     = vec![];
 
     while !parser.is_eof() {
-        match parser.parse_declaration(&logger) {
-            Ok(Some(stmt)) => statements.push(stmt),
-            Ok(None)       => {},
-            Err(err)       => errors    .push(err),
+        if let Ok(Some(stmt)) = parser.parse_declaration(&logger) {
+            statements.push(stmt);
         }
     }
 
-    if errors.len() > 0 {
-        Err(errors)
+    if parser.errors.len() > 0 {
+        Err(parser.errors)
     }
     else {
         Ok(Ast { stmts: statements })
@@ -35,6 +32,12 @@ pub struct Parser {
     tokens:  Vec<Token>,
     current: usize,
 
+    // errors recovered from so far
+    errors:      Vec<ParseError>,
+
+    // how many blocks (and class bodies) are being parsed
+    block_depth: usize,
+
     parse_table: HashMap<TokenType, ParseRule>,
 }
 
@@ -55,8 +58,11 @@ pub enum ParseErrorType {
     MissingFunctionOpenParen (FunctionType),
     MissingFunctionOpenBrace (FunctionType),
     MissingFunctionCloseParen,
+    MissingLambdaOpenParen,
     FunctionTooManyParameters,
     MissingParameterIdentifier,
+    MissingParameterDefault,
+    RestParameterNotLast,
     MissingVariableIdentifier,
     MissingVariableSemicolon,
     MissingForOpenParen,
@@ -66,6 +72,13 @@ pub enum ParseErrorType {
     MissingIfCloseParen,
     MissingPrintSemicolon,
     MissingReturnSemicolon,
+    MissingThrowSemicolon,
+    MissingTryOpenBrace,
+    MissingCatch,
+    MissingCatchOpenParen,
+    MissingCatchIdentifier,
+    MissingCatchCloseParen,
+    MissingCatchOpenBrace,
     MissingWhileOpenParen,
     MissingWhileCloseParen,
     MissingExpressionStmtSemicolon,
@@ -75,7 +88,20 @@ pub enum ParseErrorType {
     MissingSuperDot,
     MissingSuperPropertyIdentifier,
     MissingGroupingCloseParen,
-    MissingExpression(Token),
+    MissingInterpolationCloseBrace,
+    MissingTernaryColon,
+    MissingCallCloseParen,
+    CallTooManyArguments,
+    PositionalAfterNamedArgument,
+    RepeatedNamedArgument(String),
+    MissingExpression(String),
+    MissingImportPath,
+    MissingImportAs,
+    MissingImportAlias,
+    MissingImportName,
+    MissingImportCloseBrace,
+    MissingImportFrom,
+    MissingImportSemicolon,
 }
 
 #[derive(Debug)]
@@ -89,12 +115,80 @@ type Prec = Precidence;
 type Pe = ParseErrorType;
 type Tt = TokenType;
 
+const ASSIGNMENT_OPERATORS: &[Tt] = &[Tt::Equal, Tt::PlusEqual, Tt::MinusEqual, Tt::StarEqual, Tt::SlashEqual];
+
 #[derive(Debug, Clone, Copy)]
 pub enum FunctionType {
     Function,
     Method,
 }
 
+impl Display for ParseErrorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pe::MissingClassIdentifier                  => write!(f, "Expect class name"),
+            Pe::MissingSuperclassIdentifier             => write!(f, "Expect superclass name"),
+            Pe::MissingClassOpenCurly                   => write!(f, "Expect '{{' before class body"),
+            Pe::MissingClassCloseCurly                  => write!(f, "Expect '}}' after class body"),
+            Pe::MissingFunctionIdentifier(type_)        => write!(f, "Expect {} name",             type_.to_string()),
+            Pe::MissingFunctionOpenParen (type_)        => write!(f, "Expect '(' after {} name",   type_.to_string()),
+            Pe::MissingFunctionOpenBrace (type_)        => write!(f, "Expect '{{' before {} body", type_.to_string()),
+            Pe::MissingFunctionCloseParen               => write!(f, "Expect ')' after parameters"),
+            Pe::MissingLambdaOpenParen                  => write!(f, "Expect '(' after 'fun'"),
+            Pe::FunctionTooManyParameters               => write!(f, "Can't have more than 255 parameters"),
+            Pe::MissingParameterIdentifier              => write!(f, "Expect parameter name"),
+            Pe::MissingParameterDefault                 => write!(f, "Expect a default value for a parameter after one with a default"),
+            Pe::RestParameterNotLast                    => write!(f, "Expect ')' after the rest parameter, it must be the last one"),
+            Pe::MissingVariableIdentifier               => write!(f, "Expect variable name"),
+            Pe::MissingVariableSemicolon                => write!(f, "Expect ';' after variable declaration"),
+            Pe::MissingForOpenParen                     => write!(f, "Expect '(' after 'for'"),
+            Pe::MissingForCloseParen                    => write!(f, "Expect ')' after for clauses"),
+            Pe::MissingForConditionDelimiter            => write!(f, "Expect ';' after loop condition"),
+            Pe::MissingIfOpenParen                      => write!(f, "Expect '(' after 'if'"),
+            Pe::MissingIfCloseParen                     => write!(f, "Expect ')' after if condition"),
+            Pe::MissingPrintSemicolon                   => write!(f, "Expect ';' after print"),
+            Pe::MissingReturnSemicolon                  => write!(f, "Expect ';' after return value"),
+            Pe::MissingThrowSemicolon                   => write!(f, "Expect ';' after thrown value"),
+            Pe::MissingTryOpenBrace                     => write!(f, "Expect '{{' after 'try'"),
+            Pe::MissingCatch                            => write!(f, "Expect 'catch' after try block"),
+            Pe::MissingCatchOpenParen                   => write!(f, "Expect '(' after 'catch'"),
+            Pe::MissingCatchIdentifier                  => write!(f, "Expect a name for the caught value"),
+            Pe::MissingCatchCloseParen                  => write!(f, "Expect ')' after the caught value's name"),
+            Pe::MissingCatchOpenBrace                   => write!(f, "Expect '{{' before catch body"),
+            Pe::MissingWhileOpenParen                   => write!(f, "Expect '(' after while"),
+            Pe::MissingWhileCloseParen                  => write!(f, "Expect ')' after condition"),
+            Pe::MissingExpressionStmtSemicolon          => write!(f, "Expect ';' after expression"),
+            Pe::MissingBlockCloseBrace                  => write!(f, "Expect '}}' after block"),
+            Pe::MissingPropertyIdentifier               => write!(f, "Expect property name after '.'"),
+            Pe::MissingSuperDot                         => write!(f, "Expect '.' after super"),
+            Pe::MissingSuperPropertyIdentifier          => write!(f, "Expect superclass method name"),
+            Pe::MissingGroupingCloseParen               => write!(f, "Expect ')' after expression"),
+            Pe::MissingInterpolationCloseBrace          => write!(f, "Expect '}}' after the interpolated expression"),
+            Pe::MissingTernaryColon                     => write!(f, "Expect ':' after the '?' branch"),
+            Pe::MissingCallCloseParen                   => write!(f, "Expect ')' after arguments"),
+            Pe::CallTooManyArguments                    => write!(f, "Can't have more than 255 arguments"),
+            Pe::PositionalAfterNamedArgument            => write!(f, "Expect named arguments to come after positional ones"),
+            Pe::RepeatedNamedArgument(name)             => write!(f, "Argument '{}' given more than once", name),
+            Pe::MissingExpression(lexeme) if lexeme.is_empty() => write!(f, "Expect expression, found end of file"),
+            Pe::MissingExpression(lexeme)               => write!(f, "Expect expression, found '{}'", lexeme),
+            Pe::MissingImportPath                       => write!(f, "Expect a module path string"),
+            Pe::MissingImportAs                         => write!(f, "Expect 'as' after module path"),
+            Pe::MissingImportAlias                      => write!(f, "Expect module name after 'as'"),
+            Pe::MissingImportName                       => write!(f, "Expect a name to import"),
+            Pe::MissingImportCloseBrace                 => write!(f, "Expect '}}' after imported names"),
+            Pe::MissingImportFrom                       => write!(f, "Expect 'from' after imported names"),
+            Pe::MissingImportSemicolon                  => write!(f, "Expect ';' after import"),
+            Pe::InvalidAssignmentTarget(target)         => {
+                type T = AssignmentTarget;
+                match target {
+                    T::Dot  => write!(f, "Invalid assignment target for '.'"),
+                    T::Expr => write!(f, "Invalid assignment target for expression"),
+                }
+            },
+        }
+    }
+}
+
 impl FunctionType {
     pub fn to_string(&self) -> &str {
         match self {
@@ -107,14 +201,21 @@ impl FunctionType {
 #[derive(PartialEq, PartialOrd, Clone, Copy)]
 enum Precidence {
     None,
-    Assignment, // =
+    Assignment, // = += -= *= /=
+    Ternary,    // ?:
+    Coalesce,   // ??
     Or,         // or
     And,        // and
     Equality,   // == !=
     Comparison, // < > <= >=
+    BitOr,      // |
+    BitXor,     // ^
+    BitAnd,     // &
+    Shift,      // << >>
     Term,       // + -
-    Factor,     // * /
+    Factor,     // * / ~/ %
     Unary,      // ! -
+    Exponent,   // **
     Call,       // . ()
     Primary
 }
@@ -123,14 +224,21 @@ impl Precidence {
     fn next(&self) -> Precidence {
         match self {
             Prec::None       => Prec::Assignment,
-            Prec::Assignment => Prec::Or,
+            Prec::Assignment => Prec::Ternary,
+            Prec::Ternary    => Prec::Coalesce,
+            Prec::Coalesce   => Prec::Or,
             Prec::Or         => Prec::And,
             Prec::And        => Prec::Equality,
             Prec::Equality   => Prec::Comparison,
-            Prec::Comparison => Prec::Term,
+            Prec::Comparison => Prec::BitOr,
+            Prec::BitOr      => Prec::BitXor,
+            Prec::BitXor     => Prec::BitAnd,
+            Prec::BitAnd     => Prec::Shift,
+            Prec::Shift      => Prec::Term,
             Prec::Term       => Prec::Factor,
             Prec::Factor     => Prec::Unary,
-            Prec::Unary      => Prec::Call,
+            Prec::Unary      => Prec::Exponent,
+            Prec::Exponent   => Prec::Call,
             Prec::Call       => Prec::Primary,
             Prec::Primary    => panic!("Unknown precidence")
         }
@@ -164,6 +272,11 @@ impl RuleArgs {
             target:     self.target.take(),
         }
     }
+
+    // the left hand side of an infix rule, which `parse_precedence` always provides
+    fn infix_target(&mut self) -> Expr {
+        self.target.take().expect("Infix rules must be given a target")
+    }
 }
 
 impl ParseRule {
@@ -185,7 +298,9 @@ impl Parser {
     fn new(tokens: Vec<Token>) -> Self {
         Self {
             tokens,
-            current: 0,
+            current:     0,
+            errors:      vec![],
+            block_depth: 0,
             parse_table: Self::parse_table(),
         }
     }
@@ -194,33 +309,45 @@ impl Parser {
     fn parse_table() -> HashMap<TokenType, ParseRule> {
 
         HashMap::from([
-            (Tt::LeftParen,    ParseRule::new(Some(Self::parse_grouping_expr), Some(Self::parse_call_expr) ,   Prec::Call)),
-            (Tt::Dot,          ParseRule::new(None,                            Some(Self::parse_dot_expr),     Prec::Call)),
-            (Tt::Minus,        ParseRule::new(Some(Self::parse_unary_expr),    Some(Self::parse_binary_expr),  Prec::Term)),
-            (Tt::Plus,         ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Term)),
-            (Tt::Slash,        ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Factor)),
-            (Tt::Star,         ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Factor)),
-            (Tt::Bang,         ParseRule::new(Some(Self::parse_unary_expr),    None,                           Prec::None)),
-            (Tt::BangEqual,    ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Equality)),
-            (Tt::EqualEqual,   ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Equality)),
-            (Tt::Greater,      ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Comparison)),
-            (Tt::GreaterEqual, ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Comparison)),
-            (Tt::Less,         ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Comparison)),
-            (Tt::LessEqual,    ParseRule::new(None,                            Some(Self::parse_binary_expr),  Prec::Comparison)),
-            (Tt::Identifier,   ParseRule::new(Some(Self::parse_variable_expr), None,                           Prec::None)),
-            (Tt::String,       ParseRule::new(Some(Self::parse_literal_expr),  None,                           Prec::None)),
-            (Tt::Number,       ParseRule::new(Some(Self::parse_literal_expr),  None,                           Prec::None)),
-            (Tt::And,          ParseRule::new(None,                            Some(Self::parse_and_expr),     Prec::And)),
-            (Tt::Or,           ParseRule::new(None,                            Some(Self::parse_or_expr),      Prec::Or)),
-            (Tt::True,         ParseRule::new(Some(Self::parse_literal_expr),  None,                           Prec::None)),
-            (Tt::False,        ParseRule::new(Some(Self::parse_literal_expr),  None,                           Prec::None)),
-            (Tt::Nil,          ParseRule::new(Some(Self::parse_literal_expr),  None,                           Prec::None)),
-            (Tt::Super,        ParseRule::new(Some(Self::parse_super_expr),    None,                           Prec::None)),
-            (Tt::This,         ParseRule::new(Some(Self::parse_this_expr),     None,                           Prec::None)),
-            (Tt::Comma,        ParseRule::new(None,                            None,                           Prec::None)),
-            (Tt::Semicolon,    ParseRule::new(None,                            None,                           Prec::None)),
-            (Tt::RightBrace,   ParseRule::new(None,                            None,                           Prec::None)),
-            (Tt::RightParen,   ParseRule::new(None,                            None,                           Prec::None)),
+            (Tt::LeftParen,        ParseRule::new(Some(Self::parse_grouping_expr), Some(Self::parse_call_expr) ,      Prec::Call)),
+            (Tt::Dot,              ParseRule::new(None,                            Some(Self::parse_dot_expr),        Prec::Call)),
+            (Tt::Minus,            ParseRule::new(Some(Self::parse_unary_expr),    Some(Self::parse_binary_expr),     Prec::Term)),
+            (Tt::Plus,             ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Term)),
+            (Tt::Slash,            ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Factor)),
+            (Tt::Star,             ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Factor)),
+            (Tt::TildeSlash,       ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Factor)),
+            (Tt::Percent,          ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Factor)),
+            (Tt::Star2,            ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Exponent)),
+            (Tt::Ampersand,        ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::BitAnd)),
+            (Tt::Pipe,             ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::BitOr)),
+            (Tt::Caret,            ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::BitXor)),
+            (Tt::LessLess,         ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Shift)),
+            (Tt::GreaterGreater,   ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Shift)),
+            (Tt::Bang,             ParseRule::new(Some(Self::parse_unary_expr),    None,                              Prec::None)),
+            (Tt::BangEqual,        ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Equality)),
+            (Tt::EqualEqual,       ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Equality)),
+            (Tt::Greater,          ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Comparison)),
+            (Tt::GreaterEqual,     ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Comparison)),
+            (Tt::Less,             ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Comparison)),
+            (Tt::LessEqual,        ParseRule::new(None,                            Some(Self::parse_binary_expr),     Prec::Comparison)),
+            (Tt::Identifier,       ParseRule::new(Some(Self::parse_variable_expr), None,                              Prec::None)),
+            (Tt::String,           ParseRule::new(Some(Self::parse_literal_expr),  None,                              Prec::None)),
+            (Tt::Interpolation,    ParseRule::new(Some(Self::parse_string_expr),   None,                              Prec::None)),
+            (Tt::Number,           ParseRule::new(Some(Self::parse_literal_expr),  None,                              Prec::None)),
+            (Tt::And,              ParseRule::new(None,                            Some(Self::parse_and_expr),        Prec::And)),
+            (Tt::Or,               ParseRule::new(None,                            Some(Self::parse_or_expr),         Prec::Or)),
+            (Tt::QuestionQuestion, ParseRule::new(None,                            Some(Self::parse_coalesce_expr),   Prec::Coalesce)),
+            (Tt::Question,         ParseRule::new(None,                            Some(Self::parse_ternary_expr),    Prec::Ternary)),
+            (Tt::True,             ParseRule::new(Some(Self::parse_literal_expr),  None,                              Prec::None)),
+            (Tt::False,            ParseRule::new(Some(Self::parse_literal_expr),  None,                              Prec::None)),
+            (Tt::Nil,              ParseRule::new(Some(Self::parse_literal_expr),  None,                              Prec::None)),
+            (Tt::Super,            ParseRule::new(Some(Self::parse_super_expr),    None,                              Prec::None)),
+            (Tt::This,             ParseRule::new(Some(Self::parse_this_expr),     None,                              Prec::None)),
+            (Tt::Fun,              ParseRule::new(Some(Self::parse_lambda_expr),   None,                              Prec::None)),
+            (Tt::Comma,            ParseRule::new(None,                            None,                              Prec::None)),
+            (Tt::Semicolon,        ParseRule::new(None,                            None,                              Prec::None)),
+            (Tt::RightBrace,       ParseRule::new(None,                            None,                              Prec::None)),
+            (Tt::RightParen,       ParseRule::new(None,                            None,                              Prec::None)),
         ])
     }
 
@@ -230,11 +357,15 @@ impl Parser {
     fn parse_declaration(&mut self, logger: &Logger) -> ParseResult<Option<Stmt>> {
         logger.log("parse_declaration", self.peek().clone(), || {
 
+            let start = self.current;
+
             let result: Option<ParseResult<Stmt>> = match self.advance().type_ {
 
                 Tt::Class     => Some(self.parse_class_decl(logger)),
-                Tt::Fun       => Some(self.parse_function_decl(FunctionType::Function, logger).map(|f| Stmt::Function(f))),
+                // `fun (` starts a lambda in an expression statement
+                Tt::Fun if !self.check(Tt::LeftParen) => Some(self.parse_function_decl(FunctionType::Function, logger).map(|f| Stmt::Function(f))),
                 Tt::Var       => Some(self.parse_var_decl  (logger)),
+                Tt::Import    => Some(self.parse_import_decl(logger)),
                 Tt::Semicolon => None,
 
                 _ => {
@@ -245,11 +376,20 @@ impl Parser {
 
             let Some(result) = result else { return Ok(None) };
 
-            if result.is_err() {
-                self.synchronize();
-            }
+            match result {
+                Ok (stmt) => Ok(Some(stmt)),
+                Err(err)  => {
+                    self.errors.push(err);
+                    self.synchronize();
 
-            result.map(|r| Some (r))
+                    // always make progress, so a bad token can't be reported forever
+                    if self.current == start {
+                        self.advance();
+                    }
+
+                    Ok(None)
+                }
+            }
 
         })
 
@@ -258,6 +398,7 @@ impl Parser {
     fn parse_class_decl(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_class_decl", self.peek().clone(), || {
 
+            let start = self.previous();
             let name = self.consume(Tt::Identifier, Pe::MissingClassIdentifier)?;
 
             let mut superclass = None;
@@ -271,48 +412,104 @@ impl Parser {
             self.consume(Tt::LeftBrace, Pe::MissingClassOpenCurly)?;
 
             let mut methods = vec![];
+            self.block_depth += 1;
+
             while !self.check(Tt::RightBrace) && !self.is_eof() {
-                methods.push(self.parse_function_decl(FunctionType::Method, logger)?);
+                match self.parse_function_decl(FunctionType::Method, logger) {
+                    Ok (method) => methods.push(method),
+                    Err(err)    => {
+                        self.errors.push(err);
+                        self.synchronize_method();
+                    }
+                }
             }
 
+            self.block_depth -= 1;
             self.consume(Tt::RightBrace, Pe::MissingClassCloseCurly)?;
 
-            Ok(Class::new(name, superclass, methods))
+            Ok(Class::new(name, superclass, methods, self.span_from(&start)))
         })
     }
 
     fn parse_function_decl(&mut self, type_: FunctionType, logger: &Logger) -> ParseResult<FunctionStmt> {
         logger.log("parse_function_decl", self.peek().clone(), || {
 
+            // methods don't have a `fun` keyword
+            let start = match type_ {
+                FunctionType::Function => self.previous(),
+                FunctionType::Method   => self.peek().clone(),
+            };
+
             let name = self.consume(Tt::Identifier, Pe::MissingFunctionIdentifier(type_))?;
 
             self.consume(Tt::LeftParen, Pe::MissingFunctionOpenParen(type_))?;
 
-            let mut params = vec![];
-            if !self.check(Tt::RightParen) {
+            let params = self.parse_parameters(logger)?;
+            self.consume(Tt::LeftBrace,  Pe::MissingFunctionOpenBrace(type_))?;
 
-                params.push(self.consume(Tt::Identifier, Pe::MissingParameterIdentifier)?);
+            let body = self.parse_block_statement(logger)?;
 
-                while self.match_(&[Tt::Comma]) {
-                    if params.len() > 255 {
-                        return Err(self.error(Pe::FunctionTooManyParameters))
-                    }
-                    params.push(self.consume(Tt::Identifier, Pe::MissingParameterIdentifier)?);
+            Ok(FunctionStmt::new(name, params, *body.stmts, self.span_from(&start)))
+        })
+    }
+
+    // the parameters after the `(`, up to and including the `)`
+    fn parse_parameters(&mut self, logger: &Logger) -> ParseResult<Vec<FunctionParam>> {
+
+        let mut params: Vec<FunctionParam> = vec![];
+        if !self.check(Tt::RightParen) {
+            loop {
+                if params.len() == 255 {
+                    let err = self.error(Pe::FunctionTooManyParameters);
+                    self.errors.push(err);
+                }
+
+                let param = self.parse_parameter(logger)?;
+
+                // a call can't leave out a parameter before one it passes by position
+                let after_default = params.last().is_some_and(|last| last.default.is_some());
+
+                if after_default && param.default.is_none() && !param.rest {
+                    let err = self.error(Pe::MissingParameterDefault);
+                    self.errors.push(err);
+                }
+
+                let rest = param.rest;
+                params.push(param);
+
+                if rest || !self.match_(&[Tt::Comma]) {
+                    break;
                 }
             }
+        }
 
-            self.consume(Tt::RightParen, Pe::MissingFunctionCloseParen)?;
-            self.consume(Tt::LeftBrace,  Pe::MissingFunctionOpenBrace(type_))?;
+        let close = match params.last().is_some_and(|last| last.rest) {
+            true  => Pe::RestParameterNotLast,
+            false => Pe::MissingFunctionCloseParen,
+        };
+        self.consume(Tt::RightParen, close)?;
 
-            let body = self.parse_block_statement(logger)?;
+        Ok(params)
+    }
 
-            Ok(FunctionStmt::new(name, params, *body.stmts))
-        })
+    // `name`, `name = default` or `...name`
+    fn parse_parameter(&mut self, logger: &Logger) -> ParseResult<FunctionParam> {
+
+        let rest = self.match_(&[Tt::DotDotDot]);
+        let name = self.consume(Tt::Identifier, Pe::MissingParameterIdentifier)?;
+
+        let default = match !rest && self.match_(&[Tt::Equal]) {
+            true  => Some(self.parse_expression(None, logger)?),
+            false => None,
+        };
+
+        Ok(FunctionParam::new(name, default, rest))
     }
 
     fn parse_var_decl(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_var_decl", self.peek().clone(), || {
 
+            let start = self.previous();
             let name = self.consume(Tt::Identifier, Pe::MissingVariableIdentifier)?;
 
             let initializer = if self.match_(&[Tt::Equal]) {
@@ -323,7 +520,42 @@ impl Parser {
 
             self.consume(Tt::Semicolon, Pe::MissingVariableSemicolon)?;
 
-            Ok(VarStmt::new(name, initializer))
+            Ok(VarStmt::new(name, initializer, self.span_from(&start)))
+        })
+    }
+
+    // `import "path" as name;` or `import { a, b } from "path";`
+    //
+    // `as` and `from` aren't keywords, so they can still be used as names
+    fn parse_import_decl(&mut self, logger: &Logger) -> ParseResult<Stmt> {
+        logger.log("parse_import_decl", self.peek().clone(), || {
+
+            let start = self.previous();
+
+            if self.match_(&[Tt::LeftBrace]) {
+                let mut names = vec![self.consume(Tt::Identifier, Pe::MissingImportName)?];
+
+                while self.match_(&[Tt::Comma]) {
+                    names.push(self.consume(Tt::Identifier, Pe::MissingImportName)?);
+                }
+
+                self.consume(Tt::RightBrace, Pe::MissingImportCloseBrace)?;
+                self.consume_word("from", Pe::MissingImportFrom)?;
+
+                let path = self.consume(Tt::String, Pe::MissingImportPath)?;
+                self.consume(Tt::Semicolon, Pe::MissingImportSemicolon)?;
+
+                return Ok(ImportStmt::new(path, None, names, self.span_from(&start)));
+            }
+
+            let path = self.consume(Tt::String, Pe::MissingImportPath)?;
+
+            self.consume_word("as", Pe::MissingImportAs)?;
+            let alias = self.consume(Tt::Identifier, Pe::MissingImportAlias)?;
+
+            self.consume(Tt::Semicolon, Pe::MissingImportSemicolon)?;
+
+            Ok(ImportStmt::new(path, Some(alias), vec![], self.span_from(&start)))
         })
     }
 
@@ -339,6 +571,8 @@ impl Parser {
                 Tt::If        => self.parse_if_statement    (logger),
                 Tt::Print     => self.parse_print_statement (logger),
                 Tt::Return    => self.parse_return_statement(logger),
+                Tt::Throw     => self.parse_throw_statement (logger),
+                Tt::Try       => self.parse_try_statement   (logger),
                 Tt::While     => self.parse_while_statement (logger),
                 Tt::LeftBrace => self.parse_block_statement (logger).map(|block| Stmt::Block(block)),
                 _ => {
@@ -352,25 +586,73 @@ impl Parser {
     fn parse_block_statement(&mut self, logger: &Logger) -> ParseResult<Block> {
         logger.log("parse_block_statement", self.peek().clone(), || {
 
+            let start = self.previous();
             let mut statements = vec![];
 
+            self.block_depth += 1;
+
             while !self.check(Tt::RightBrace) && !self.is_eof() {
                 self.parse_declaration(logger)?.map(|stmt| statements.push(stmt));
             }
 
+            self.block_depth -= 1;
             self.consume(Tt::RightBrace, Pe::MissingBlockCloseBrace)?;
 
-            Ok(Block { stmts: Box::new(statements), locals: 0})
+            Ok(Block { stmts: Box::new(statements), locals: 0, span: self.span_from(&start) })
         })
     }
 
     fn parse_for_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_for_statement", self.peek().clone(), || {
 
+            let start = self.previous();
+
+            let (initializer, condition, increment) = match self.parse_for_clauses(logger) {
+                Ok (clauses) => clauses,
+                Err(err)     => {
+                    // the clauses may contain semicolons, so skip them here
+                    // rather than leaving it to `synchronize`, and still check the body
+                    self.errors.push(err);
+                    self.skip_past_close_paren();
+
+                    (None, None, None)
+                }
+            };
+
+            let mut body = self.parse_statement(logger)?;
+            let span     = self.span_from(&start);
+
+            if let Some(increment) = increment {
+                let increment_span = increment.span();
+
+                body = Block::new(vec![
+                    body,
+                    ExpressionStmt::new(increment, increment_span)
+                ], span);
+            }
+
+            let condition = condition.unwrap_or(
+                Literal::new(Token::new_true().with_span(start.span))
+            );
+
+            let body = WhileStmt::new(condition, body, span);
+
+            let body = match initializer {
+                None       => body,
+                Some(init) => Block::new(vec![init, body], span),
+            };
+
+            Ok(body)
+        })
+    }
+
+    fn parse_for_clauses(&mut self, logger: &Logger) -> ParseResult<(Option<Stmt>, Option<Expr>, Option<Expr>)> {
+        logger.log("parse_for_clauses", self.peek().clone(), || {
+
             self.consume(Tt::LeftParen, Pe::MissingForOpenParen)?;
 
             let initializer = match self.peek().type_ {
-                Tt::Semicolon => None,
+                Tt::Semicolon => { self.advance(); None },
                 Tt::Var       => { self.advance(); Some(self.parse_var_decl(logger)?)},
                 _             =>                   Some(self.parse_expression_statement(logger)?),
             };
@@ -390,34 +672,14 @@ impl Parser {
 
             self.consume(Tt::RightParen, Pe::MissingForCloseParen)?;
 
-            let mut body = self.parse_statement(logger)?;
-
-            if let Some(increment) = increment {
-                body = Block::new(vec![
-                    body,
-                    ExpressionStmt::new(increment)
-                ]);
-            }
-
-            let condition = condition.unwrap_or(
-                Literal::new(Token::new_true())
-            );
-
-            let body = WhileStmt::new(condition, body);
-
-            let body = match initializer {
-                None       => body,
-                Some(init) => Block::new(vec![init, body]),
-            };
-
-            Ok(body)
+            Ok((initializer, condition, increment))
         })
     }
 
-
     fn parse_if_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_if_statement", self.peek().clone(), || {
 
+            let start = self.previous();
             self.consume(Tt::LeftParen, Pe::MissingIfOpenParen)?;
             let condition = self.parse_expression(None, logger)?;
             self.consume(Tt::RightParen, Pe::MissingIfCloseParen)?;
@@ -430,18 +692,19 @@ impl Parser {
                 None
             };
 
-            Ok(IfStmt::new(condition, then_branch, else_branch))
+            Ok(IfStmt::new(condition, then_branch, else_branch, self.span_from(&start)))
         })
     }
 
     fn parse_print_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_print_statement", self.peek().clone(), || {
 
+            let start = self.previous();
             let value = self.parse_expression(None, logger)?;
 
             self.consume(Tt::Semicolon, Pe::MissingPrintSemicolon)?;
 
-            Ok(PrintStmt::new(value))
+            Ok(PrintStmt::new(value, self.span_from(&start)))
         })
     }
 
@@ -457,30 +720,68 @@ impl Parser {
 
             self.consume(Tt::Semicolon, Pe::MissingReturnSemicolon)?;
 
-            Ok(ReturnStmt::new(keyword, value))
+            let span = self.span_from(&keyword);
+
+            Ok(ReturnStmt::new(keyword, value, span))
+        })
+    }
+
+    fn parse_throw_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
+        logger.log("parse_throw_statement", self.peek().clone(), || {
+
+            let keyword = self.previous();
+            let value   = self.parse_expression(None, logger)?;
+
+            self.consume(Tt::Semicolon, Pe::MissingThrowSemicolon)?;
+
+            let span = self.span_from(&keyword);
+
+            Ok(ThrowStmt::new(keyword, value, span))
+        })
+    }
+
+    fn parse_try_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
+        logger.log("parse_try_statement", self.peek().clone(), || {
+
+            let start = self.previous();
+
+            self.consume(Tt::LeftBrace, Pe::MissingTryOpenBrace)?;
+            let body = self.parse_block_statement(logger)?;
+
+            self.consume(Tt::Catch,      Pe::MissingCatch)?;
+            self.consume(Tt::LeftParen,  Pe::MissingCatchOpenParen)?;
+            let name = self.consume(Tt::Identifier, Pe::MissingCatchIdentifier)?;
+            self.consume(Tt::RightParen, Pe::MissingCatchCloseParen)?;
+
+            self.consume(Tt::LeftBrace,  Pe::MissingCatchOpenBrace)?;
+            let handler = self.parse_block_statement(logger)?;
+
+            Ok(TryStmt::new(body, name, handler, self.span_from(&start)))
         })
     }
 
     fn parse_while_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_while_statement", self.peek().clone(), || {
 
+            let start = self.previous();
             self.consume(Tt::LeftParen, Pe::MissingWhileOpenParen)?;
             let condition = self.parse_expression(None, logger)?;
             self.consume(Tt::RightParen, Pe::MissingWhileCloseParen)?;
 
             let body = self.parse_statement(logger)?;
 
-            Ok(WhileStmt::new(condition, body))
+            Ok(WhileStmt::new(condition, body, self.span_from(&start)))
         })
     }
 
     fn parse_expression_statement(&mut self, logger: &Logger) -> ParseResult<Stmt> {
         logger.log("parse_expression_statement", self.peek().clone(), || {
 
-            let expr = self.parse_expression(None, logger)?;
+            let start = self.peek().clone();
+            let expr  = self.parse_expression(None, logger)?;
             self.consume(Tt::Semicolon, Pe::MissingExpressionStmtSemicolon)?;
 
-            Ok(ExpressionStmt::new(expr))
+            Ok(ExpressionStmt::new(expr, self.span_from(&start)))
         })
     }
 
@@ -493,24 +794,30 @@ impl Parser {
     fn parse_precedence(&mut self, prec: Precidence, mut target: Option<Expr>, logger: &Logger) -> ParseResult<Expr> {
         logger.log("parse_precedence", self.peek().clone(), || {
 
-            let op   = self.advance();
-            let rule = self.get_rule(op.type_)?;
-
             let can_assign = prec <= Prec::Assignment;
 
+            // don't consume a token that can't start an expression, it likely closes or ends something else
+            let Some(prefix) = self.get_rule(self.peek().type_).prefix else {
+                let lexeme = self.peek().lexeme.to_owned();
+                return Err(self.error_at_current(Pe::MissingExpression(lexeme)))
+            };
+
+            self.advance();
 
-            let prefix = rule.prefix.ok_or_else(|| self.panic("Missing Prefix Rule"))?;
             let mut target = prefix(self, RuleArgs {
                 can_assign,
                 target: target.take(),
             }, logger)?;
 
 
-            while prec <= self.get_rule(self.peek().type_)?.precidence {
-                let op   = self.advance();
-                let rule = self.get_rule(op.type_)?;
+            loop {
+                let rule = self.get_rule(self.peek().type_);
 
-                let infix = rule.infix.ok_or_else(|| self.panic("Missing Infix Rule"))?;
+                let Some(infix) = rule.infix.filter(|_| prec <= rule.precidence) else {
+                    break;
+                };
+
+                self.advance();
 
                 target = infix(self, RuleArgs {
                     can_assign,
@@ -518,7 +825,7 @@ impl Parser {
                 }, logger)?;
             }
 
-            if can_assign && self.match_(&[Tt::Equal]) {
+            if can_assign && self.match_(ASSIGNMENT_OPERATORS) {
                 type T = AssignmentTarget;
                 return Err(self.error(Pe::InvalidAssignmentTarget(T::Expr)))
             }
@@ -532,57 +839,77 @@ impl Parser {
     fn parse_grouping_expr(&mut self, _: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
         logger.log("parse_grouping_expr", self.peek().clone(), || {
 
-            let expr = self.parse_expression(None, logger)?;
+            if self.is_arrow_lambda() {
+                return self.parse_arrow_expr(logger);
+            }
+
+            let start = self.previous();
+            let expr  = self.parse_expression(None, logger)?;
 
             self.consume(Tt::RightParen, Pe::MissingGroupingCloseParen)?;
 
-            Ok(expr)
+            Ok(Grouping::new(expr, self.span_from(&start)))
+        })
+    }
+
+    // `(a, b) => expr`, the body is desugared into returning the expression
+    fn parse_arrow_expr(&mut self, logger: &Logger) -> ParseResult<Expr> {
+        logger.log("parse_arrow_expr", self.peek().clone(), || {
+
+            let start  = self.previous();
+            let params = self.parse_parameters(logger)?;
+            let arrow  = self.advance();
+            let value  = self.parse_expression(None, logger)?;
+
+            let span = self.span_from(&start);
+            let body = vec![ReturnStmt::new(arrow, Some(value), span)];
+
+            Ok(Lambda::new(FunctionStmt::new(lambda_name(&start), params, body, span), true))
+        })
+    }
+
+    // `fun (a, b) { ... }`
+    fn parse_lambda_expr(&mut self, _: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
+        logger.log("parse_lambda_expr", self.peek().clone(), || {
+
+            let start = self.previous();
+            self.consume(Tt::LeftParen, Pe::MissingLambdaOpenParen)?;
+
+            let params = self.parse_parameters(logger)?;
+            self.consume(Tt::LeftBrace, Pe::MissingFunctionOpenBrace(FunctionType::Function))?;
+
+            let body = self.parse_block_statement(logger)?;
+            let func = FunctionStmt::new(lambda_name(&start), params, *body.stmts, self.span_from(&start));
+
+            Ok(Lambda::new(func, false))
         })
     }
 
     fn parse_call_expr(&mut self, mut rule_args: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
         logger.log("parse_call_expr", self.peek().clone(), || {
 
-            let callee = rule_args.target.take().ok_or_else(|| self.panic("Missing Callee for call expression"))?;
+            let callee = rule_args.infix_target();
 
-            let (paren, arguments) = self.parse_argument_list(logger)?;
+            let (paren, arguments, named) = self.parse_argument_list(logger)?;
 
-            Ok(Call::new(callee, paren, arguments))
+            Ok(Call::new(callee, paren, arguments, named))
         })
     }
 
     fn parse_dot_expr(&mut self, mut rule_args: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
         logger.log("parse_dot_expr", self.peek().clone(), || {
 
-            let target = rule_args.target.take().ok_or_else(|| self.panic("Missing target for dot expression"))?;
+            let target = rule_args.infix_target();
             let name   = self.consume(Tt::Identifier, Pe::MissingPropertyIdentifier)?;
 
-            let result = if rule_args.can_assign && self.match_(&[Tt::Equal]) {
-                let value = self.parse_expression(Some(target.clone()), logger)?;
-
-                match value {
-                    Expr::Variable(_)   => Ok(Assign::new(Variable::new(name), value)),
-                    Expr::Get     (get) => Ok(Set   ::new(target, name, *get.expr)),
-                    Expr::Literal (val) => Ok(Set   ::new(target, name, Expr::Literal(val))),
-
-                    _                   => {
-                        Err(self.error(Pe::InvalidAssignmentTarget(AssignmentTarget::Dot)))
-                    }
-                }
+            if rule_args.can_assign && self.match_(ASSIGNMENT_OPERATORS) {
+                let operator = self.previous();
+                let value    = self.parse_expression(None, logger)?;
 
+                return Ok(Set::new(target, name, operator, value));
             }
-            else if self.match_(&[Tt::LeftParen]) {
-                let (paren, arguments) = self.parse_argument_list(logger)?;
-                Ok(Call::new(target, paren, arguments))
-            }
-            else {
-                match target {
-                    Expr::Get(v) => Ok(Get::new(*v.expr, name)),
-                    _            => Ok(Get::new(target,  name)),
-                }
-            };
 
-            result
+            Ok(Get::new(target, name))
         })
     }
 
@@ -600,38 +927,77 @@ impl Parser {
         logger.log("parse_binary_expr", self.peek().clone(), || {
 
             let op   = self.previous();
-            let rule = self.get_rule(op.type_)?;
+            let rule = self.get_rule(op.type_);
+
+            // `**` is right associative, and its right operand can be negated (`2 ** -1`)
+            let right_prec = match op.type_ {
+                Tt::Star2 => Prec::Unary,
+                _         => rule.precidence.next(),
+            };
 
-            let left  = args.target.take().ok_or_else(|| self.panic("Missing left operand for binary expression"))?;
-            let right = self.parse_precedence(rule.precidence.next(), None, logger)?;
+            let left  = args.infix_target();
+            let right = self.parse_precedence(right_prec, None, logger)?;
 
             Ok(BinaryOperator::new(left, op, right))
         })
     }
 
 
-    fn parse_argument_list(&mut self, logger: &Logger) -> ParseResult<(Token, Vec<Expr>)> {
+    // positional arguments, then `name: value` ones
+    fn parse_argument_list(&mut self, logger: &Logger) -> ParseResult<(Token, Vec<Expr>, Vec<NamedArg>)> {
         logger.log("parse_argument_list", self.peek().clone(), || {
 
-            let mut args = vec![];
+            let mut args  = vec![];
+            let mut named = vec![];
 
             if !self.check(Tt::RightParen) {
+                loop {
+                    let arg_start = self.current;
 
-                args.push(self.parse_expression(None, logger)?);
+                    if args.len() + named.len() == 255 {
+                        let err = self.error_at_current(Pe::CallTooManyArguments);
+                        self.errors.push(err);
+                    }
 
-                while self.match_(&[Tt::Comma]) {
+                    let name = match self.check(Tt::Identifier) && self.peek_next_type() == Some(Tt::Colon) {
+                        true  => {
+                            let name = self.advance();
+                            self.advance();
+                            Some(name)
+                        }
+                        false => None,
+                    };
+
+                    if name.is_none() && !named.is_empty() {
+                        let err = self.error_at_current(Pe::PositionalAfterNamedArgument);
+                        self.errors.push(err);
+                    }
+
+                    let repeated = name.as_ref().filter(|name| named.iter().any(|arg: &NamedArg| arg.name.lexeme == name.lexeme));
+
+                    if let Some(name) = repeated {
+                        let err = ParseError { type_: Pe::RepeatedNamedArgument(name.lexeme.clone()), token: name.clone() };
+                        self.errors.push(err);
+                    }
 
-                    if args.len() > 255 {
-                        return Err(self.error(Pe::FunctionTooManyParameters));
+                    match (self.parse_expression(None, logger), name) {
+                        (Ok (value), Some(name)) => named.push(NamedArg { name, value }),
+                        (Ok (arg),   None)       => args .push(arg),
+                        (Err(err),   _)          => {
+                            self.errors.push(err);
+                            self.synchronize_argument(arg_start);
+                        }
                     }
 
-                    args.push(self.parse_expression(None, logger)?);
+                    if !self.match_(&[Tt::Comma]) {
+                        break;
+                    }
                 }
             }
 
-            let paren = self.consume(Tt::RightParen, Pe::MissingFunctionCloseParen)?;
+            let paren = self.consume(Tt::RightParen, Pe::MissingCallCloseParen)?;
 
-            Ok((paren, args))
+            Ok((paren, args, named))
         })
     }
 
@@ -641,9 +1007,10 @@ impl Parser {
             let name = self.previous();
             let target = Variable::new(name.clone());
 
-            if args.can_assign && self.match_(&[Tt::Equal]) {
-                let value  = self.parse_expression(None, logger)?;
-                Ok(Assign::new(target, value))
+            if args.can_assign && self.match_(ASSIGNMENT_OPERATORS) {
+                let operator = self.previous();
+                let value    = self.parse_expression(None, logger)?;
+                Ok(Assign::new(target, operator, value))
             }
             else {
                 Ok(target.as_expr())
@@ -651,6 +1018,29 @@ impl Parser {
         })
     }
 
+    // an interpolated string, the scanner having split it into the literals around each expression
+    fn parse_string_expr(&mut self, _: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
+        logger.log("parse_string_expr", self.peek().clone(), || {
+
+            let start     = self.previous();
+            let mut parts = vec![Literal::new(start.clone())];
+
+            loop {
+                parts.push(self.parse_expression(None, logger)?);
+
+                if self.match_(&[Tt::Interpolation]) {
+                    parts.push(Literal::new(self.previous()));
+                    continue;
+                }
+
+                let end = self.consume(Tt::String, Pe::MissingInterpolationCloseBrace)?;
+                parts.push(Literal::new(end));
+
+                return Ok(Interpolation::new(parts, self.span_from(&start)));
+            }
+        })
+    }
+
     fn parse_literal_expr(&mut self, _: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
         logger.log_no_children("parse_literal_expr", self.peek());
 
@@ -671,11 +1061,33 @@ impl Parser {
         })
     }
 
+    fn parse_coalesce_expr(&mut self, mut args: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
+        logger.log("parse_coalesce_expr", self.peek().clone(), || {
+
+            self.parse_logical_expr(args.take(), Prec::Coalesce, logger)
+        })
+    }
+
+    // `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+    fn parse_ternary_expr(&mut self, mut args: RuleArgs, logger: &Logger) -> ParseResult<Expr> {
+        logger.log("parse_ternary_expr", self.peek().clone(), || {
+
+            let condition   = args.infix_target();
+            let then_branch = self.parse_expression(None, logger)?;
+
+            self.consume(Tt::Colon, Pe::MissingTernaryColon)?;
+
+            let else_branch = self.parse_precedence(Prec::Ternary, None, logger)?;
+
+            Ok(Ternary::new(condition, then_branch, else_branch))
+        })
+    }
+
     fn parse_logical_expr(&mut self, mut args: RuleArgs, prec: Precidence, logger: &Logger) -> ParseResult<Expr> {
         logger.log("parse_logical_expr", self.peek().clone(), || {
 
             let operator = self.previous();
-            let left     = args.target.take().ok_or_else(|| self.panic("Missing target for logical expression"))?;
+            let left     = args.infix_target();
 
             let right = self.parse_precedence(prec, None, logger)?;
 
@@ -743,6 +1155,10 @@ impl Parser {
         &self.tokens[self.current]
     }
 
+    pub fn peek_next_type(&self) -> Option<TokenType> {
+        self.tokens.get(self.current + 1).map(|token| token.type_)
+    }
+
     pub fn previous(&self) -> Token {
         self.tokens[self.current -1].to_owned()
     }
@@ -752,7 +1168,25 @@ impl Parser {
             return Ok(self.advance().to_owned());
         }
 
-        Err(self.error(error_type))
+        // a missing `;` is reported at the end of the statement, as whatever is next is usually on the line below
+        match token_type {
+            Tt::Semicolon => Err(self.error           (error_type)),
+            _             => Err(self.error_at_current(error_type)),
+        }
+    }
+
+    /// The span from the start of `start` to the end of the last consumed token
+    // an identifier used as a contextual keyword
+    fn consume_word(&mut self, word: &str, error_type: ParseErrorType) -> ParseResult<Token> {
+        if self.check(Tt::Identifier) && self.peek().lexeme == word {
+            return Ok(self.advance().to_owned());
+        }
+
+        Err(self.error_at_current(error_type))
+    }
+
+    pub fn span_from(&self, start: &Token) -> Span {
+        start.span.to(self.previous().span)
     }
 
     pub fn error(&self, type_: ParseErrorType) -> ParseError {
@@ -762,19 +1196,28 @@ impl Parser {
         }
     }
 
-    pub fn panic(&self, msg: &str) -> ! {
-        eprintln!("Error while parsing Token: {}\n> {}", self.previous(), msg);
-
-        panic!()
+    // for errors about the next token, rather than the one just consumed
+    pub fn error_at_current(&self, type_: ParseErrorType) -> ParseError {
+        ParseError {
+            type_,
+            token: self.peek().clone(),
+        }
     }
 
-    fn get_rule(&self, op: TokenType) -> ParseResult<ParseRule> {
-        Ok(*self.parse_table
+    // tokens without a rule can't appear in, or continue, an expression
+    fn get_rule(&self, op: TokenType) -> ParseRule {
+        self.parse_table
             .get(&op)
-            .ok_or(self.error(Pe::MissingExpression(self.previous())))?
-        )
+            .copied()
+            .unwrap_or(ParseRule::new(None, None, Prec::None))
     }
 
+    // Panic mode recovery
+    //
+    // After an error, skip to where the next declaration most likely starts,
+    // so the rest of the source can still be checked without reporting errors caused by the first
+
+    // skips to the end of the current statement, or to the start of the next one
     fn synchronize(&mut self) {
 
         while !self.is_eof() {
@@ -786,11 +1229,25 @@ impl Parser {
                   Tt::Class
                   | Tt::Fun
                   | Tt::Var
+                  | Tt::Import
                   | Tt::For
                   | Tt::If
                   | Tt::While
                   | Tt::Print
-                  | Tt::Return => {
+                  | Tt::Return
+                  | Tt::Throw
+                  | Tt::Try => {
+                    return;
+                }
+
+                // leave the closing brace for the enclosing block
+                Tt::RightBrace if self.block_depth > 0 => {
+                    return;
+                }
+
+                // a block ends the statement it belongs to (such as a function with a broken signature)
+                Tt::LeftBrace => {
+                    self.skip_block();
                     return;
                 }
 
@@ -802,9 +1259,121 @@ impl Parser {
 
     }
 
+    // skips to the next method of a class body, or the end of the body
+    fn synchronize_method(&mut self) {
+        let start = self.current;
+
+        while !self.is_eof() {
+            match self.peek().type_ {
+                Tt::RightBrace => return,
+                Tt::LeftBrace  => {
+                    self.skip_block();
+                    return;
+                },
+
+                // the start of the next method
+                Tt::Identifier if self.current > start && self.peek_next_type() == Some(Tt::LeftParen) => return,
+
+                _ => { self.advance(); },
+            }
+        }
+    }
+
+    // skips to the next argument of a call, or the closing paren
+    //
+    // `arg_start` is the index of the first token of the argument, any parens opened since then are skipped over
+    fn synchronize_argument(&mut self, arg_start: usize) {
+        let mut depth = self.tokens[arg_start .. self.current].iter()
+            .map(|token| match token.type_ {
+                Tt::LeftParen  =>  1,
+                Tt::RightParen => -1,
+                _              =>  0,
+            })
+            .sum::<i32>()
+            .max(0);
+
+        while !self.is_eof() {
+            match self.peek().type_ {
+                Tt::Comma | Tt::RightParen if depth == 0 => return,
+
+                // the call is missing its closing paren, leave the rest of the statement to `synchronize`
+                Tt::Semicolon | Tt::LeftBrace | Tt::RightBrace => return,
+
+                Tt::LeftParen  => depth += 1,
+                Tt::RightParen => depth -= 1,
+                _              => {},
+            }
+
+            self.advance();
+        }
+    }
+
+    // skips a `{ ... }` block, including any nested blocks
+    fn skip_block(&mut self) {
+        let mut depth = 0;
+
+        while !self.is_eof() {
+            match self.advance().type_ {
+                Tt::LeftBrace  => depth += 1,
+                Tt::RightBrace => depth -= 1,
+                _              => {},
+            }
+
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    // whether the `(` just consumed has a `=>` after its matching `)`
+    fn is_arrow_lambda(&self) -> bool {
+        let mut depth = 1;
+
+        for (i, token) in self.tokens[self.current..].iter().enumerate() {
+            match token.type_ {
+                Tt::LeftParen  => depth += 1,
+                Tt::RightParen => depth -= 1,
+                Tt::EOF        => return false,
+                _              => {},
+            }
+
+            if depth == 0 {
+                return self.tokens.get(self.current + i + 1).is_some_and(|next| next.type_ == Tt::Arrow);
+            }
+        }
+
+        false
+    }
+
+    // skips past the paren closing one already consumed, stopping early at a block
+    fn skip_past_close_paren(&mut self) {
+        let mut depth = 1;
+
+        while !self.is_eof() {
+            match self.peek().type_ {
+                Tt::LeftBrace | Tt::RightBrace => return,
+
+                Tt::LeftParen  => depth += 1,
+                Tt::RightParen => depth -= 1,
+                _              => {},
+            }
+
+            self.advance();
+
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
 }
 
 
+// a lambda has no name of its own, stack traces call it `lambda`
+fn lambda_name(start: &Token) -> Token {
+    Token::new(Tt::Identifier, "lambda", start.line, start.col).with_span(start.span)
+}
+
 fn print_ind(ind: usize, msg: &str) {
     println!("{}{}", " ".repeat(ind * 4), msg);
 }
@@ -871,3 +1440,70 @@ impl Logger {
         println!("{ind}{name} ({token});");
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::script::{diagnostic::DiagnosticReport, scanner::scan_tokens, RunError};
+
+    use super::parse_ast;
+
+    // Renders the errors in a malformed script, and compares them against the `.expected` file beside it.
+    // Set `BLESS=1` to write the `.expected` file instead
+    fn assert_golden(file: &str) {
+        let path   = format!("./test_scripts/unit_tests/parser/{file}");
+        let source = fs::read_to_string(&path).unwrap();
+
+        let tokens = scan_tokens(&source).unwrap();
+        let errors = parse_ast(tokens).expect_err("expect the script to have parse errors");
+
+        let diagnostics = RunError::ParserError(errors).to_diagnostics(file, &source);
+        let report      = DiagnosticReport::new(source, diagnostics).to_string();
+
+        let expected_path = path.replace(".lox", ".expected");
+
+        if env::var("BLESS").is_ok() {
+            fs::write(&expected_path, &report).unwrap();
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap();
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn test_missing_semicolons() {
+        assert_golden("missing_semicolons.lox");
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_golden("blocks.lox");
+    }
+
+    #[test]
+    fn test_class_body() {
+        assert_golden("class_body.lox");
+    }
+
+    #[test]
+    fn test_arguments() {
+        assert_golden("arguments.lox");
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_golden("statements.lox");
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_golden("operators.lox");
+    }
+
+    #[test]
+    fn test_exceptions() {
+        assert_golden("exceptions.lox");
+    }
+}
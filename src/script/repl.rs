@@ -0,0 +1,422 @@
+use std::{io::{self, BufRead, Write}, path::Path};
+
+use crate::script::{
+    ast::{Ast, PrintStmt, Stmt},
+    diagnostic::{Diagnostic, DiagnosticReport},
+    display_ast,
+    lint::filter_allowed,
+    lint_diagnostics,
+    module::Loader,
+    parser::parse_ast,
+    resolver::resolve,
+    scanner::{scan_tokens, ScannerErrorType},
+    tokens::{Token, TokenType},
+    vm::{compiler::compile, graph::{Attr, Attrs, GraphSnapshot}, ArenaRoot, Limits, Root, Vm},
+    RunError,
+};
+
+
+static FILE: &str = "<repl>";
+
+static HELP: &str = "\
+:ast [code]   show the syntax tree of `code`, or of the last input
+:disasm       disassemble the functions compiled by the last input
+:graph        show the nodes and attributes of the graph so far
+:trace        toggle the per instruction debug trace
+:help         show this message
+:quit         exit the repl";
+
+type Re = RunError;
+
+/// An interactive session, that keeps one vm (and its globals, classes and graph) alive between inputs
+pub struct Repl {
+    vm:         Vm,
+    loader:     Loader, // imports are relative to the working directory
+    last_input: String,
+    last_funcs: usize, // the index of the first function compiled by the last input
+
+    /// Every input so far, one after the other. The spans and lines of each input are offset to where it is in here,
+    /// so an error in a function from an earlier input points at that input
+    history:    String,
+    lines:      usize, // in `history`
+}
+
+enum Command {
+    Continue,
+    Quit,
+}
+
+
+/// Reads and evaluates inputs from stdin until it's closed or `:quit`, each input within `limits`
+pub fn run_repl(limits: Limits) {
+
+    let mut repl  = Repl::new();
+    repl.set_limits(limits);
+    let mut stdin = io::stdin().lock();
+
+    println!("lightweaver repl, :help for commands");
+
+    let mut buffer = String::new();
+
+    loop {
+        let prompt = match buffer.is_empty() {
+            true  => "> ",
+            false => "... ",
+        };
+
+        print!("{}", prompt);
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+
+        buffer.push_str(&line);
+
+        if buffer.trim_start().starts_with(':') {
+            let command = std::mem::take(&mut buffer);
+
+            match repl.command(command.trim()) {
+                Command::Continue => continue,
+                Command::Quit     => break,
+            }
+        }
+
+        if is_incomplete(&buffer) {
+            continue;
+        }
+
+        let input  = std::mem::take(&mut buffer);
+        let report = repl.eval(&input);
+
+        eprint!("{}", report);
+    }
+}
+
+
+impl Repl {
+
+    pub fn new() -> Self {
+        Self::with_root(ArenaRoot::new(|_ctx| Root::new()))
+    }
+
+    fn with_root(root: ArenaRoot) -> Self {
+        let mut vm = Vm::init(root);
+
+        vm.root().mutate_root(|_ctx, root| root.set_trace(false));
+
+        Self {
+            vm,
+            loader:     Loader::new(FILE, None),
+            last_input: String::new(),
+            last_funcs: 0,
+            history:    String::new(),
+            lines:      0,
+        }
+    }
+
+    /// Bounds on each input, `Limits::default()` unless set
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.vm.root().mutate_root(|_ctx, root| root.set_limits(limits));
+    }
+
+    /// Compiles `input` into a new top level function and runs it,
+    /// printing the value of a trailing expression statement
+    pub fn eval(&mut self, input: &str) -> DiagnosticReport {
+
+        let at = (self.history.len(), self.lines);
+
+        self.history.push_str(input);
+
+        if !input.ends_with('\n') {
+            self.history.push('\n');
+        }
+
+        self.lines += self.history[at.0..].matches('\n').count();
+
+        let mut diagnostics = vec![];
+        let mut sources     = vec![];
+
+        if let Err(err) = self.try_eval(input, at, &mut diagnostics) {
+            diagnostics.extend(err.to_diagnostics(FILE, &self.history));
+            sources = err.sources();
+        }
+
+        DiagnosticReport::new(self.history.clone(), diagnostics).with_sources(sources)
+    }
+
+    fn try_eval(&mut self, input: &str, at: (usize, usize), diagnostics: &mut Vec<Diagnostic>) -> Result<(), RunError> {
+
+        let mut ast = parse_input(input, at)?;
+
+        if matches!(ast.stmts.last(), Some(Stmt::Expression(_))) {
+            let Some(Stmt::Expression(stmt)) = ast.stmts.pop() else {
+                unreachable!()
+            };
+
+            ast.stmts.push(PrintStmt::new(stmt.expr, stmt.span));
+        }
+
+        let lints = resolve(&mut ast).map_err(Re::ResolveError)?;
+        diagnostics.extend(lint_diagnostics(&filter_allowed(lints, &self.history), FILE));
+
+        self.last_input = input.to_owned();
+
+        self.loader.load_imports(&mut ast, Path::new("."))?;
+
+        self.vm.start_budget();
+        self.loader.run_pending(&mut self.vm)?;
+
+        let index = self.vm.root().mutate(|_ctx, root| root.function_count());
+
+        self.vm.root().mutate_root(|ctx, root| {
+            compile(ast, root, ctx)
+        })
+        .map_err(Re::CompileError)?;
+
+        self.last_funcs = index;
+
+        self.vm.load_script(index);
+        self.vm.run().map_err(|err| self.loader.runtime_error(err))
+    }
+
+    fn command(&mut self, command: &str) -> Command {
+
+        let (name, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let arg         = arg.trim();
+
+        match name {
+            ":ast" => {
+                let source = match arg.is_empty() {
+                    true  => self.last_input.clone(),
+                    false => arg.to_owned(),
+                };
+
+                match parse_input(&source, (0, 0)) {
+                    Ok (ast) => display_ast(&ast),
+                    Err(err) => eprint!("{}", err.report(FILE, source)),
+                }
+            }
+
+            ":disasm" => {
+                let start = self.last_funcs;
+                self.vm.root().mutate(|_ctx, root| root.dbg_funcs_from(start));
+            }
+
+            ":graph" => {
+                let graph = self.vm.root().mutate(|_ctx, root| root.graph());
+                print!("{}", display_graph(&graph));
+            }
+
+            ":trace" => {
+                let trace = self.vm.root().mutate_root(|_ctx, root| {
+                    root.set_trace(!root.is_tracing());
+                    root.is_tracing()
+                });
+
+                println!("trace {}", if trace { "on" } else { "off" });
+            }
+
+            ":help"           => println!("{}", HELP),
+            ":quit" | ":q"    => return Command::Quit,
+
+            _ => eprintln!("Unknown command '{}', :help for commands", name),
+        }
+
+        Command::Continue
+    }
+}
+
+
+// A trailing `;` is optional, so `1 + 2` is a valid input.
+// `at` is the byte offset and line count of the history before the input
+fn parse_input(input: &str, at: (usize, usize)) -> Result<Ast, RunError> {
+
+    let tokens = scan_input(input, at)?;
+
+    match parse_ast(tokens) {
+        Ok(ast) => Ok(ast),
+
+        Err(err) => {
+            let trimmed = input.trim_end();
+
+            if trimmed.ends_with(';') || trimmed.ends_with('}') {
+                return Err(Re::ParserError(err));
+            }
+
+            let tokens = scan_input(&format!("{};", trimmed), at)?;
+            parse_ast(tokens).map_err(|_| Re::ParserError(err))
+        }
+    }
+}
+
+// the tokens of an input, and its errors, placed after the history before it
+fn scan_input(input: &str, (offset, lines): (usize, usize)) -> Result<Vec<Token>, RunError> {
+
+    let tokens = scan_tokens(input).map_err(|mut errors| {
+        for err in errors.iter_mut() {
+            err.span.start += offset;
+            err.span.end   += offset;
+            err.line       += lines;
+        }
+
+        Re::ScannerError(errors)
+    })?;
+
+    Ok(tokens.into_iter()
+        .map(|mut token| {
+            token.span.start += offset;
+            token.span.end   += offset;
+            token.line       += lines;
+            token
+        })
+        .collect())
+}
+
+// an unterminated string or unbalanced brackets continue onto the next line
+fn is_incomplete(input: &str) -> bool {
+
+    let tokens = match scan_tokens(input) {
+        Ok (tokens) => tokens,
+        Err(errors) => {
+            return errors.iter().any(|e| matches!(e.type_, ScannerErrorType::UnterminatedString));
+        }
+    };
+
+    let depth = tokens.iter()
+        .map(|token| match token.type_ {
+            TokenType::LeftBrace  | TokenType::LeftParen  =>  1,
+            TokenType::RightBrace | TokenType::RightParen => -1,
+            _                                             =>  0,
+        })
+        .sum::<isize>();
+
+    depth > 0
+}
+
+fn display_graph(graph: &GraphSnapshot) -> String {
+
+    let mut out = format!("graph {}\n", display_attrs(&graph.attrs));
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let classes: Vec<_> = node.classes.iter()
+            .map(|class| class.name.as_str())
+            .collect();
+
+        out.push_str(&format!("[{}] {} {}\n", i, classes.join(" < "), display_attrs(&node.attrs)));
+    }
+
+    out
+}
+
+fn display_attrs(attrs: &Attrs) -> String {
+
+    let attrs: Vec<_> = attrs.iter()
+        .map(|(name, attr)| {
+            let value = match attr {
+                Attr::Nil       => "nil".to_owned(),
+                Attr::Bool  (b) => b.to_string(),
+                Attr::Number(n) => n.to_string(),
+                Attr::String(s) => format!("{:?}", s),
+                Attr::Object(o) => o.to_owned(),
+            };
+
+            format!("{}: {}", name, value)
+        })
+        .collect();
+
+    match attrs.is_empty() {
+        true  => "{}".to_owned(),
+        false => format!("{{ {} }}", attrs.join(", ")),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repl() -> Repl {
+        Repl::with_root(ArenaRoot::new(|_ctx| Root::new_test()))
+    }
+
+    fn out(repl: &mut Repl) -> Vec<String> {
+        repl.vm.root().mutate_root(|_ctx, root| root.take_out())
+    }
+
+    #[test]
+    fn globals_persist() {
+        let mut repl = repl();
+
+        assert!(!repl.eval("var a = 1;").has_errors());
+        assert!(!repl.eval("fun add(x) { return a + x; }").has_errors());
+        assert!(!repl.eval("class Box {}").has_errors());
+
+        assert!(!repl.eval("add(2)").has_errors());
+        assert!(!repl.eval("print Box;").has_errors());
+
+        assert_eq!(out(&mut repl), vec!["3", "<class Box>"]);
+    }
+
+    #[test]
+    fn continues_after_errors() {
+        let mut repl = repl();
+
+        assert!(!repl.eval("var a = 1;").has_errors());
+
+        assert!(repl.eval("print a +;")     .has_errors());
+        assert!(repl.eval("print undefined;").has_errors());
+        assert!(repl.eval("a = -\"s\";")    .has_errors());
+
+        assert!(!repl.eval("a + 1").has_errors());
+        assert_eq!(out(&mut repl), vec!["2"]);
+
+        // each input gets the full budget
+        repl.set_limits(Limits { instructions: Some(1000), ..Limits::none() });
+
+        assert!(repl.eval("while (true) {}").to_string().contains("Exceeded the limit of 1000 instructions"));
+        assert!(!repl.eval("a + 2").has_errors());
+        assert_eq!(out(&mut repl), vec!["3"]);
+    }
+
+    #[test]
+    fn errors_point_at_their_input() {
+        let mut repl = repl();
+
+        assert!(!repl.eval("fun bad(x) {\n    return x + nil;\n}\n").has_errors());
+        assert!(!repl.eval("var a = 1;").has_errors());
+
+        // the error is in the function's input, and the call in the latest one
+        let report = repl.eval("bad(a);").to_string();
+
+        assert!(report.contains("--> <repl>:2:12\n"));
+        assert!(report.contains("2 |     return x + nil;\n"));
+        assert!(report.contains("[line 5, col 1] in script"));
+
+        assert!(repl.eval("print +;").to_string().contains("--> <repl>:6:7\n"));
+    }
+
+    #[test]
+    fn graph_persists() {
+        let mut repl = repl();
+
+        repl.eval("class Node {}");
+        repl.eval("var a = graph.add(Node());");
+        repl.eval("a.label = \"a\";");
+        repl.eval("graph.theme = \"dark\";");
+
+        let graph = repl.vm.root().mutate(|_ctx, root| root.graph());
+
+        assert_eq!(display_graph(&graph), "graph { theme: \"dark\" }\n[0] Node { label: \"a\" }\n");
+    }
+
+    #[test]
+    fn incomplete_input() {
+        assert!( is_incomplete("fun f() {"));
+        assert!( is_incomplete("print (1 +"));
+        assert!( is_incomplete("print \"abc"));
+        assert!(!is_incomplete("fun f() {}"));
+        assert!(!is_incomplete("print 1"));
+    }
+}
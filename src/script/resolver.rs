@@ -1,23 +1,95 @@
 
-use std::usize;
+use std::{fmt::Display, usize};
 
-use crate::script::{ast::*, vm::chunk::{StackIndex, StackOffset, UpvalueIndex}};
+use crate::script::{ast::*, diagnostic::Span, lint::{Lint, LintType}, tokens::Token, vm::chunk::{Capture, StackIndex, StackOffset, UpvalueIndex}};
 
 
-pub fn resolve(ast: &mut Ast) {
+pub type ResolveResult = Result<Vec<Lint>, Vec<ResolveError>>;
+
+/// Resolves every variable to a local, upvalue or global,
+/// linting the locals along the way
+pub fn resolve(ast: &mut Ast) -> ResolveResult {
+
+    let resolution = analyze(ast);
+
+    if !resolution.errors.is_empty() {
+        Err(resolution.errors)
+    }
+    else {
+        Ok(resolution.lints)
+    }
+}
+
+/// Like `resolve`, but also returns every variable reference, for editor tooling
+pub fn analyze(ast: &mut Ast) -> Resolution {
 
     let mut resolver = Resolver::new();
+    resolver.globals = ast.stmts.iter().flat_map(declared_names).collect();
 
     for stmt in ast.stmts.iter_mut() {
         resolver.resolve_stmt(stmt);
     }
+
+    let mut lints = resolver.lints;
+    lints.sort_by_key(|lint| lint.span.start);
+
+    Resolution {
+        lints,
+        errors:     resolver.errors,
+        references: resolver.references,
+    }
+}
+
+
+pub struct Resolution {
+    pub lints:      Vec<Lint>,
+    pub errors:     Vec<ResolveError>,
+    pub references: Vec<Reference>,
+}
+
+/// A use of a variable by name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name:     String,
+    pub span:     Span,
+    pub var_type: VarType,
+
+    /// The name in the declaration, for locals and upvalues
+    pub decl:     Option<Span>,
 }
 
 
 struct Resolver<'a> {
     scopes:         Vec<Scope<'a>>,
     funcs:          Vec<Func>,
+    classes:        Vec<ClassType>,
     temporaries:    usize,
+    errors:         Vec<ResolveError>,
+    lints:          Vec<Lint>,
+    references:     Vec<Reference>,
+    globals:        Vec<String>,  // declared at the top level, for the shadowing lint
+
+    // assignments in different branches don't overwrite each other
+    branch:         usize,
+    branches:       usize,
+}
+
+#[derive(Debug)]
+pub struct ResolveError {
+    pub type_: ResolveErrorType,
+    pub token: Token,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResolveErrorType {
+    ReadInOwnInitializer,
+    Redeclaration,
+    TopLevelReturn,
+    ThisOutsideClass,
+    SuperOutsideClass,
+    SuperWithoutSuperclass,
+    InheritFromSelf,
+    NestedImport,
 }
 
 #[derive(Debug)]
@@ -26,17 +98,57 @@ struct Local<'a> {
     pub function_depth: Option<usize>,
     pub name:           String,
     pub type_:          LocalType<'a>,
+
+    // false while resolving a variable's initializer
+    pub initialized:    bool,
+
+    pub kind:           LocalKind,
+    pub span:           Span,
+    pub used:           bool,
+    pub captured:       bool,
+    pub last_read:      Option<usize>,
+    pub writes:         Vec<Write>,  // assignments that haven't been read yet
+    pub overwritten:    Option<Span>,  // a parameter's argument, assigned over before it's read
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LocalKind {
+    Slot,
+    Var,
+    Param,
+    Function,
+    Class,
+}
+
+#[derive(Debug)]
+struct Write {
+    span:   Span,
+    branch: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Write(Span),
 }
 
 #[derive(Debug)]
 enum LocalType<'a> {
     Local(&'a mut VarDeclType),
-    Call (VarDeclType)
+    Slot (&'a mut VarDeclType),
 }
 
 #[derive(Debug)]
 struct Upvalue {
-    index: UpvalueIndex,
+    local:  usize, // the captured variable's position among all the locals
+    source: UpvalueSource,
+}
+
+// where a closure gets an upvalue from when it's created
+#[derive(Debug, Clone, Copy)]
+enum UpvalueSource {
+    Local,
+    Upvalue(UpvalueIndex),
 }
 
 struct Scope<'a> {
@@ -49,6 +161,43 @@ struct Func {
     pub upvalues: Vec<Upvalue>,
 }
 
+impl Func {
+    // a variable is only captured once, however many times it's used
+    fn add_upvalue(&mut self, local: usize, source: UpvalueSource) -> UpvalueIndex {
+        let found = self.upvalues.iter().position(|upvalue| upvalue.local == local);
+
+        UpvalueIndex(found.unwrap_or_else(|| {
+            self.upvalues.push(Upvalue { local, source });
+            self.upvalues.len() - 1
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassType {
+    Class,
+    Subclass,
+}
+
+type Re = ResolveErrorType;
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = &self.token.lexeme;
+
+        match self.type_ {
+            Re::ReadInOwnInitializer   => write!(f, "Can't read local variable '{}' in its own initializer", name),
+            Re::Redeclaration          => write!(f, "Already a variable named '{}' in this scope", name),
+            Re::TopLevelReturn         => write!(f, "Can't return from top-level code"),
+            Re::ThisOutsideClass       => write!(f, "Can't use 'this' outside of a class"),
+            Re::SuperOutsideClass      => write!(f, "Can't use 'super' outside of a class"),
+            Re::SuperWithoutSuperclass => write!(f, "Can't use 'super' in a class with no superclass"),
+            Re::InheritFromSelf        => write!(f, "A class can't inherit from itself"),
+            Re::NestedImport           => write!(f, "Imports must be at the top level of a script"),
+        }
+    }
+}
+
 
 impl<'a> Resolver<'a> {
 
@@ -56,7 +205,14 @@ impl<'a> Resolver<'a> {
         Self {
             scopes:      vec![],
             funcs:       vec![],
+            classes:     vec![],
             temporaries: 0,
+            errors:      vec![],
+            lints:       vec![],
+            references:  vec![],
+            globals:     vec![],
+            branch:      0,
+            branches:    0,
         }
     }
 
@@ -69,8 +225,11 @@ impl<'a> Resolver<'a> {
             Stmt::Expression (stmt) => self.resolve_expr_stmt  (stmt),
             Stmt::Function   (stmt) => self.resolve_func_decl  (stmt),
             Stmt::If         (stmt) => self.resolve_if_stmt    (stmt),
+            Stmt::Import     (stmt) => self.resolve_import_stmt(stmt),
             Stmt::Print      (stmt) => self.resolve_print_stmt (&mut stmt.expr),
             Stmt::Return     (stmt) => self.resolve_return_stmt(stmt),
+            Stmt::Throw      (stmt) => self.resolve_expr       (&mut stmt.value),
+            Stmt::Try        (stmt) => self.resolve_try_stmt   (stmt),
             Stmt::Var        (stmt) => self.resolve_var_decl   (stmt),
             Stmt::While      (stmt) => self.resolve_while_stmt (stmt),
         };
@@ -80,6 +239,7 @@ impl<'a> Resolver<'a> {
 
     fn resolve_block_stmt(&mut self, block: &'a mut Block) {
         self.begin_scope();
+        self.check_unreachable(&block.stmts);
 
         for stmt in block.stmts.iter_mut() {
             self.resolve_stmt(stmt);
@@ -89,17 +249,40 @@ impl<'a> Resolver<'a> {
     }
 
     fn resolve_class_decl(&mut self, class: &'a mut Class) {
+
+        let class_type = match class.superclass {
+            Some(_) => ClassType::Subclass,
+            None    => ClassType::Class,
+        };
+
+        // the superclass is loaded on top of the new class object
+        if let Some(superclass) = &mut class.superclass {
+            if superclass.name.lexeme == class.name.lexeme {
+                self.error(Re::InheritFromSelf, &superclass.name);
+            }
+
+            self.temporaries = 1;
+            self.resolve_var_expr(superclass);
+        }
+
+        self.classes.push(class_type);
         self.begin_scope();
 
-        // TODO:
+        // methods aren't compiled yet, they're only checked
+        for method in class.methods.iter_mut() {
+            let FunctionStmt { name, params, body, locals, slot_type, captures, .. } = method;
+
+            (*locals, *captures) = self.resolve_function(params, body, slot_type, "this".to_owned(), name.span);
+        }
 
         self.end_scope();
+        self.classes.pop();
 
         if !self.is_global_scope() {
             class.var_type = VarDeclType::Local;
             let type_      = LocalType  ::Local(&mut class.var_type);
 
-            self.push_local(class.name.lexeme.to_owned(), type_);
+            self.declare(&class.name, LocalKind::Class, type_);
         }
 
     }
@@ -109,34 +292,92 @@ impl<'a> Resolver<'a> {
     }
 
     fn resolve_func_decl(&mut self, func: &'a mut FunctionStmt) {
+        let FunctionStmt { name, params, body, locals, var_type, slot_type, captures, .. } = func;
 
-        let arity = func.params.len();
+        let is_local = !self.is_global_scope();
 
-        if !self.is_global_scope() {
-            func.var_type = VarDeclType::Local;
-            let type_     = LocalType::Local(&mut func.var_type);
+        // the function isn't on the stack until its closure has been made,
+        // inside the body its name is the call's slot
+        if is_local {
+            *var_type = VarDeclType::Local;
+            let type_ = LocalType::Local(var_type);
 
-            self.push_local(func.name.lexeme.to_string(), type_);
+            self.declare(name, LocalKind::Function, type_);
+            self.set_initialized(false);
         }
 
+        (*locals, *captures) = self.resolve_function(params, body, slot_type, name.lexeme.clone(), name.span);
+
+        if is_local {
+            self.set_initialized(true);
+        }
+    }
+
+    // `slot` names the reserved first stack slot of the call, declared at `span`,
+    // returns the number of locals in the body and what the closure captures
+    fn resolve_function(&mut self,
+        params:    &'a mut [FunctionParam],
+        body:      &'a mut [Stmt],
+        slot_type: &'a mut VarDeclType,
+        slot:      String,
+        span:      Span,
+    )
+        -> (usize, Vec<Capture>)
+    {
+
+        let arity = params.len();
+
+        // the closure is made with the temporaries on the stack, the body starts without any
+        let temps = self.temporaries;
+        self.temporaries = 0;
+
         self.begin_func();
         self.begin_scope();
 
-        let type_ = LocalType::Call(VarDeclType::Local);
-        self.push_local(func.name.lexeme.clone(), type_);
+        *slot_type = VarDeclType::Local;
+        let type_  = LocalType::Slot(slot_type);
+        self.push_local(slot, LocalKind::Slot, span, type_);
 
-        for arg in func.params.iter_mut() {
-            let type_ = LocalType::Local(&mut arg.var_type);
-            self.push_local(arg.name.lexeme.to_owned(), type_);
+        let mut defaults = vec![];
+
+        for FunctionParam { name, var_type, default, .. } in params.iter_mut() {
+            let type_ = LocalType::Local(var_type);
+            self.declare(name, LocalKind::Param, type_);
+            self.add_write(name.span);
+
+            defaults.extend(default.as_mut());
         }
 
-        for stmt in func.body.iter_mut() {
+        // defaults run before the body, with every parameter in scope
+        for default in defaults {
+            self.resolve_expr(default);
+            self.temporaries = 0;
+        }
+
+        self.check_unreachable(body);
+
+        for stmt in body.iter_mut() {
             self.resolve_stmt(stmt);
         }
 
-        func.locals = self.end_scope() - arity;
-        self.funcs.pop();
+        let locals = self.end_scope() - arity;
+        let func   = self.end_func();
+
+        let captures = func.upvalues.iter()
+            .map(|upvalue| self.capture(upvalue, temps))
+            .collect();
+
+        self.temporaries = temps;
+
+        (locals, captures)
+    }
 
+    // where the new closure finds an upvalue, `temporaries` is the number of values above the locals
+    fn capture(&self, upvalue: &Upvalue, temporaries: usize) -> Capture {
+        match upvalue.source {
+            UpvalueSource::Upvalue(index) => Capture::Upvalue(index),
+            UpvalueSource::Local          => Capture::Local(StackOffset(self.locals_above(upvalue.local) + temporaries)),
+        }
     }
 
     fn resolve_if_stmt(&mut self, if_stmt: &'a mut IfStmt) {
@@ -145,176 +386,413 @@ impl<'a> Resolver<'a> {
         self.resolve_expr(&mut if_stmt.condition);
         self.temporaries = temps;
 
+        let branch = self.branch;
+
+        self.begin_branch();
         self.resolve_stmt(&mut if_stmt.then_branch);
 
         if let Some(stmt) = &mut if_stmt.else_branch {
+            self.begin_branch();
             self.resolve_stmt(stmt);
         }
+
+        self.branch = branch;
     }
 
     fn resolve_print_stmt(&mut self, expr: &'a mut Expr) {
         self.resolve_expr(expr);
     }
 
+    // the imported names are globals of the importing module
+    fn resolve_import_stmt(&mut self, import: &'a mut ImportStmt) {
+        if !self.is_global_scope() {
+            self.error(Re::NestedImport, &import.path);
+        }
+    }
+
     fn resolve_return_stmt(&mut self, return_: &'a mut ReturnStmt) {
+        if self.funcs.is_empty() {
+            self.error(Re::TopLevelReturn, &return_.keyword);
+        }
+
         if let Some(val) = &mut return_.value {
             self.resolve_expr(val);
         }
     }
 
+    // either block may be cut short, so neither overwrites the other's assignments
+    fn resolve_try_stmt(&mut self, try_: &'a mut TryStmt) {
+        let branch = self.branch;
+
+        self.begin_branch();
+        self.resolve_block_stmt(&mut try_.body);
+
+        // the caught value is a local in a scope of its own, around the handler
+        self.begin_branch();
+        self.begin_scope();
+
+        try_.var_type = VarDeclType::Local;
+        self.declare(&try_.name, LocalKind::Var, LocalType::Local(&mut try_.var_type));
+
+        self.resolve_block_stmt(&mut try_.handler);
+        self.end_scope();
+
+        self.branch = branch;
+    }
+
     fn resolve_var_decl(&mut self, stmt: &'a mut VarStmt) {
 
-        if !self.is_global_scope() {
+        let is_local = !self.is_global_scope();
+        let write    = stmt.initializer.as_ref().map(|val| stmt.name.span.to(val.span()));
+
+        if is_local {
             stmt.var_type = VarDeclType::Local;
             let type_     = LocalType  ::Local(&mut stmt.var_type);
 
-            self.push_local(stmt.name.lexeme.to_owned(), type_);
+            self.declare(&stmt.name, LocalKind::Var, type_);
+            self.set_initialized(false);
         }
 
         if let Some(val) = &mut stmt.initializer {
             self.resolve_expr(val);
         }
+
+        if is_local {
+            self.set_initialized(true);
+
+            if let Some(span) = write {
+                self.add_write(span);
+            }
+        }
     }
 
     fn resolve_while_stmt(&mut self, while_: &'a mut WhileStmt) {
+        let branch = self.branch;
+        self.begin_branch();
+
+        let temps = self.temporaries;
         self.resolve_expr(&mut while_.condition);
+        self.temporaries = temps;
 
         self.resolve_stmt(&mut while_.body);
+
+        self.branch = branch;
+        self.end_loop(while_.span);
     }
 
 
     // Expressions
 
+    // `temporaries` counts the values above the locals on the stack. Every expression leaves exactly
+    // its own value there, so each only has to account for what it pushes or pops between its operands
     fn resolve_expr(&mut self, expr: &'a mut Expr) {
+        let temps = self.temporaries;
 
         match expr {
-            Expr::Assign   (expr) => self.resolve_assign_expr (expr),
-            Expr::Binary   (expr) => self.resolve_binary_expr (expr),
-            Expr::Call     (expr) => self.resolve_call_expr   (expr),
-            Expr::Get      (_)    => {},
-            Expr::Grouping (expr) => self.resolve_expr        (&mut expr.expr),
-            Expr::Literal  (_)    => {},
-            Expr::Logical  (expr) => self.resolve_logical_expr(expr),
-            Expr::Set      (expr) => self.resolve_set_expr    (expr),
-            Expr::Super    (expr) => self.resolve_super_expr  (expr),
-            Expr::This     (expr) => self.resolve_this_expr   (expr),
-            Expr::Unary    (expr) => self.resolve_unary_expr  (expr),
-            Expr::Variable (expr) => self.resolve_var_expr    (expr),
+            Expr::Assign        (expr) => self.resolve_assign_expr        (expr),
+            Expr::Binary        (expr) => self.resolve_binary_expr        (expr),
+            Expr::Call          (expr) => self.resolve_call_expr          (expr),
+            Expr::Get           (expr) => self.resolve_get_expr           (expr),
+            Expr::Grouping      (expr) => self.resolve_expr               (&mut expr.expr),
+            Expr::Interpolation (expr) => self.resolve_interpolation_expr (expr),
+            Expr::Lambda        (expr) => self.resolve_lambda_expr        (expr),
+            Expr::Literal       (_)    => {},
+            Expr::Logical       (expr) => self.resolve_logical_expr       (expr),
+            Expr::Set           (expr) => self.resolve_set_expr           (expr),
+            Expr::Super         (expr) => self.resolve_super_expr         (expr),
+            Expr::Ternary       (expr) => self.resolve_ternary_expr       (expr),
+            Expr::This          (expr) => self.resolve_this_expr          (expr),
+            Expr::Unary         (expr) => self.resolve_unary_expr         (expr),
+            Expr::Variable      (expr) => self.resolve_var_expr           (expr),
         };
+
+        self.temporaries = temps +1;
     }
 
+    // the left value is popped before the right is evaluated, and the result takes its place
     fn resolve_logical_expr(&mut self, logical: &'a mut Logical) {
-        let temps = self.temporaries;
-
         self.resolve_expr(&mut logical.left);
-        self.temporaries = temps +1;
+        self.temporaries -= 1;
 
         self.resolve_expr(&mut logical.right);
-        self.temporaries = temps +2;
     }
 
+    // the condition is popped before either branch is evaluated
+    fn resolve_ternary_expr(&mut self, ternary: &'a mut Ternary) {
+        self.resolve_expr(&mut ternary.condition);
+        self.temporaries -= 1;
 
-    fn resolve_assign_expr(&mut self, assign: &'a mut Assign) {
         let temps = self.temporaries;
+        self.resolve_expr(&mut ternary.then_branch);
 
+        self.temporaries = temps;
+        self.resolve_expr(&mut ternary.else_branch);
+    }
+
+    // the assigned value is on top of the stack when it's stored
+    fn resolve_assign_expr(&mut self, assign: &'a mut Assign) {
         self.resolve_expr(&mut assign.value);
-        self.temporaries = temps +1;
+
+        let access = Access::Write(assign.span);
+        assign.target.var_type = self.resolve_name(&assign.target.name, self.temporaries, access);
     }
 
     fn resolve_binary_expr(&mut self, binary: &'a mut BinaryOperator) {
-        let temps = self.temporaries;
-
         self.resolve_expr(&mut binary.left);
-        self.temporaries = temps +1;
-
         self.resolve_expr(&mut binary.right);
-        self.temporaries = temps +2;
     }
 
+    // the callee, then the positional arguments, then each named argument's value above its name
     fn resolve_call_expr(&mut self, call: &'a mut Call) {
-        let temps = self.temporaries;
-
         self.resolve_expr(&mut call.callee);
-        self.temporaries = temps +1;
 
-        for (i, arg) in call.args.iter_mut().enumerate() {
+        for arg in call.args.iter_mut() {
             self.resolve_expr(arg);
-            self.temporaries = temps + i;
+        }
+
+        for arg in call.named.iter_mut() {
+            self.temporaries += 1;
+            self.resolve_expr(&mut arg.value);
         }
     }
 
-    fn resolve_unary_expr(&mut self, unary: &'a mut UnaryOperator) {
-        let temps = self.temporaries;
+    // each part is left on the stack until they're all joined
+    fn resolve_interpolation_expr(&mut self, interpolation: &'a mut Interpolation) {
+        for part in interpolation.parts.iter_mut() {
+            self.resolve_expr(part);
+        }
+    }
 
+    fn resolve_lambda_expr(&mut self, lambda: &'a mut Lambda) {
+        let FunctionStmt { name, params, body, locals, slot_type, captures, .. } = &mut lambda.func;
+
+        // a lambda's slot has no name, so it can't refer to itself
+        (*locals, *captures) = self.resolve_function(params, body, slot_type, String::new(), name.span);
+    }
+
+    fn resolve_unary_expr(&mut self, unary: &'a mut UnaryOperator) {
         self.resolve_expr(&mut unary.right);
-        self.temporaries = temps +1;
     }
 
     fn resolve_var_expr(&mut self, var: &'a mut Variable) {
-        self.temporaries += 1;
+        var.var_type = self.resolve_name(&var.name, self.temporaries, Access::Read);
+    }
+
+    fn resolve_name(&mut self, name: &Token, temporaries: usize, access: Access) -> VarType {
+        let (var_type, decl) = self.lookup(name, temporaries, access);
+
+        self.references.push(Reference {
+            name: name.lexeme.to_owned(),
+            span: name.span,
+            var_type,
+            decl,
+        });
+
+        var_type
+    }
+
+    // `temporaries` is the number of values above the locals on the stack,
+    // returns where the name was declared, if it's not a global
+    fn lookup(&mut self, name: &Token, temporaries: usize, access: Access) -> (VarType, Option<Span>) {
 
         if self.is_global_scope() {
-            return;
+            return (VarType::Global, None);
         }
 
-        let locals = self.scopes.iter_mut()
-            .flat_map(|scope|
-                &mut scope.locals
-            )
-            .rev()
+        let found = self.scopes.iter()
+            .flat_map(|scope| &scope.locals)
             .enumerate()
-        ;
+            .filter(|(_, local)| local.name == name.lexeme)
+            .last()
+            .map(|(index, _)| index);
 
-        let mut found = None;
-        for l in locals {
-            if l.1.name == var.name.lexeme {
-                found = Some(l);
-                break;
-            }
+        let Some(index) = found else {
+            return (VarType::Global, None);
+        };
+
+        let above = self.locals_above(index);
+        let local = self.scopes.iter_mut()
+            .flat_map(|scope| &mut scope.locals)
+            .nth(index)
+            .expect("Expect the local to be in scope");
+
+        let decl = Some(local.span);
+
+        if access == Access::Read && !local.initialized {
+            self.errors.push(ResolveError {
+                type_: Re::ReadInOwnInitializer,
+                token: name.clone(),
+            });
         }
 
-        let Some((i, local)) = found else {
-            return;
-        };
+        let is_local = local.function_depth == self.funcs.last().map(|func| func.depth);
 
+        match access {
+            Access::Read => {
+                local.used      = true;
+                local.last_read = Some(name.span.start);
+                local.writes.clear();
+            }
 
-        let func = self.funcs.last();
+            // an earlier assignment in the same branch is overwritten before it's read
+            Access::Write(span) if is_local && !local.captured => {
+                let branch = self.branch;
+
+                for write in local.writes.extract_if(.., |write| write.branch == branch) {
+                    // whether the argument was needed depends on the parameter being read at all
+                    if local.kind == LocalKind::Param && write.span == local.span {
+                        local.overwritten = Some(write.span);
+                        continue;
+                    }
+
+                    self.lints.push(Lint {
+                        type_: LintType::UnusedAssignment,
+                        name:  local.name.clone(),
+                        span:  write.span,
+                    });
+                }
+
+                local.writes.push(Write { span, branch });
+            }
 
-        if func.is_none_or(|func| local.function_depth.unwrap() == func.depth) {
-            var.var_type = VarType::Local(StackOffset(i + self.temporaries -1));
-            return;
+            Access::Write(_) => {},
         }
 
+        if is_local {
+            return (VarType::Local(StackOffset(above + temporaries)), decl);
+        }
 
-        let func  = self.funcs.last_mut().unwrap();
-        let index = UpvalueIndex(func.upvalues.len());
-        func.upvalues.push(Upvalue { index });
+        // closures can read and write the variable at any time
+        local.captured = true;
+        local.writes.clear();
 
-        let LocalType::Local(decl_type) = &mut local.type_ else {
-            panic!("The resolve stack got done borked");
-        };
+        let (LocalType::Local(decl_type) | LocalType::Slot(decl_type)) = &mut local.type_;
+        **decl_type = VarDeclType::Upvalue;
+
+        // every function between the declaration and this one closes over the variable,
+        // the outermost from the stack, the others from the closure enclosing them
+        let outermost = local.function_depth.map_or(0, |depth| depth + 1);
+
+        let mut source   = UpvalueSource::Local;
+        let mut upvalue  = UpvalueIndex(0);
 
-        **decl_type  = VarDeclType::Upvalue;
-        var.var_type = VarType    ::Upvalue(index);
+        for func in self.funcs[outermost..].iter_mut() {
+            upvalue = func.add_upvalue(index, source);
+            source  = UpvalueSource::Upvalue(upvalue);
+        }
+
+        (VarType::Upvalue(upvalue), decl)
+    }
 
+    fn resolve_get_expr(&mut self, get: &'a mut Get) {
+        self.resolve_expr(&mut get.expr);
     }
 
     fn resolve_set_expr(&mut self, set: &'a mut Set) {
-        let temps = self.temporaries;
+        self.resolve_expr(&mut set.target);
+
+        // a compound assignment has the property's current value under its value
+        if set.compound.is_some() {
+            self.temporaries += 1;
+        }
 
         self.resolve_expr(&mut set.value);
-        self.temporaries = temps +1;
     }
 
-    fn resolve_super_expr(&mut self, _super: &mut Super) {
-        todo!()
+    fn resolve_super_expr(&mut self, super_: &mut Super) {
+        match self.classes.last() {
+            None                    => self.error(Re::SuperOutsideClass,      &super_.keyword),
+            Some(ClassType::Class)  => self.error(Re::SuperWithoutSuperclass, &super_.keyword),
+            Some(ClassType::Subclass) => {},
+        }
+    }
+
+    fn resolve_this_expr(&mut self, this: &mut This) {
+        if self.classes.is_empty() {
+            self.error(Re::ThisOutsideClass, &this.keyword);
+        }
+    }
+
+    fn error(&mut self, type_: ResolveErrorType, token: &Token) {
+        self.errors.push(ResolveError {
+            type_,
+            token: token.clone(),
+        });
     }
 
-    fn resolve_this_expr(&mut self, _this: &mut This) {
-        todo!()
+    fn lint(&mut self, type_: LintType, name: &str, span: Span) {
+        self.lints.push(Lint {
+            type_,
+            name: name.to_owned(),
+            span,
+        });
     }
 
-    fn push_local(&mut self, name: String, var_type: LocalType<'a>) {
+    fn declare(&mut self, name: &Token, kind: LocalKind, type_: LocalType<'a>) {
+        self.check_redeclaration(name);
+        self.check_shadowing(name);
+
+        self.push_local(name.lexeme.to_owned(), kind, name.span, type_);
+    }
+
+    // locals can't be redeclared in the same scope, though globals can
+    fn check_redeclaration(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last() else {
+            return;
+        };
+
+        let redeclared = scope.locals.iter()
+            .any(|local| local.kind != LocalKind::Slot && local.name == name.lexeme);
+
+        if redeclared {
+            self.error(Re::Redeclaration, name);
+        }
+    }
+
+    fn check_shadowing(&mut self, name: &Token) {
+        let outer = self.scopes.len().saturating_sub(1);
+
+        let shadows = self.scopes[..outer].iter()
+            .flat_map(|scope| &scope.locals)
+            .any(|local| local.kind != LocalKind::Slot && local.name == name.lexeme);
+
+        let shadows = shadows || self.globals.contains(&name.lexeme);
+
+        if shadows {
+            self.lint(LintType::Shadowing, &name.lexeme, name.span);
+        }
+    }
+
+    // statements after one that always returns
+    fn check_unreachable(&mut self, stmts: &[Stmt]) {
+        let Some(i) = stmts.iter().position(always_returns) else {
+            return;
+        };
+
+        if let (Some(first), Some(last)) = (stmts.get(i + 1), stmts.last()) {
+            self.lint(LintType::UnreachableCode, "", first.span().to(last.span()));
+        }
+    }
+
+    fn set_initialized(&mut self, initialized: bool) {
+        let local = self.scopes.last_mut()
+            .and_then(|scope| scope.locals.last_mut())
+            .expect("Expect a local to have just been declared");
+
+        local.initialized = initialized;
+    }
+
+    // the most recently declared local was assigned to
+    fn add_write(&mut self, span: Span) {
+        let branch = self.branch;
+        let local  = self.scopes.last_mut()
+            .and_then(|scope| scope.locals.last_mut())
+            .expect("Expect a local to have just been declared");
+
+        local.writes.push(Write { span, branch });
+    }
+
+    fn push_local(&mut self, name: String, kind: LocalKind, span: Span, var_type: LocalType<'a>) {
 
         let last        = self.scopes.last_mut().expect("cannot push local in global scope");
         let scope_depth = last.depth;
@@ -324,10 +802,34 @@ impl<'a> Resolver<'a> {
             name,
             function_depth: self.funcs.last().map(|f| f.depth),
             type_:          var_type,
-
+            initialized:    true,
+
+            kind,
+            span,
+            used:           false,
+            captured:       false,
+            last_read:      None,
+            writes:         vec![],
+            overwritten:    None,
         });
     }
 
+    fn begin_branch(&mut self) {
+        self.branches += 1;
+        self.branch    = self.branches;
+    }
+
+    // assignments in a loop can be read by the next iteration
+    fn end_loop(&mut self, loop_: Span) {
+        let locals = self.scopes.iter_mut()
+            .flat_map(|scope| &mut scope.locals)
+            .filter(|local| local.last_read.is_some_and(|read| read >= loop_.start));
+
+        for local in locals {
+            local.writes.retain(|write| write.span.start < loop_.start);
+        }
+    }
+
     fn begin_scope(&mut self) {
         let depth = self.scopes.len();
         self.scopes.push(Scope {
@@ -340,9 +842,48 @@ impl<'a> Resolver<'a> {
     fn end_scope(&mut self) -> usize {
         let scope = self.scopes.pop().expect("Cannot pop global scope");
 
+        for local in scope.locals.iter() {
+            self.lint_local(local);
+        }
+
         scope.locals.len()
     }
 
+    fn lint_local(&mut self, local: &Local) {
+        if local.name.starts_with('_') {
+            return;
+        }
+
+        let unused = match local.kind {
+            LocalKind::Slot     => return,
+            LocalKind::Var      => LintType::UnusedVariable,
+            LocalKind::Param    => LintType::UnusedParameter,
+            LocalKind::Function => LintType::UnusedFunction,
+            LocalKind::Class    => LintType::UnusedClass,
+        };
+
+        if !local.used {
+            self.lint(unused, &local.name, local.span);
+
+            // a variable's only assignment is its initializer, but a parameter's are in the body
+            if local.kind != LocalKind::Param {
+                return;
+            }
+        }
+
+        // an unused parameter's argument is already reported as the parameter
+        let argument = |span: &Span| !local.used && *span == local.span;
+
+        let writes = local.writes.iter()
+            .map(|write| write.span)
+            .chain(local.overwritten)
+            .filter(|span| !argument(span));
+
+        for span in writes {
+            self.lint(LintType::UnusedAssignment, &local.name, span);
+        }
+    }
+
     fn begin_func(&mut self) {
         let depth = self.funcs.len();
 
@@ -353,8 +894,18 @@ impl<'a> Resolver<'a> {
 
     }
 
-    fn end_func(&mut self) {
-        self.funcs.pop().expect("Cannot pop global scope");
+    fn end_func(&mut self) -> Func {
+        self.funcs.pop().expect("Cannot pop global scope")
+    }
+
+    // the number of locals on the stack above the local at `index` (of every local in scope),
+    // a variable isn't on the stack until its initializer has been evaluated
+    fn locals_above(&self, index: usize) -> usize {
+        self.scopes.iter()
+            .flat_map(|scope| &scope.locals)
+            .skip(index + 1)
+            .filter(|local| local.initialized)
+            .count()
     }
 
     fn is_global_scope(&self) -> bool {
@@ -372,6 +923,38 @@ impl<'a> Resolver<'a> {
 
 }
 
+// the globals a top level statement declares
+fn declared_names(stmt: &Stmt) -> Vec<String> {
+    let names = match stmt {
+        Stmt::Var     (var)    => vec![&var.name],
+        Stmt::Function(func)   => vec![&func.name],
+        Stmt::Class   (class)  => vec![&class.name],
+        Stmt::Import  (import) => import.alias.iter().chain(&import.names).collect(),
+        _ => vec![],
+    };
+
+    names.into_iter().map(|name| name.lexeme.to_owned()).collect()
+}
+
+// a throw leaves the block just as a return does
+fn always_returns(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_)     => true,
+        Stmt::Throw (_)     => true,
+        Stmt::Block (block) => block.stmts.iter().any(always_returns),
+        Stmt::If    (if_)   => {
+            always_returns(&if_.then_branch)
+                && if_.else_branch.as_ref().is_some_and(|stmt| always_returns(stmt))
+        }
+        Stmt::Try   (try_)  => {
+            try_.body.stmts.iter().any(always_returns)
+                && try_.handler.stmts.iter().any(always_returns)
+        }
+        _ => false,
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use crate::script::{parser, scanner, vm::chunk::StackOffset};
@@ -401,7 +984,7 @@ mod tests {
     fn test_1() {
         let mut ast = get_ast("test_1.lox");
 
-        resolve(&mut ast);
+        resolve(&mut ast).unwrap();
 
         let var_decl_a:        &VarStmt      = get!(&ast       .stmts[0]);
         let var_decl_b:        &VarStmt      = get!(&ast       .stmts[1]);
@@ -453,7 +1036,7 @@ mod tests {
 
         let mut ast = get_ast("test_2.lox");
 
-        resolve(&mut ast);
+        resolve(&mut ast).unwrap();
 
         let fn_decl_outer:      &FunctionStmt = get!(&ast           .stmts[0]);
         let var_decl_mid:       &VarStmt      = get!(&ast           .stmts[1]);
@@ -502,7 +1085,7 @@ mod tests {
 
         let mut ast = get_ast("recursion.lox");
 
-        resolve(&mut ast);
+        resolve(&mut ast).unwrap();
 
         let rec_decl:    &FunctionStmt   = get!(& ast        .stmts [0]);
 
@@ -544,7 +1127,7 @@ mod tests {
 
         let mut ast = get_ast("recursive_fib.lox");
 
-        resolve(&mut ast);
+        resolve(&mut ast).unwrap();
 
         let fib_decl:    &FunctionStmt   = get!(& ast        .stmts [0]);
 
@@ -572,7 +1155,7 @@ mod tests {
 
         let mut ast = get_ast("upvalue_in_func_args.lox");
 
-        resolve(&mut ast);
+        resolve(&mut ast).unwrap();
 
         let decl_fn_1:   &FunctionStmt   = get!(& ast        .stmts [0]);
         let decl_a:      &FunctionParam  = get!(& decl_fn_1  .params[0]);
@@ -596,5 +1179,124 @@ mod tests {
         assert_eq!(print_d.var_type, VarType::Upvalue(UpvalueIndex(1)));
     }
 
+    #[test]
+    fn test_local_offsets() {
+
+        let mut ast = get_ast("local_offsets.lox");
+
+        resolve(&mut ast).unwrap();
+
+        let decl_f:      &FunctionStmt   = get!(& ast        .stmts [0]);
+
+        let decl_c:      &VarStmt        = get!(& decl_f     .body  [0]);
+        let decl_c_a:    &Variable       = get!(  decl_c     .initializer.as_ref().unwrap());
+
+        let print_g:     &PrintStmt      = get!(& decl_f     .body  [1]);
+        let call_g:      &Call           = get!(& print_g    .expr);
+        let call_g_a:    &Variable       = get!(& call_g     .args  [0]);
+        let call_g_b:    &Variable       = get!(& call_g     .args  [1]);
+
+        let while_stmt:  &WhileStmt      = get!(& decl_f     .body  [2]);
+        let while_body:  &Block          = get!(&*while_stmt .body);
+        let print_c:     &PrintStmt      = get!(& while_body .stmts [0]);
+        let print_c:     &Variable       = get!(& print_c    .expr);
+
+        // `c` isn't on the stack while its initializer runs
+        assert_eq!(decl_c_a.var_type, VarType::Local(StackOffset(1)));
+
+        // stack +1 for the callee, +1 for the first argument
+        assert_eq!(call_g_a.var_type, VarType::Local(StackOffset(3)));
+        assert_eq!(call_g_b.var_type, VarType::Local(StackOffset(3)));
+
+        // the loop condition has been popped
+        assert_eq!(print_c .var_type, VarType::Local(StackOffset(0)));
+    }
+
+    #[test]
+    fn test_captures() {
+
+        let mut ast = get_ast("captures.lox");
+
+        resolve(&mut ast).unwrap();
+
+        let decl_outer:  &FunctionStmt   = get!(& ast        .stmts [0]);
+        let decl_a:      &FunctionParam  = get!(& decl_outer .params[0]);
+        let decl_b:      &VarStmt        = get!(& decl_outer .body  [0]);
+        let decl_middle: &FunctionStmt   = get!(& decl_outer .body  [1]);
+
+        let decl_c:      &VarStmt        = get!(& decl_middle.body  [0]);
+        let ret_lambda:  &ReturnStmt     = get!(& decl_middle.body  [1]);
+        let lambda:      &Lambda         = get!(  ret_lambda .value.as_ref().unwrap());
+
+        assert_eq!(decl_a     .var_type, VarDeclType::Upvalue);
+        assert_eq!(decl_b     .var_type, VarDeclType::Upvalue);
+        assert_eq!(decl_c     .var_type, VarDeclType::Upvalue);
+        assert_eq!(decl_middle.var_type, VarDeclType::Local);
+
+        // `middle` isn't on the stack while its closure is made
+        assert_eq!(decl_middle.captures, vec![
+            Capture::Local(StackOffset(1)),
+            Capture::Local(StackOffset(0)),
+        ]);
+
+        // `a` is only captured once, and through `middle`
+        assert_eq!(lambda.func.captures, vec![
+            Capture::Upvalue(UpvalueIndex(0)),
+            Capture::Upvalue(UpvalueIndex(1)),
+            Capture::Local  (StackOffset (0)),
+        ]);
+    }
 
+    #[test]
+    fn test_errors() {
+
+        let mut ast = get_ast("errors.lox");
+
+        let errors = resolve(&mut ast).unwrap_err();
+        let errors: Vec<_> = errors.iter()
+            .map(|e| (&e.type_, e.token.lexeme.as_str(), e.token.line))
+            .collect();
+
+        assert_eq!(errors, vec![
+            (&Re::Redeclaration,          "a",      1),
+            (&Re::ReadInOwnInitializer,   "b",      2),
+            (&Re::Redeclaration,          "c",      4),
+            (&Re::ThisOutsideClass,       "this",  10),
+            (&Re::TopLevelReturn,         "return",13),
+            (&Re::SuperWithoutSuperclass, "super", 17),
+            (&Re::InheritFromSelf,        "Square",21),
+            (&Re::SuperOutsideClass,      "super", 27),
+        ]);
+    }
+    #[test]
+    fn test_lints() {
+
+        let mut ast = get_ast("lints.lox");
+
+        let lints = resolve(&mut ast).unwrap();
+        let lints: Vec<_> = lints.iter()
+            .map(|l| (l.type_, l.name.as_str()))
+            .collect();
+
+        type Lt = LintType;
+        assert_eq!(lints, vec![
+            (Lt::UnusedParameter,  "unused"),
+            (Lt::UnusedVariable,   "dead"),
+            (Lt::UnusedAssignment, "x"),
+            (Lt::UnusedAssignment, "x"),
+            (Lt::UnusedAssignment, "z"),
+            (Lt::Shadowing,        "used"),
+            (Lt::UnusedVariable,   "used"),
+            (Lt::UnusedFunction,   "helper"),
+            (Lt::UnusedClass,      "Unused"),
+            (Lt::UnreachableCode,  ""),
+            (Lt::UnusedVariable,   "error"),
+            (Lt::UnusedAssignment, "z"),
+            (Lt::UnreachableCode,  ""),
+            (Lt::UnusedAssignment, "overwritten"),
+            (Lt::UnusedParameter,  "reassigned"),
+            (Lt::UnusedAssignment, "reassigned"),
+            (Lt::Shadowing,        "global"),
+        ]);
+    }
 }
@@ -1,6 +1,6 @@
 
-use super::tokens::{Token, TokenType::{self, *}};
-use std::{collections::HashMap, string::String};
+use super::{diagnostic::Span, tokens::{Token, TokenType::{self, *}}};
+use std::{collections::HashMap, fmt::Display, string::String};
 
 type Keywords   = HashMap<String, TokenType>;
 type ScanResult = Result<Vec<Token>, Vec<ScannerError>>;
@@ -9,6 +9,22 @@ type Se = ScannerErrorType;
 type Tt = TokenType;
 
 pub fn scan_tokens(source: &str) -> ScanResult {
+    let scanner = scan(source);
+
+    if scanner.errors.len() == 0 {
+        Ok(scanner.tokens)
+    }
+    else {
+        Err(scanner.errors)
+    }
+}
+
+/// The `//` comments in the source, which the tokens leave out
+pub fn scan_comments(source: &str) -> Vec<Comment> {
+    scan(source).comments
+}
+
+fn scan(source: &str) -> Scanner {
     let mut scanner = Scanner::new(source);
 
     while !scanner.is_eof() {
@@ -18,13 +34,7 @@ pub fn scan_tokens(source: &str) -> ScanResult {
     }
 
     scanner.finalize();
-
-    if scanner.errors.len() == 0 {
-        Ok(scanner.tokens)
-    }
-    else {
-        Err(scanner.errors)
-    }
+    scanner
 }
 
 
@@ -35,9 +45,14 @@ struct Scanner {
     line:    usize,
     col:     usize,
 
-    source: Vec<char>,
-    tokens: Vec<Token>,
-    errors: Vec<ScannerError>,
+    source:  Vec<char>,
+    offsets: Vec<usize>, // byte offset of each character, and of the end of the source
+    tokens:   Vec<Token>,
+    errors:   Vec<ScannerError>,
+    comments: Vec<Comment>,
+
+    // the unclosed braces in each interpolated expression being scanned, and the string it's in, innermost last
+    interpolations: Vec<(usize, StringKind)>,
 
     keywords: Keywords
 }
@@ -45,21 +60,60 @@ struct Scanner {
 #[derive(Debug)]
 pub enum ScannerErrorType {
     UnterminatedString,
+    UnknownEscape(String),
+    InvalidUnicodeEscape,
+    UnterminatedInterpolation,
+    EmptyInterpolation,
     UnexpectedCharacter(String),
 }
 
+/// How a string literal is delimited, and so how its contents are read
+#[derive(Debug, Clone, Copy, Default)]
+struct StringKind {
+    raw:    bool,  // `r"..."`, without escapes or interpolation
+    triple: bool,  // `"""..."""`, which can span lines and contain quotes
+    hashes: usize, // `r#"..."#`, so a raw string can contain `"`
+    indent: usize, // the whitespace removed from the start of each line of a triple quoted string
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String, // excluding the leading `//`
+    pub line: usize,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct ScannerError {
     pub line:  usize,
     pub col:   usize,
+    pub span:  Span,
     pub type_: ScannerErrorType
 }
 
+impl Display for ScannerErrorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Se::UnterminatedString        => write!(f, "Unterminated string"),
+            Se::UnknownEscape(escape)     => write!(f, "Unknown escape sequence '{}'", escape),
+            Se::InvalidUnicodeEscape      => write!(f, "Expect 1 to 6 hex digits of a unicode character in '\\u{{...}}'"),
+            Se::UnterminatedInterpolation => write!(f, "Expect '}}' after the interpolated expression"),
+            Se::EmptyInterpolation        => write!(f, "Expect an expression between '{{' and '}}'"),
+            Se::UnexpectedCharacter(ch)   => write!(f, "Unexpected character: '{}'", ch),
+        }
+    }
+}
+
 impl Scanner {
     fn new(source: &str) -> Self {
 
         let bytes = source.chars().collect();
 
+        let offsets = source.char_indices()
+            .map(|(i, _)| i)
+            .chain([source.len()])
+            .collect();
+
         Self {
             start:   0,
             current: 0,
@@ -67,8 +121,12 @@ impl Scanner {
             col:     0,
 
             source: bytes,
-            tokens: Vec::new(),
-            errors: Vec::new(),
+            offsets,
+            tokens:   Vec::new(),
+            errors:   Vec::new(),
+            comments: Vec::new(),
+
+            interpolations: Vec::new(),
 
             keywords: get_keywords()
         }
@@ -87,6 +145,10 @@ impl Scanner {
         self.start = self.current;
         let ch = self.advance();
 
+        if ch == 'r' && self.is_raw_string() {
+            self.parse_raw_string();
+            return;
+        }
         if is_alpha(ch) {
             self.parse_identifier();
             return;
@@ -100,22 +162,39 @@ impl Scanner {
 
             '(' => self.add_token(LeftParen),
             ')' => self.add_token(RightParen),
-            '{' => self.add_token(LeftBrace),
-            '}' => self.add_token(RightBrace),
+            '{' => self.open_brace(),
+            '}' => self.close_brace(),
             ';' => self.add_token(Semicolon),
             ',' => self.add_token(Comma),
+            '.' if self.match_('.') && self.peek_next() == '.' => {
+                self.advance();
+                self.advance();
+                self.add_token(DotDotDot);
+            }
             '.' => self.add_token(Dot),
-            '-' => self.add_token(Minus),
-            '+' => self.add_token(Plus),
-            '/' => self.add_token(Slash),
-            '*' => self.add_token(Star),
-
-            '!' => self.add_token_match('=', Bang,    BangEqual),
-            '=' => self.add_token_match('=', Equal,   EqualEqual),
-            '<' => self.add_token_match('=', Less,    LessEqual),
-            '>' => self.add_token_match('=', Greater, GreaterEqual),
+            ':' => self.add_token(Colon),
+            '%' => self.add_token(Percent),
+            '&' => self.add_token(Ampersand),
+            '|' => self.add_token(Pipe),
+            '^' => self.add_token(Caret),
+
+            '!' => self.add_token_match('=', Bang,     BangEqual),
+            '-' => self.add_token_match('=', Minus,    MinusEqual),
+            '+' => self.add_token_match('=', Plus,     PlusEqual),
+            '/' => self.add_token_match('=', Slash,    SlashEqual),
+            '?' => self.add_token_match('?', Question, QuestionQuestion),
+
+            '=' => self.add_token_matches(Equal,   &[('=', EqualEqual),   ('>', Arrow)]),
+            '*' => self.add_token_matches(Star,    &[('*', Star2),        ('=', StarEqual)]),
+            '<' => self.add_token_matches(Less,    &[('=', LessEqual),    ('<', LessLess)]),
+            '>' => self.add_token_matches(Greater, &[('=', GreaterEqual), ('>', GreaterGreater)]),
+
+            '~' if self.match_('/') => {
+                self.advance();
+                self.add_token(TildeSlash);
+            }
 
-            '"' => self.parse_string(),
+            '"' => self.open_string(StringKind::default()),
 
             _   => self.add_error(Se::UnexpectedCharacter(ch.to_string())),
         }
@@ -132,8 +211,8 @@ impl Scanner {
                 }
 
                 '\n' => {
-                    self.line();
                     self.advance();
+                    self.line();
                 }
 
                 '/' => {
@@ -143,9 +222,12 @@ impl Scanner {
                     }
 
                     // Consume comment to the end of a line
+                    self.start = self.current;
                     while self.peek() != '\n' && !self.is_eof() {
                         self.advance();
                     }
+
+                    self.add_comment();
                 }
 
                 // don't consume
@@ -154,21 +236,288 @@ impl Scanner {
         }
     }
 
-    fn parse_string(&mut self) {
+    // `r"`, `r#"` or `r"""`, rather than an identifier starting with `r`
+    fn is_raw_string(&self) -> bool {
+        let hashes = (0..).take_while(|&i| self.seek(i) == '#').count();
+
+        self.seek(hashes) == '"'
+    }
+
+    fn parse_raw_string(&mut self) {
+        let mut hashes = 0;
+
+        while self.advance() == '#' {
+            hashes += 1;
+        }
+
+        self.open_string(StringKind { raw: true, hashes, ..Default::default() });
+    }
+
+    // after the opening `"`
+    fn open_string(&mut self, mut kind: StringKind) {
+        if self.peek() == '"' && self.peek_next() == '"' {
+            self.advance();
+            self.advance();
+
+            kind.triple = true;
+            kind.indent = self.open_triple_quoted(kind.raw);
+        }
+
+        self.parse_string(kind);
+    }
+
+    // A triple quoted string starting on the line after its quotes has the first line break left out,
+    // and the indentation every line has in common (counting the closing quotes) removed from every line.
+    // Returns the indentation
+    fn open_triple_quoted(&mut self, raw: bool) -> usize {
+        let rest_of_line = (0..).take_while(|&i| matches!(self.seek(i), ' ' | '\t' | '\r')).count();
+
+        if self.seek(rest_of_line) != '\n' {
+            return 0;
+        }
+
+        for _ in 0..=rest_of_line {
+            self.advance();
+        }
+        self.line();
+
+        let indent = self.triple_quoted_indent(raw);
+
+        for _ in 0..indent {
+            self.advance();
+        }
+
+        indent
+    }
+
+    // The indentation is the whitespace every line starts with, compared character by character,
+    // so a tab and a space aren't the same indentation.
+    // The closing quotes are found without scanning the interpolated expressions, which can't contain them
+    fn triple_quoted_indent(&self, raw: bool) -> usize {
+        let mut indent = usize::MAX;
+        let mut first  = None; // where the indentation of the first line counted starts
+        let mut i      = 0;
+
+        loop {
+            let start       = i;
+            let line_indent = (i..).take_while(|&i| matches!(self.seek(i), ' ' | '\t')).count();
+            i += line_indent;
+
+            // the whitespace this line has in common with the lines before it
+            let common = |indent: usize| match first {
+                Some(first) => (0..indent.min(line_indent)).take_while(|&k| self.seek(first + k) == self.seek(start + k)).count(),
+                None        => line_indent,
+            };
+
+            match self.seek(i) {
+                '\0' if self.current + i >= self.source.len() => return 0,
+
+                '"' if self.seek(i + 1) == '"' && self.seek(i + 2) == '"' => return common(indent),
+
+                '\n' | '\r' => {}
+
+                _ => {
+                    indent = common(indent);
+                    first  = first.or(Some(start));
+                }
+            }
+
+            while self.current + i < self.source.len() && self.seek(i) != '\n' {
+                if self.seek(i) == '"' && self.seek(i + 1) == '"' && self.seek(i + 2) == '"' {
+                    return indent;
+                }
+
+                i += if self.seek(i) == '\\' && !raw { 2 } else { 1 };
+            }
+
+            i += 1;
+        }
+    }
+
+    // `{` starts an interpolated expression, which the scanner returns to the string after,
+    // and `{{` and `}}` are braces. Placeholders for `format` (`{}`, `{0}`, `{:>8}`) are left as they are.
+    // Raw strings are read as they're written
+    fn parse_string(&mut self, kind: StringKind) {
+
+        let mut value = String::new();
+
+        loop {
+            if self.is_eof() {
+                self.add_error(Se::UnterminatedString);
+                return;
+            }
+
+            if self.is_string_end(kind) {
+                break;
+            }
+
+            let ch = self.advance();
+
+            match ch {
+                '\\' if !kind.raw => self.parse_escape(&mut value),
+
+                '{' | '}' if !kind.raw && self.peek() == ch => {
+                    self.advance();
+                    value.push(ch);
+                }
+
+                '{' if !kind.raw && self.is_placeholder() => {
+                    value.push(ch);
+
+                    while !self.is_eof() && !matches!(self.peek(), '}' | '"') {
+                        value.push(self.advance());
+                    }
+                }
+
+                '{' if !kind.raw => {
+                    self.add_interpolation(value, kind);
+                    return;
+                }
+
+                '\n' => {
+                    self.line();
+                    value.push(ch);
+
+                    for _ in 0..kind.indent {
+                        if !matches!(self.peek(), ' ' | '\t') {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+
+                _ => value.push(ch),
+            }
+        }
+
+        // closing quotes on their own line don't end the string with a line break
+        if kind.triple && self.is_on_own_line() {
+            let line = value.trim_end_matches([' ', '\t']);
+
+            if let Some(line) = line.strip_suffix('\n') {
+                value.truncate(line.len());
+            }
+        }
+
+        let quotes = if kind.triple { 3 } else { 1 };
+
+        for _ in 0..quotes + kind.hashes {
+            self.advance();
+        }
+
+        self.add_string_token(value);
+    }
+
+    // whether only whitespace comes before the current character on its line
+    fn is_on_own_line(&self) -> bool {
+        let mut before = self.source[self.start .. self.current].iter().rev();
+
+        before.find(|&&ch| !matches!(ch, ' ' | '\t')) == Some(&'\n')
+    }
+
+    fn is_string_end(&self, kind: StringKind) -> bool {
+        let quotes = if kind.triple { 3 } else { 1 };
+
+        (0      .. quotes)              .all(|i| self.seek(i) == '"') &&
+        (quotes .. quotes + kind.hashes).all(|i| self.seek(i) == '#')
+    }
 
-        while self.peek() != '"' && !self.is_eof() {
-            if self.peek() == '\n' {
-                self.line();
+    // after the `\`
+    fn parse_escape(&mut self, value: &mut String) {
+        let start = self.current - 1;
+
+        let escaped = match self.advance_or_eof() {
+            'n'  => '\n',
+            't'  => '\t',
+            'r'  => '\r',
+            '0'  => '\0',
+            '\\' => '\\',
+            '"'  => '"',
+            '\'' => '\'',
+            '{'  => '{',
+            '}'  => '}',
+
+            'u'  => match self.parse_unicode_escape() {
+                Some(ch) => ch,
+                None     => return self.error_from(start, Se::InvalidUnicodeEscape),
+            },
+
+            ch   => {
+                let escape = format!("\\{}", ch);
+                return self.error_from(start, Se::UnknownEscape(escape));
             }
+        };
+
+        value.push(escaped);
+    }
+
+    // `\u{1F600}`
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        if self.advance_or_eof() != '{' {
+            return None;
+        }
+
+        let mut hex = String::new();
+
+        while self.peek().is_ascii_hexdigit() && hex.len() < 6 {
+            hex.push(self.advance());
+        }
+
+        if self.advance_or_eof() != '}' || hex.is_empty() {
+            return None;
+        }
+
+        char::from_u32(u32::from_str_radix(&hex, 16).ok()?)
+    }
+
+    fn add_interpolation(&mut self, value: String, kind: StringKind) {
+        self.tokens.push(Token::new(Tt::Interpolation, &value, self.line, self.col).with_span(self.span()));
+        self.start = self.current;
+
+        self.skip_whitespace();
+
+        if self.peek() == '}' {
             self.advance();
+            self.add_error(Se::EmptyInterpolation);
+
+            self.start = self.current;
+            self.parse_string(kind);
+            return;
         }
 
-        if self.is_eof() {
-            self.add_error(Se::UnterminatedString);
+        self.interpolations.push((0, kind));
+    }
+
+    // an expression can't start with `:`, and there's no point interpolating a number
+    fn is_placeholder(&self) -> bool {
+        let digits = (0..).take_while(|&i| is_digit(self.seek(i))).count();
+
+        matches!(self.seek(digits), '}' | ':')
+    }
+
+    fn open_brace(&mut self) {
+        if let Some((depth, _)) = self.interpolations.last_mut() {
+            *depth += 1;
         }
 
-        self.advance();
-        self.add_string_token();
+        self.add_token(LeftBrace);
+    }
+
+    // the end of an interpolated expression continues the string
+    fn close_brace(&mut self) {
+        match self.interpolations.last_mut() {
+            Some((0, kind)) => {
+                let kind = *kind;
+
+                self.interpolations.pop();
+                self.parse_string(kind);
+            }
+            Some((depth, _)) => {
+                *depth -= 1;
+                self.add_token(RightBrace);
+            }
+            None => self.add_token(RightBrace),
+        }
     }
 
     fn parse_number(&mut self) {
@@ -218,14 +567,14 @@ impl Scanner {
 
     fn add_token(&mut self, type_: TokenType) {
         let lexeme = self.get_lexeme();
-        self.tokens.push(Token::new(type_, &lexeme, self.line, self.col));
+        self.tokens.push(Token::new(type_, &lexeme, self.line, self.col).with_span(self.span()));
 
         self.start = self.current;
     }
 
-    fn add_string_token(&mut self) {
-        let lexeme = self.get_wrapped_lexeme(1);
-        self.tokens.push(Token::new(Tt::String, &lexeme, self.line, self.col));
+    // the lexeme of a string is its value, without the quotes and with the braces unescaped
+    fn add_string_token(&mut self, value: String) {
+        self.tokens.push(Token::new(Tt::String, &value, self.line, self.col).with_span(self.span()));
 
         self.start = self.current;
     }
@@ -240,6 +589,20 @@ impl Scanner {
         }
     }
 
+    fn add_token_matches(&mut self, type_: TokenType, matches: &[(char, TokenType)]) {
+        match matches.iter().find(|(ch, _)| self.match_(*ch)) {
+            Some(&(_, second_type)) => self.add_token_match(self.peek(), type_, second_type),
+            None                    => self.add_token(type_),
+        }
+    }
+
+    fn advance_or_eof(&mut self) -> char {
+        match self.is_eof() {
+            true  => '\0',
+            false => self.advance(),
+        }
+    }
+
     fn advance(&mut self) -> char {
         let i = self.current;
         self.current += 1;
@@ -284,18 +647,43 @@ impl Scanner {
         chars_to_str(&self.source[self.start..self.current])
     }
 
-    fn get_wrapped_lexeme(&self, i: usize) -> String {
-        chars_to_str(&self.source[self.start +i .. self.current -i])
+    // the span of the current lexeme
+    fn span(&self) -> Span {
+        Span::new(self.offsets[self.start], self.offsets[self.current])
+    }
+
+    fn add_comment(&mut self) {
+        self.comments.push(Comment {
+            text: self.get_lexeme()[2..].to_owned(),
+            line: self.line,
+            span: self.span(),
+        });
     }
 
     fn finalize(&mut self) {
-        self.tokens.push(Token::new(Tt::EOF, "", self.line, self.col));
+        if !self.interpolations.is_empty() {
+            self.start = self.current;
+            self.add_error(Se::UnterminatedInterpolation);
+        }
+
+        let end = self.offsets[self.current];
+        self.tokens.push(Token::new(Tt::EOF, "", self.line, self.col).with_span(Span::new(end, end)));
+    }
+
+    // an error in the middle of a token, from the character at `start`
+    fn error_from(&mut self, start: usize, err_type: ScannerErrorType) {
+        let token_start = self.start;
+
+        self.start = start;
+        self.add_error(err_type);
+        self.start = token_start;
     }
 
     fn add_error(&mut self, err_type: ScannerErrorType) {
         self.errors.push(ScannerError {
             line:  self.line,
             col:   self.col,
+            span:  self.span(),
             type_: err_type,
         });
     }
@@ -332,19 +720,23 @@ pub fn get_keywords() -> HashMap<String, TokenType> {
     };
 
     add("and",     Tt::And);
+    add("catch",   Tt::Catch);
     add("class",   Tt::Class);
     add("else",    Tt::Else);
     add("false",   Tt::False);
     add("for",     Tt::For);
     add("fun",     Tt::Fun);
     add("if",      Tt::If);
+    add("import",  Tt::Import);
     add("nil",     Tt::Nil);
     add("or",      Tt::Or);
     add("print",   Tt::Print);
     add("return",  Tt::Return);
     add("super",   Tt::Super);
     add("this",    Tt::This);
+    add("throw",   Tt::Throw);
     add("true",    Tt::True);
+    add("try",     Tt::Try);
     add("var",     Tt::Var);
     add("while",   Tt::While);
 
@@ -362,9 +754,9 @@ fn format_ch(ch: char) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::script::test::get_example_001;
+    use crate::script::{test::get_example_001, tokens::TokenType};
 
-    use super::scan_tokens;
+    use super::{scan_comments, scan_tokens};
 
 
 
@@ -380,4 +772,132 @@ mod tests {
         assert_eq!(tokens, example.tokens);
     }
 
+    #[test]
+    fn spans() {
+        let source = "print \"é\" +\n  12.5;";
+
+        let tokens = scan_tokens(source).unwrap();
+        let lexemes: Vec<_> = tokens.iter()
+            .map(|t| &source[t.span.start .. t.span.end])
+            .collect();
+
+        assert_eq!(lexemes, vec!["print", "\"é\"", "+", "12.5", ";", ""]);
+
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.type_,      TokenType::EOF);
+        assert_eq!(eof.span.start, source.len());
+    }
+
+    #[test]
+    fn comments() {
+        let source = "// first\nprint \"// not a comment\"; // second";
+
+        let comments = scan_comments(source);
+        let texts: Vec<_> = comments.iter()
+            .map(|c| (c.text.as_str(), c.line, &source[c.span.start .. c.span.end]))
+            .collect();
+
+        assert_eq!(texts, vec![
+            (" first",  1, "// first"),
+            (" second", 2, "// second"),
+        ]);
+    }
+
+    #[test]
+    fn interpolation() {
+        let source = "\"{{a}} {f(x)} and {\"{b}\"}!{} {0:>2}\"";
+
+        let tokens = scan_tokens(source).unwrap();
+        let tokens: Vec<_> = tokens.iter()
+            .map(|t| (t.type_, t.lexeme.as_str(), &source[t.span.start .. t.span.end]))
+            .collect();
+
+        type Tt = TokenType;
+        assert_eq!(tokens, vec![
+            (Tt::Interpolation,  "{a} ",        "\"{{a}} {"),
+            (Tt::Identifier,     "f",           "f"),
+            (Tt::LeftParen,      "(",           "("),
+            (Tt::Identifier,     "x",           "x"),
+            (Tt::RightParen,     ")",           ")"),
+            (Tt::Interpolation,  " and ",       "} and {"),
+            (Tt::Interpolation,  "",            "\"{"),
+            (Tt::Identifier,     "b",           "b"),
+            (Tt::String,         "",            "}\""),
+            (Tt::String,         "!{} {0:>2}",  "}!{} {0:>2}\""),
+            (Tt::EOF,            "",            ""),
+        ]);
+
+        let errors = |source: &str| -> Vec<String> {
+            scan_tokens(source).unwrap_err().iter()
+                .map(|e| e.type_.to_string())
+                .collect()
+        };
+
+        assert_eq!(errors("\"{ }\""),    vec!["Expect an expression between '{' and '}'"]);
+        assert_eq!(errors("\"{a"),        vec!["Expect '}' after the interpolated expression"]);
+        assert_eq!(errors("\"{a} b"),     vec!["Unterminated string"]);
+    }
+
+    #[test]
+    fn operators() {
+        let types: Vec<_> = scan_tokens("% ~/ ** * & | ^ << <= < >> >= > / ? ?? : += -= *= /= => == = ... . // comment").unwrap()
+            .iter()
+            .map(|t| t.type_)
+            .collect();
+
+        type Tt = TokenType;
+        assert_eq!(types, vec![
+            Tt::Percent,   Tt::TildeSlash, Tt::Star2,          Tt::Star,
+            Tt::Ampersand, Tt::Pipe,       Tt::Caret,
+            Tt::LessLess,  Tt::LessEqual,  Tt::Less,
+            Tt::GreaterGreater, Tt::GreaterEqual, Tt::Greater,
+            Tt::Slash,     Tt::Question,   Tt::QuestionQuestion, Tt::Colon,
+            Tt::PlusEqual, Tt::MinusEqual, Tt::StarEqual,        Tt::SlashEqual,
+            Tt::Arrow,     Tt::EqualEqual, Tt::Equal,
+            Tt::DotDotDot, Tt::Dot,
+            Tt::EOF,
+        ]);
+    }
+
+    #[test]
+    fn string_literals() {
+        let strings = |source: &str| -> Vec<String> {
+            scan_tokens(source).unwrap().into_iter()
+                .filter(|t| t.type_ == TokenType::String)
+                .map(|t| t.lexeme)
+                .collect()
+        };
+
+        assert_eq!(strings(r#""a\tb\n\"c\" \\ \{ \u{48}\u{1F600}""#), vec!["a\tb\n\"c\" \\ { H\u{1F600}"]);
+        assert_eq!(strings(r###"r"C:\dir\{x}" r#"say "hi""# r"""a"b""""###), vec![r"C:\dir\{x}", r#"say "hi""#, "a\"b"]);
+        assert_eq!(strings(r#" "" """""" "#),      vec!["", ""]);
+
+        let source = "
+            var sql = \"\"\"
+                select *
+                  from {table}
+                 where id = 1
+                \"\"\";
+            var inline = \"\"\"a \"quoted\" \"\" b\"\"\";
+        ";
+        assert_eq!(strings(source), vec!["\n where id = 1", "a \"quoted\" \"\" b"]);
+
+        let tokens = scan_tokens(source).unwrap();
+        assert_eq!(tokens[3].lexeme, "select *\n  from ");
+
+        // a tab isn't the same indentation as a space, only the whitespace every line starts with is removed
+        assert_eq!(strings("\"\"\"\n\t  a\n\t\tb\n\t  \"\"\""),   vec!["  a\n\tb"]);
+        assert_eq!(strings("\"\"\"\n    a\n\tb\n    \"\"\""),     vec!["    a\n\tb"]);
+
+        let errors = |source: &str| -> Vec<(String, String)> {
+            scan_tokens(source).unwrap_err().iter()
+                .map(|e| (e.type_.to_string(), source[e.span.start .. e.span.end].to_string()))
+                .collect()
+        };
+
+        assert_eq!(errors(r#""a\qb""#),     vec![("Unknown escape sequence '\\q'".into(), r"\q".into())]);
+        assert_eq!(errors(r#""\u{110000}""#), vec![("Expect 1 to 6 hex digits of a unicode character in '\\u{...}'".into(), r"\u{110000}".into())]);
+        assert_eq!(errors("\"\"\"a\"\""),   vec![("Unterminated string".into(), "\"\"\"a\"\"".into())]);
+    }
+
 }
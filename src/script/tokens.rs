@@ -1,5 +1,7 @@
 use core::fmt;
 
+use super::diagnostic::Span;
+
 #[derive(Debug)]
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TokenType {
@@ -8,22 +10,32 @@ pub enum TokenType {
   LeftParen,  RightParen,
   LeftBrace,  RightBrace,
   Comma,      Dot,        Minus, Plus,
-  Semicolon,  Slash,      Star,
+  Semicolon,  Slash,      Star,  Percent,
+  Ampersand,  Pipe,       Caret, Colon,
 
   // One or two character tokens.
   Bang,    BangEqual,
-  Equal,   EqualEqual,
-  Greater, GreaterEqual,
-  Less,    LessEqual,
+  Equal,   EqualEqual,   Arrow,
+  Greater, GreaterEqual, GreaterGreater,
+  Less,    LessEqual,    LessLess,
+  Star2,   TildeSlash,   DotDotDot,
+  Question,  QuestionQuestion,
+
+  // Compound assignment.
+  PlusEqual, MinusEqual, StarEqual, SlashEqual,
 
   // Literals.
   Identifier, String, Number,
 
+  // The part of a string before an interpolated expression (`"Table {`, or `} and {`)
+  Interpolation,
+
   // Keywords.
   And,   Class,  Else,  False,
-  For,   Fun,    If,    Nil,   Or,
+  For,   Fun,    If,    Import, Nil,   Or,
   Print, Return, Super, This,
   True,  Var,    While,
+  Throw, Try,    Catch,
 
   Error, EOF
 }
@@ -35,6 +47,9 @@ pub struct Token {
     pub lexeme: String,
     pub line:   usize,
     pub col:    usize,
+
+    /// Where the token is in the source, including the quotes of a string
+    pub span:   Span,
 }
 
 impl Token {
@@ -44,9 +59,15 @@ impl Token {
             lexeme: String::from(lexeme),
             line,
             col,
+            span:   Span::default(),
         }
     }
 
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
     pub fn new_true() -> Self {
         Self::new(
             TokenType::True,
@@ -65,6 +86,19 @@ impl Token {
         )
     }
 
+    /// The binary operator a compound assignment applies, `+` for `+=`
+    pub fn compound_operator(&self) -> Option<Token> {
+        let (type_, lexeme) = match self.type_ {
+            TokenType::PlusEqual  => (TokenType::Plus,  "+"),
+            TokenType::MinusEqual => (TokenType::Minus, "-"),
+            TokenType::StarEqual  => (TokenType::Star,  "*"),
+            TokenType::SlashEqual => (TokenType::Slash, "/"),
+            _                     => return None,
+        };
+
+        Some(Token::new(type_, lexeme, self.line, self.col).with_span(self.span))
+    }
+
     pub fn to_string(&self) -> String {
         format!("{} {} {} {}", self.type_, self.lexeme, self.line, self.col)
     }
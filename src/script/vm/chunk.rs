@@ -3,42 +3,51 @@ use std::{fmt::Display, ops::{Deref, DerefMut}};
 use ast_macro::derive_all;
 use gc_arena::{Collect, Gc, Mutation, lock::{GcRefLock, RefLock}};
 
-use crate::script::vm::object::{ObjFunction};
+use crate::script::{diagnostic::Span, vm::object::{ObjFunction}};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Collect)]
 #[collect(no_drop)]
 pub enum OpCode<'gc> {
-    GetConstant { index:     ConstIndex },
+    GetConstant  { index:     ConstIndex },
 
-    DefGlobal   { name_idx:  ConstIndex },
-    GetGlobal   { name_idx:  ConstIndex },
-    SetGlobal   { name_idx:  ConstIndex },
+    DefGlobal    { name_idx:  ConstIndex },
+    GetGlobal    { name_idx:  ConstIndex },
+    SetGlobal    { name_idx:  ConstIndex },
 
-    GetProperty { name_idx:  ConstIndex },
-    SetProperty { name_idx:  ConstIndex },
+    GetProperty  { name_idx:  ConstIndex },
+    SetProperty  { name_idx:  ConstIndex },
 
-    GetLocal    { offset:    StackOffset },
-    SetLocal    { offset:    StackOffset },
+    GetLocal     { offset:    StackOffset },
+    SetLocal     { offset:    StackOffset },
 
-    GetUpvalue  { index:     UpvalueIndex },
-    SetUpvalue  { index:     UpvalueIndex },
-    PushUpvalue { index:     StackOffset },
+    GetUpvalue   { index:     UpvalueIndex },
+    SetUpvalue   { index:     UpvalueIndex },
+    CloseLocal   { offset:    StackOffset },
 
-    JumpIfFalse { offset:    Offset },
-    JumpIfTrue  { offset:    Offset },
-    Jump        { offset:    Offset },
+    JumpIfFalse  { offset:    Offset },
+    JumpIfTrue   { offset:    Offset },
+    JumpIfNotNil { offset:    Offset },
+    JumpIfPassed { param:     usize, offset: Offset },
+    Jump         { offset:    Offset },
 
-    Loop        { offset:    Offset },
+    Loop         { offset:    Offset },
 
-    Call        { arg_count: usize },
-    Class       { name_idx:  ConstIndex },
-    Closure     { func:      Gc<'gc, ObjFunction<'gc>> },
+    // starts a try block, with the handler at `offset`
+    Try          { offset:    Offset },
+
+    Call         { arg_count: usize, named: usize },
+    Concat       { count:     usize },
+    Class        { name_idx:  ConstIndex },
+    Closure      { func:      Gc<'gc, ObjFunction<'gc>> },
+    Import       { module:    ModuleIndex },
 
 
     Nil,
     True,
     False,
     Pop,
+    Dup,
+    Inherit,
     Equal,
     Greater,
     Less,
@@ -46,10 +55,20 @@ pub enum OpCode<'gc> {
     Subtract,
     Multiply,
     Divide,
+    IntDivide,
+    Modulo,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Not,
     Negate,
     Print,
     Return,
+    EndTry,
+    Throw,
 }
 
 #[derive(Debug, Clone, Collect, PartialEq, Eq)]
@@ -57,6 +76,10 @@ pub enum OpCode<'gc> {
 pub struct Chunk<'gc> {
     pub code:      Vec<OpCode<'gc>>,
     pub lines:     Vec<usize>,
+
+    /// The source of the expression or statement each op was compiled from
+    #[collect(require_static)]
+    pub spans:     Vec<Span>,
 }
 
 impl<'gc> Chunk<'gc> {
@@ -64,14 +87,16 @@ impl<'gc> Chunk<'gc> {
         Gc::new(ctx, RefLock::new(Self {
             code:  vec![],
             lines: vec![],
+            spans: vec![],
         }))
     }
 
-    pub fn write_op(&mut self, op: OpCode<'gc>, line: usize) -> BytecodeIndex {
+    pub fn write_op(&mut self, op: OpCode<'gc>, line: usize, span: Span) -> BytecodeIndex {
         let index = self.code.len();
 
         self.code .push(op);
         self.lines.push(line);
+        self.spans.push(span);
 
         BytecodeIndex(index)
     }
@@ -82,28 +107,35 @@ impl<'gc> Display for OpCode<'gc> {
 
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match self {
-            OpCode::GetConstant { index }                      => format!("Constant {}",      **index   ),
-            OpCode::DefGlobal   { name_idx }                   => format!("DefGlobal {}",     **name_idx),
-            OpCode::GetGlobal   { name_idx }                   => format!("GetGlobal {}",     **name_idx),
-            OpCode::SetGlobal   { name_idx }                   => format!("SetGlobal {}",     **name_idx),
-            OpCode::GetProperty { name_idx }                   => format!("GetProperty {}",   **name_idx),
-            OpCode::SetProperty { name_idx }                   => format!("SetProperty {}",   **name_idx),
-            OpCode::GetLocal    { offset }                     => format!("GetLocal {}",      **offset),
-            OpCode::SetLocal    { offset }                     => format!("SetLocal {}",      **offset),
-            OpCode::GetUpvalue  { index }                      => format!("GetUpvalue {}",    **index   ),
-            OpCode::SetUpvalue  { index }                      => format!("SetUpvalue {}",    **index   ),
-            OpCode::PushUpvalue { index }                      => format!("PushUpvalue {}",   **index),
-            OpCode::JumpIfFalse { offset }                     => format!("JumpIfFalse {}",   **offset  ),
-            OpCode::JumpIfTrue  { offset }                     => format!("JumpIfTrue {}",    **offset  ),
-            OpCode::Jump        { offset }                     => format!("Jump {}",          **offset  ),
-            OpCode::Loop        { offset }                     => format!("Loop {}",          **offset  ),
-            OpCode::Call        { arg_count }                  => format!("Call (args: {})",  arg_count ),
-            OpCode::Class       { name_idx }                   => format!("Class {}",         **name_idx),
-            OpCode::Closure     { func }                       => format!("Closure {}",       func.name),
+            OpCode::GetConstant  { index }                     => format!("Constant {}",      **index   ),
+            OpCode::DefGlobal    { name_idx }                  => format!("DefGlobal {}",     **name_idx),
+            OpCode::GetGlobal    { name_idx }                  => format!("GetGlobal {}",     **name_idx),
+            OpCode::SetGlobal    { name_idx }                  => format!("SetGlobal {}",     **name_idx),
+            OpCode::GetProperty  { name_idx }                  => format!("GetProperty {}",   **name_idx),
+            OpCode::SetProperty  { name_idx }                  => format!("SetProperty {}",   **name_idx),
+            OpCode::GetLocal     { offset }                    => format!("GetLocal {}",      **offset),
+            OpCode::SetLocal     { offset }                    => format!("SetLocal {}",      **offset),
+            OpCode::GetUpvalue   { index }                     => format!("GetUpvalue {}",    **index   ),
+            OpCode::SetUpvalue   { index }                     => format!("SetUpvalue {}",    **index   ),
+            OpCode::CloseLocal   { offset }                    => format!("CloseLocal {}",    **offset),
+            OpCode::JumpIfFalse  { offset }                    => format!("JumpIfFalse {}",   **offset  ),
+            OpCode::JumpIfTrue   { offset }                    => format!("JumpIfTrue {}",    **offset  ),
+            OpCode::JumpIfNotNil { offset }                    => format!("JumpIfNotNil {}",  **offset  ),
+            OpCode::JumpIfPassed { param, offset }             => format!("JumpIfPassed {} {}", param, **offset),
+            OpCode::Jump         { offset }                    => format!("Jump {}",          **offset  ),
+            OpCode::Loop         { offset }                    => format!("Loop {}",          **offset  ),
+            OpCode::Try          { offset }                    => format!("Try {}",           **offset  ),
+            OpCode::Call         { arg_count, named }          => format!("Call (args: {}, named: {})", arg_count, named),
+            OpCode::Concat       { count }                     => format!("Concat (parts: {})", count),
+            OpCode::Class        { name_idx }                  => format!("Class {}",         **name_idx),
+            OpCode::Closure      { func }                      => format!("Closure {}",       func.name),
+            OpCode::Import       { module }                    => format!("Import {}",        **module),
             OpCode::Nil                                        => format!("Nil"),
             OpCode::True                                       => format!("True"),
             OpCode::False                                      => format!("False"),
             OpCode::Pop                                        => format!("Pop"),
+            OpCode::Dup                                        => format!("Dup"),
+            OpCode::Inherit                                    => format!("Inherit"),
             OpCode::Equal                                      => format!("Equal"),
             OpCode::Greater                                    => format!("Greater"),
             OpCode::Less                                       => format!("Less"),
@@ -111,10 +143,20 @@ impl<'gc> Display for OpCode<'gc> {
             OpCode::Subtract                                   => format!("Subtract"),
             OpCode::Multiply                                   => format!("Multiply"),
             OpCode::Divide                                     => format!("Divide"),
+            OpCode::IntDivide                                  => format!("IntDivide"),
+            OpCode::Modulo                                     => format!("Modulo"),
+            OpCode::Power                                      => format!("Power"),
+            OpCode::BitAnd                                     => format!("BitAnd"),
+            OpCode::BitOr                                      => format!("BitOr"),
+            OpCode::BitXor                                     => format!("BitXor"),
+            OpCode::ShiftLeft                                  => format!("ShiftLeft"),
+            OpCode::ShiftRight                                 => format!("ShiftRight"),
             OpCode::Not                                        => format!("Not"),
             OpCode::Negate                                     => format!("Negate"),
             OpCode::Print                                      => format!("Print"),
             OpCode::Return                                     => format!("Return"),
+            OpCode::EndTry                                     => format!("EndTry"),
+            OpCode::Throw                                      => format!("Throw"),
         })
     }
 }
@@ -140,6 +182,17 @@ pub struct Offset       (pub usize);
 #[derive_all]
 pub struct BytecodeIndex(pub usize);
 
+#[derive_all]
+pub struct ModuleIndex  (pub usize);
+
+/// Where a new closure finds each of its upvalues, relative to the function creating it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Collect)]
+#[collect(no_drop)]
+pub enum Capture {
+    Local  (StackOffset),
+    Upvalue(UpvalueIndex),
+}
+
 
 // TODO: macro this
 impl Deref for BytecodeIndex {
@@ -213,6 +266,14 @@ impl Deref for Offset {
     }
 }
 
+impl Deref for ModuleIndex {
+    type Target = usize;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 impl Deref for ConstIndex {
     type Target = usize;
 
@@ -5,6 +5,7 @@ use gc_arena::{Gc, Mutation};
 
 use crate::script::{
     ast::*,
+    diagnostic::Span,
     tokens::{Token, TokenType},
     vm::{
         Root, chunk::*, object::{ObjFunction, ObjPtr, Object}
@@ -20,8 +21,15 @@ type Op<'gc> = OpCode<'gc>;
 pub fn compile<'gc>(ast: Ast, root: &'gc mut Root<'gc>, ctx: &'gc Mutation<'gc>) ->
     CompilerResult<()>
 {
+    compile_module(ast, ModuleIndex(0), root, ctx)
+}
 
-    let mut compiler = Compiler::new(root, ctx);
+/// Compiles a script whose globals live in `module`, pushing its top level function onto `Root::functions`
+pub fn compile_module<'gc>(ast: Ast, module: ModuleIndex, root: &'gc mut Root<'gc>, ctx: &'gc Mutation<'gc>) ->
+    CompilerResult<()>
+{
+
+    let mut compiler = Compiler::new(root, module, ctx);
 
     for stmt in ast.stmts {
         compiler.compile_stmt(stmt)?;
@@ -40,7 +48,9 @@ pub fn compile<'gc>(ast: Ast, root: &'gc mut Root<'gc>, ctx: &'gc Mutation<'gc>)
 struct Compiler<'gc> {
     root:           &'gc mut Root<'gc>,
     ctx:            &'gc Mutation<'gc>,
+    module:         ModuleIndex,
     line:           usize,
+    spans:          Vec<Span>, // the nodes being compiled, innermost last
     scope_depth:    usize,
     function_stack: Vec<Func<'gc>>,
 
@@ -89,7 +99,10 @@ pub type CompilerResult<T> = Result<T, CompileError>;
 enum JumpType {
     IfFalse,
     IfTrue,
+    IfNotNil,
+    IfPassed(usize),
     Always,
+    Try,
 }
 
 impl<'gc> Func<'gc> {
@@ -104,17 +117,19 @@ impl<'gc> Func<'gc> {
 
 impl<'gc> Compiler<'gc> {
 
-    fn new(root: &'gc mut Root<'gc>, ctx: &'gc Mutation<'gc>) -> Self {
+    fn new(root: &'gc mut Root<'gc>, module: ModuleIndex, ctx: &'gc Mutation<'gc>) -> Self {
 
         let chunk = Chunk::new(ctx);
-        let func  = Gc::new(ctx, ObjFunction::new("script".to_owned(), 0, chunk));
+        let func  = Gc::new(ctx, ObjFunction::new("script".to_owned(), 0, chunk, module));
         root.functions.push(func);
 
         Self {
             root,
             ctx,
+            module,
 
             line:           0,
+            spans:          vec![],
             scope_depth:    0,
             function_stack: vec![Func::new(FuncType::Script, func)],
 
@@ -125,18 +140,24 @@ impl<'gc> Compiler<'gc> {
     // Statements
 
     fn compile_stmt(&mut self, stmt: Stmt) -> CompilerResult<()> {
+        self.spans.push(stmt.span());
+
         match stmt {
             Stmt::Block      (stmt) => self.compile_block_stmt (stmt)?,
             Stmt::Class      (stmt) => self.compile_class_decl (stmt),
             Stmt::Expression (stmt) => self.compile_expr_stmt  (stmt),
             Stmt::Function   (stmt) => self.compile_func_decl  (stmt)?,
             Stmt::If         (stmt) => self.compile_if_stmt    (stmt)?,
+            Stmt::Import     (stmt) => self.compile_import_stmt(stmt),
             Stmt::Print      (stmt) => self.compile_print_stmt (stmt.expr),
             Stmt::Return     (stmt) => self.compile_return_stmt(stmt),
+            Stmt::Throw      (stmt) => self.compile_throw_stmt (stmt),
+            Stmt::Try        (stmt) => self.compile_try_stmt   (stmt)?,
             Stmt::Var        (stmt) => self.compile_var_decl   (stmt)?,
             Stmt::While      (stmt) => self.compile_while_stmt (stmt)?,
         };
 
+        self.spans.pop();
         Ok(())
     }
 
@@ -162,12 +183,16 @@ impl<'gc> Compiler<'gc> {
 
         self.write_op(Op::Class { name_idx });
 
+        if let Some(superclass) = class.superclass {
+            self.compile_var_expr(superclass);
+            self.write_op(Op::Inherit);
+        }
+
         if is_global.is_some() {
             self.define_global(name_idx);
         }
 
-        self.write_op(Op::Pop);
-
+        self.close_local(class.var_type, StackOffset(0));
     }
 
     fn compile_expr_stmt(&mut self, expr_stmt: ExpressionStmt) {
@@ -178,6 +203,7 @@ impl<'gc> Compiler<'gc> {
     fn compile_func_decl(&mut self, func: FunctionStmt) -> CompilerResult<()> {
 
         let global_idx = self.declare_variable(&func.name);
+        let var_type   = func.var_type;
 
         let obj = self.make_function(func, FuncType::Function)?;
 
@@ -189,6 +215,7 @@ impl<'gc> Compiler<'gc> {
             self.define_global(name_idx);
         }
 
+        self.close_local(var_type, StackOffset(0));
 
         Ok(())
     }
@@ -200,17 +227,42 @@ impl<'gc> Compiler<'gc> {
         -> CompilerResult<Gc<'gc, ObjFunction<'gc>>>
     {
 
+        let span  = stmt.span;
+        let arity = stmt.params.len();
+        let rest  = stmt.has_rest();
         let chunk = Chunk::new(self.ctx);
-        let func  = Gc::new(self.ctx, ObjFunction::new(stmt.name.lexeme, stmt.params.len(), chunk));
-        let func  = Func::new(func_type, func);
+
+        let mut func = ObjFunction::new(stmt.name.lexeme, arity, chunk, self.module);
+        func.rest     = rest;
+        func.params   = stmt.params.iter().map(|param| param.name.lexeme.clone()).collect();
+        func.required = stmt.params.iter().take_while(|param| param.default.is_none() && !param.rest).count();
+        func.captures = stmt.captures;
+
+        let func  = Func::new(func_type, Gc::new(self.ctx, func));
 
         self.root.functions.push(func.func_obj);
         self.function_stack.push(func);
 
         self.begin_scope();
 
-        for (i, _) in stmt.params.iter().enumerate().filter(|a| a.1.var_type == VarDeclType::Upvalue) {
-            self.write_op(Op::PushUpvalue { index: StackOffset(i) });
+        // the slot is below the arguments, and the first argument is deepest
+        self.close_local(stmt.slot_type, StackOffset(arity));
+
+        for (i, param) in stmt.params.iter().enumerate() {
+            self.close_local(param.var_type, StackOffset(arity - 1 - i));
+        }
+
+        // a default is only evaluated when the caller left its parameter out
+        for (i, param) in stmt.params.into_iter().enumerate() {
+            let Some(default) = param.default else {
+                continue;
+            };
+
+            let jump_op = self.emit_jump(JumpType::IfPassed(i));
+            self.compile_expr(default);
+            self.write_op(Op::SetLocal { offset: StackOffset(arity - i) });
+            self.write_op(Op::Pop);
+            self.patch_jump(jump_op);
         }
 
         for stmt in stmt.body.into_iter() {
@@ -221,6 +273,7 @@ impl<'gc> Compiler<'gc> {
             Token::new(
                 TokenType::Return, "return", 0, 0),
             None,
+            span,
         );
         self.compile_stmt(implicit_return)?;
 
@@ -235,6 +288,13 @@ impl<'gc> Compiler<'gc> {
 
     }
 
+    // a local captured by a closure is moved into a box both of them share
+    fn close_local(&mut self, var_type: VarDeclType, offset: StackOffset) {
+        if var_type == VarDeclType::Upvalue {
+            self.write_op(Op::CloseLocal { offset });
+        }
+    }
+
     fn compile_if_stmt(&mut self, if_stmt: IfStmt) -> CompilerResult<()> {
 
         self.compile_expr(if_stmt.condition);
@@ -264,6 +324,26 @@ impl<'gc> Compiler<'gc> {
     }
 
 
+    // the resolver only allows imports at the top level, so the names are always globals
+    fn compile_import_stmt(&mut self, import: ImportStmt) {
+        let module = import.module.expect("expect imported module to be loaded");
+
+        if let Some(alias) = import.alias {
+            let name_idx = self.make_identifier_constant(alias);
+
+            self.write_op(Op::Import { module });
+            self.define_global(name_idx);
+        }
+
+        for name in import.names {
+            let name_idx = self.make_identifier_constant(name);
+
+            self.write_op(Op::Import      { module });
+            self.write_op(Op::GetProperty { name_idx });
+            self.define_global(name_idx);
+        }
+    }
+
     fn compile_print_stmt(&mut self, expr: Expr) {
         self.compile_expr(expr);
         self.write_op(Op::Print);
@@ -278,6 +358,34 @@ impl<'gc> Compiler<'gc> {
         self.write_op(Op::Return);
     }
 
+    fn compile_throw_stmt(&mut self, throw: ThrowStmt) {
+        self.line = throw.keyword.line;
+
+        self.compile_expr(throw.value);
+        self.write_op(Op::Throw);
+    }
+
+    fn compile_try_stmt(&mut self, try_: TryStmt) -> CompilerResult<()> {
+        let handler_op = self.emit_jump(JumpType::Try);     // goto: Handler
+
+        self.compile_block_stmt(try_.body)?;
+        self.write_op(Op::EndTry);
+
+        let end_op = self.emit_jump(JumpType::Always);      // goto: End
+
+        // the vm unwinds the stack to where it was at the start of the try block, then pushes the thrown value
+        self.patch_jump(handler_op);
+
+        self.begin_scope();
+        self.close_local(try_.var_type, StackOffset(0));
+        self.compile_block_stmt(try_.handler)?;
+        self.end_scope(1);
+
+        self.patch_jump(end_op);
+
+        Ok(())
+    }
+
     fn compile_var_decl(&mut self, stmt: VarStmt) -> CompilerResult<()> {
         let global = self.declare_variable(&stmt.name);
 
@@ -290,9 +398,7 @@ impl<'gc> Compiler<'gc> {
             self.define_global(index);
         }
 
-        if stmt.var_type == VarDeclType::Upvalue {
-            self.write_op(OpCode::PushUpvalue { index: StackOffset(0) });
-        }
+        self.close_local(stmt.var_type, StackOffset(0));
 
         Ok(())
     }
@@ -327,22 +433,27 @@ impl<'gc> Compiler<'gc> {
     // Expressions
 
     fn compile_expr(&mut self, expr: Expr) {
+        self.spans.push(expr.span());
+
         match expr {
-            Expr::Assign   (expr) => self.compile_assign_expr (expr),
-            Expr::Binary   (expr) => self.compile_binary_expr (expr),
-            Expr::Call     (expr) => self.compile_call_expr   (expr),
-            Expr::Get      (expr) => self.compile_get_expr    (expr),
-            Expr::Grouping (expr) => self.compile_expr        (*expr.expr),
-            Expr::Literal  (expr) => self.compile_literal_expr(expr),
-            Expr::Logical  (expr) => self.compile_logical_expr(expr),
-            Expr::Set      (expr) => self.compile_set_expr    (expr),
-            Expr::Super    (expr) => self.compile_super_expr  (expr),
-            Expr::This     (expr) => self.compile_this_expr   (expr),
-            Expr::Unary    (expr) => self.compile_unary_expr  (expr),
-            Expr::Variable (expr) => self.compile_var_expr    (expr),
+            Expr::Assign        (expr) => self.compile_assign_expr        (expr),
+            Expr::Binary        (expr) => self.compile_binary_expr        (expr),
+            Expr::Call          (expr) => self.compile_call_expr          (expr),
+            Expr::Get           (expr) => self.compile_get_expr           (expr),
+            Expr::Grouping      (expr) => self.compile_expr               (*expr.expr),
+            Expr::Interpolation (expr) => self.compile_interpolation_expr (expr),
+            Expr::Lambda        (expr) => self.compile_lambda_expr        (expr),
+            Expr::Literal       (expr) => self.compile_literal_expr       (expr),
+            Expr::Logical       (expr) => self.compile_logical_expr       (expr),
+            Expr::Set           (expr) => self.compile_set_expr           (expr),
+            Expr::Super         (expr) => self.compile_super_expr         (expr),
+            Expr::Ternary       (expr) => self.compile_ternary_expr       (expr),
+            Expr::This          (expr) => self.compile_this_expr          (expr),
+            Expr::Unary         (expr) => self.compile_unary_expr         (expr),
+            Expr::Variable      (expr) => self.compile_var_expr           (expr),
         };
 
-        // sdlfkjsdflkjsdf
+        self.spans.pop();
     }
 
     fn compile_literal_expr(&mut self, literal: Literal) {
@@ -364,11 +475,30 @@ impl<'gc> Compiler<'gc> {
         };
     }
 
+    fn compile_interpolation_expr(&mut self, interpolation: Interpolation) {
+        let count = interpolation.parts.len();
+
+        for part in interpolation.parts {
+            self.compile_expr(part);
+        }
+
+        self.write_op(Op::Concat { count });
+    }
+
+    fn compile_lambda_expr(&mut self, lambda: Lambda) {
+        // compiling never fails, mistakes are caught by the parser and resolver
+        let func = self.make_function(lambda.func, FuncType::Function)
+            .expect("expect a function body to compile");
+
+        self.write_op(Op::Closure { func });
+    }
+
     fn compile_logical_expr(&mut self, logical: Logical) {
         self.compile_expr(*logical.left);
         match logical.type_ {
-            LogicalType::And => self.compile_logical_jump(*logical.right, JumpType::IfFalse),
-            LogicalType::Or  => self.compile_logical_jump(*logical.right, JumpType::IfTrue),
+            LogicalType::And      => self.compile_logical_jump(*logical.right, JumpType::IfFalse),
+            LogicalType::Or       => self.compile_logical_jump(*logical.right, JumpType::IfTrue),
+            LogicalType::Coalesce => self.compile_logical_jump(*logical.right, JumpType::IfNotNil),
         }
     }
 
@@ -381,6 +511,25 @@ impl<'gc> Compiler<'gc> {
         self.patch_jump(jump_op);
     }
 
+    fn compile_ternary_expr(&mut self, ternary: Ternary) {
+        self.compile_expr(*ternary.condition);
+
+        let jump_then_op = self.emit_jump(JumpType::IfFalse); // goto: Else
+
+        self.write_pop();
+        self.compile_expr(*ternary.then_branch);
+        let jump_else_op = self.emit_jump(JumpType::Always);  // goto: End
+
+        // Else:
+        self.patch_jump(jump_then_op);
+
+        self.write_pop();
+        self.compile_expr(*ternary.else_branch);
+
+        // End:
+        self.patch_jump(jump_else_op);
+    }
+
     fn compile_assign_expr(&mut self, assign: Assign) {
 
         self.compile_expr(*assign.value);
@@ -404,8 +553,12 @@ impl<'gc> Compiler<'gc> {
         self.compile_expr(*binary.left);
         self.compile_expr(*binary.right);
 
+        self.write_binary_op(binary.type_);
+    }
+
+    fn write_binary_op(&mut self, type_: BinaryOpType) {
         type B = BinaryOpType;
-        match binary.type_ {
+        match type_ {
             B::NotEqual     => self.write_ops(Op::Equal,   Op::Not),
             B::Equal        => self.write_op (Op::Equal           ),
             B::Greater      => self.write_op (Op::Greater         ),
@@ -417,19 +570,36 @@ impl<'gc> Compiler<'gc> {
             B::Subtract     => self.write_op (Op::Subtract),
             B::Multiply     => self.write_op (Op::Multiply),
             B::Divide       => self.write_op (Op::Divide),
+            B::IntDivide    => self.write_op (Op::IntDivide),
+            B::Modulo       => self.write_op (Op::Modulo),
+            B::Power        => self.write_op (Op::Power),
+
+            B::BitAnd       => self.write_op (Op::BitAnd),
+            B::BitOr        => self.write_op (Op::BitOr),
+            B::BitXor       => self.write_op (Op::BitXor),
+            B::ShiftLeft    => self.write_op (Op::ShiftLeft),
+            B::ShiftRight   => self.write_op (Op::ShiftRight),
         };
     }
 
     fn compile_call_expr(&mut self, call: Call) {
 
         let arity = call.args.len();
+        let named = call.named.len();
 
         self.compile_expr(*call.callee);
         for arg in call.args.into_iter() {
             self.compile_expr(arg);
         }
 
-        self.write_op(Op::Call { arg_count: arity });
+        // each named argument is its name followed by its value
+        for arg in call.named.into_iter() {
+            let name = self.new_str_val(arg.name.lexeme);
+            self.emit_constant(name);
+            self.compile_expr(arg.value);
+        }
+
+        self.write_op(Op::Call { arg_count: arity, named });
 
     }
 
@@ -462,6 +632,7 @@ impl<'gc> Compiler<'gc> {
     }
 
     fn compile_get_expr(&mut self, get: Get) {
+        self.compile_expr(*get.expr);
         self.line = get.name.line;
 
         let name_idx = self.make_identifier_constant(get.name);
@@ -470,11 +641,23 @@ impl<'gc> Compiler<'gc> {
     }
 
     fn compile_set_expr(&mut self, set: Set) {
+        self.compile_expr(*set.target);
         self.line = set.name.line;
 
         let name_idx = self.make_identifier_constant(set.name);
+
+        // the target stays on the stack for `SetProperty`, under its current value
+        if set.compound.is_some() {
+            self.write_op(Op::Dup);
+            self.write_op(Op::GetProperty { name_idx });
+        }
+
         self.compile_expr(*set.value);
 
+        if let Some(type_) = set.compound {
+            self.write_binary_op(type_);
+        }
+
         self.write_op(Op::SetProperty { name_idx, });
     }
 
@@ -499,9 +682,12 @@ impl<'gc> Compiler<'gc> {
         let max = Offset(usize::MAX);
 
         let op = match jump {
-            JumpType::IfFalse => Op::JumpIfFalse { offset: max },
-            JumpType::IfTrue  => Op::JumpIfTrue  { offset: max },
-            JumpType::Always  => Op::Jump        { offset: max },
+            JumpType::IfFalse  => Op::JumpIfFalse  { offset: max },
+            JumpType::IfTrue   => Op::JumpIfTrue   { offset: max },
+            JumpType::IfNotNil => Op::JumpIfNotNil { offset: max },
+            JumpType::IfPassed(param) => Op::JumpIfPassed { param, offset: max },
+            JumpType::Always   => Op::Jump         { offset: max },
+            JumpType::Try      => Op::Try          { offset: max },
         };
 
         self.write_op(op)
@@ -518,9 +704,12 @@ impl<'gc> Compiler<'gc> {
         let op = &mut chunk.code[index];
 
         match op {
-            Op::JumpIfTrue  { offset } => *offset = new_offset,
-            Op::JumpIfFalse { offset } => *offset = new_offset,
-            Op::Jump        { offset } => *offset = new_offset,
+            Op::JumpIfTrue   { offset } => *offset = new_offset,
+            Op::JumpIfFalse  { offset } => *offset = new_offset,
+            Op::JumpIfNotNil { offset } => *offset = new_offset,
+            Op::JumpIfPassed { offset, .. } => *offset = new_offset,
+            Op::Jump         { offset } => *offset = new_offset,
+            Op::Try          { offset } => *offset = new_offset,
 
             _ => panic!("The indexed op code is not a jump: index: {}, opcode: '{}'", index, op),
         };
@@ -588,13 +777,19 @@ impl<'gc> Compiler<'gc> {
 
     fn write_op(&mut self, op: OpCode<'gc>) -> BytecodeIndex {
         let line = self.line;
-        self.current_chunk_mut().write_op(op, line)
+        let span = self.current_span();
+        self.current_chunk_mut().write_op(op, line, span)
     }
     fn write_ops(&mut self, op1: OpCode<'gc>, op2: OpCode<'gc>) -> BytecodeIndex {
         let line = self.line;
+        let span = self.current_span();
         let mut chunk = self.current_chunk_mut();
-        chunk.write_op(op1, line);
-        chunk.write_op(op2, line)
+        chunk.write_op(op1, line, span);
+        chunk.write_op(op2, line, span)
+    }
+
+    fn current_span(&self) -> Span {
+        self.spans.last().copied().unwrap_or_default()
     }
 
     fn write_pop(&mut self) -> BytecodeIndex {
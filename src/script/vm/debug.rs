@@ -48,21 +48,28 @@ impl<'gc> OpCode<'gc> {
 
             O::GetUpvalue   { index }     => byte_instruction    ("OP_GET_UPVALUE",   **index),
             O::SetUpvalue   { index }     => byte_instruction    ("OP_SET_UPVALUE",   **index),
-            O::PushUpvalue  { index }     => byte_instruction    ("OP_PUSH_UPVALUE",  **index),
-
-            O::JumpIfFalse  { offset }    => jump_instruction    ("OP_JUMP_IF_FALSE", ip, offset,  1),
-            O::JumpIfTrue   { offset }    => jump_instruction    ("OP_JUMP_IF_TRUE",  ip, offset,  1),
-            O::Jump         { offset }    => jump_instruction    ("OP_JUMP",          ip, offset,  1),
-            O::Loop         { offset }    => jump_instruction    ("OP_LOOP",          ip, offset, -1),
-
-            O::Call         { arg_count } => byte_instruction    ("OP_CALL",          *arg_count),
+            O::CloseLocal   { offset }    => byte_instruction    ("OP_CLOSE_LOCAL",   **offset),
+
+            O::JumpIfFalse  { offset }    => jump_instruction    ("OP_JUMP_IF_FALSE",   ip, offset,  1),
+            O::JumpIfTrue   { offset }    => jump_instruction    ("OP_JUMP_IF_TRUE",    ip, offset,  1),
+            O::JumpIfNotNil { offset }    => jump_instruction    ("OP_JUMP_IF_NOT_NIL", ip, offset,  1),
+            O::JumpIfPassed { offset, .. } => jump_instruction   ("OP_JUMP_IF_PASSED",  ip, offset,  1),
+            O::Jump         { offset }    => jump_instruction    ("OP_JUMP",            ip, offset,  1),
+            O::Loop         { offset }    => jump_instruction    ("OP_LOOP",            ip, offset, -1),
+            O::Try          { offset }    => jump_instruction    ("OP_TRY",             ip, offset,  1),
+
+            O::Call         { arg_count, .. } => byte_instruction("OP_CALL",          *arg_count),
+            O::Concat       { count }     => byte_instruction    ("OP_CONCAT",        *count),
             O::Class        { name_idx }  => constant_instruction("OP_CLASS",         data, name_idx),
             O::Closure      { func }      => closure_instruction ("OP_CLOSURE",       func),
+            O::Import       { module }    => byte_instruction    ("OP_IMPORT",        **module),
 
             O::Nil                        => simple_instruction  ("OP_NIL"),
             O::True                       => simple_instruction  ("OP_TRUE"),
             O::False                      => simple_instruction  ("OP_FALSE"),
             O::Pop                        => simple_instruction  ("OP_POP"),
+            O::Dup                        => simple_instruction  ("OP_DUP"),
+            O::Inherit                    => simple_instruction  ("OP_INHERIT"),
 
             O::Equal                      => simple_instruction  ("OP_EQUAL"),
             O::Greater                    => simple_instruction  ("OP_GREATER"),
@@ -72,11 +79,21 @@ impl<'gc> OpCode<'gc> {
             O::Subtract                   => simple_instruction  ("OP_SUBTRACT"),
             O::Multiply                   => simple_instruction  ("OP_MULTIPLY"),
             O::Divide                     => simple_instruction  ("OP_DIVIDE"),
+            O::IntDivide                  => simple_instruction  ("OP_INT_DIVIDE"),
+            O::Modulo                     => simple_instruction  ("OP_MODULO"),
+            O::Power                      => simple_instruction  ("OP_POWER"),
+            O::BitAnd                     => simple_instruction  ("OP_BIT_AND"),
+            O::BitOr                      => simple_instruction  ("OP_BIT_OR"),
+            O::BitXor                     => simple_instruction  ("OP_BIT_XOR"),
+            O::ShiftLeft                  => simple_instruction  ("OP_SHIFT_LEFT"),
+            O::ShiftRight                 => simple_instruction  ("OP_SHIFT_RIGHT"),
             O::Not                        => simple_instruction  ("OP_NOT"),
 
             O::Print                      => simple_instruction  ("OP_PRINT"),
             O::Negate                     => simple_instruction  ("OP_NEGATE"),
             O::Return                     => simple_instruction  ("OP_RETURN"),
+            O::EndTry                     => simple_instruction  ("OP_END_TRY"),
+            O::Throw                      => simple_instruction  ("OP_THROW"),
         }
     }
 }
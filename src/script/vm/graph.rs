@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+
+use gc_arena::{lock::GcRefLock, Gc, Mutation};
+
+use crate::script::vm::{
+    native::NativeCtx,
+    object::{Arity, Fields, NativeFn, ObjClass, ObjInstance, ObjPtr},
+    stdlib::type_of,
+    value::Value,
+    Root, RuntimeResult,
+};
+
+
+/// The name of the builtin global that holds graph wide attributes (`graph.theme = "dark";`)
+pub static GRAPH_GLOBAL: &str = "graph";
+static GRAPH_CLASS:      &str = "Graph";
+
+pub type Attrs = BTreeMap<String, Attr>;
+
+/// A copy of the graph state built by a script, detached from the gc arena
+/// so that it can outlive the vm
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GraphSnapshot {
+    pub attrs: Attrs,
+    pub nodes: Vec<NodeSnapshot>,
+}
+
+/// An instance the script added to the graph with `graph.add(node)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeSnapshot {
+    /// The class of the node, followed by its superclasses (nearest first)
+    pub classes: Vec<ClassSnapshot>,
+    pub attrs:   Attrs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassSnapshot {
+    pub name:  String,
+    pub attrs: Attrs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attr {
+    Nil,
+    Bool  (bool),
+    Number(f64),
+    String(String),
+    Object(String),
+}
+
+
+impl<'gc> Root<'gc> {
+
+    pub fn graph(&self) -> GraphSnapshot {
+
+        let attrs = self.builtins
+            .get(GRAPH_GLOBAL)
+            .and_then(|graph| graph.to_obj())
+            .and_then(|graph| graph.to_instance())
+            .map     (|graph| to_attrs(&graph.borrow().fields))
+            .unwrap_or_default()
+        ;
+
+        let nodes = self.nodes.iter()
+            .map(|node| {
+                let node = node.borrow();
+
+                let classes = ObjClass::chain(node.class).iter()
+                    .map(|class| {
+                        let class = class.borrow();
+
+                        ClassSnapshot {
+                            name:  class.name.to_owned(),
+                            attrs: to_attrs(&class.fields),
+                        }
+                    })
+                    .collect()
+                ;
+
+                NodeSnapshot {
+                    classes,
+                    attrs: to_attrs(&node.fields),
+                }
+            })
+            .collect()
+        ;
+
+        GraphSnapshot {
+            attrs,
+            nodes,
+        }
+    }
+}
+
+impl<'gc> Root<'gc> {
+    /// Adds `node` to the graph, unless it already is in it
+    pub fn add_node(&mut self, node: GcRefLock<'gc, ObjInstance<'gc>>) {
+        if !self.nodes.iter().any(|added| Gc::ptr_eq(*added, node)) {
+            self.nodes.push(node);
+        }
+    }
+}
+
+pub fn def_graph<'gc>(globals: &mut HashMap<String, Value<'gc>>, ctx: &Mutation<'gc>) {
+    let class = ObjPtr::new_class(GRAPH_CLASS.to_owned(), ctx);
+    let class = class.to_class().expect("expect class object");
+
+    // a class field, so that it isn't one of the graph's attributes
+    let add = ObjPtr::new_native_fn("add".to_owned(), NativeFn::new(Arity::Fixed(1), add_native), ctx);
+    class.borrow_mut(ctx).set_field("add".to_owned(), Value::new_obj(add));
+
+    let graph = ObjPtr::new_instance(class, ctx);
+
+    globals.insert(GRAPH_GLOBAL.to_owned(), Value::new_obj(graph));
+}
+
+// `graph.add(node)` returns the node, for `var users = graph.add(Table());`
+fn add_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> RuntimeResult<Value<'gc>> {
+    let node = args[0].as_obj()
+        .and_then(|obj| obj.to_instance())
+        .ok_or_else(|| native.error(format!("Only instances can be added to the graph, found '{}'", type_of(&args[0]))))?;
+
+    native.add_node(node);
+
+    Ok(args[0])
+}
+
+impl NodeSnapshot {
+    pub fn class(&self) -> &str {
+        &self.classes[0].name
+    }
+}
+
+impl Attr {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Attr::String(s) => Some(s),
+            _               => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Attr::Number(n) => Some(*n),
+            _               => None,
+        }
+    }
+
+    pub fn display_type(&self) -> &str {
+        match self {
+            Attr::Nil       => "Nil",
+            Attr::Bool  (_) => "Boolean",
+            Attr::Number(_) => "Number",
+            Attr::String(_) => "String",
+            Attr::Object(_) => "Object",
+        }
+    }
+}
+
+fn to_attrs(fields: &Fields) -> Attrs {
+    fields.iter()
+        .map(|(name, value)| (name.to_owned(), to_attr(value)))
+        .collect()
+}
+
+fn to_attr(value: &Value) -> Attr {
+    match value {
+        Value::Nil       => Attr::Nil,
+        Value::Bool  (b) => Attr::Bool  (*b),
+        Value::Number(n) => Attr::Number(*n),
+        Value::Obj   (_) => match value.as_str() {
+            Some(s) => Attr::String(s.string.to_owned()),
+            None    => Attr::Object(value.to_string()),
+        },
+        Value::Closed(_) => Attr::Object(value.to_string()),
+    }
+}
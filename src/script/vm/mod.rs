@@ -1,15 +1,17 @@
 use std::cell::{RefMut};
-use std::time::{SystemTime, UNIX_EPOCH};
 use std::{collections::HashMap};
-use std::fmt::Write;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
 
 use chunk::{Chunk, OpCode};
 use gc_arena::lock::{GcRefLock, RefLock};
 use gc_arena::{Arena, Collect, Gc, Mutation, Rootable};
-use value::Value;
+use value::{HostValue, Value};
 
-use crate::script::vm::chunk::{StackOffset, UpvalueIndex};
+use crate::script::diagnostic::Span;
+use crate::script::vm::chunk::{Capture, ModuleIndex, StackOffset, UpvalueIndex};
 use crate::script::vm::debug::DisassembleData;
+use crate::script::vm::native::NativeCtx;
 use crate::script::vm::object::*;
 use crate::script::vm::{
         chunk::{
@@ -26,19 +28,30 @@ pub mod debug;
 pub mod value;
 pub mod compiler;
 pub mod object;
+pub mod graph;
+pub mod native;
+pub mod stdlib;
 
 
 static DEBUG_TRACE_EXECUTION: bool = true;
 // static DEBUG_TRACE_EXECUTION: bool = false;
 
-static STACK_FRAMES_MAX:       usize = 10000; // ¯\_(ツ)_/¯
 static INITIAL_STACK_CAPACITY: usize = 10000; // ¯\_(ツ)_/¯
 
-pub fn interpret(root: ArenaRoot) -> RuntimeResult<()> {
+// reading the clock takes longer than most instructions, so the time limit is checked every so many
+static CLOCK_INTERVAL:         u64   = 1024;
+
+// each call from a native back into a script recurses on the Rust stack, so there can only be so many at once,
+// whatever the call depth limit. A debug build fits a few times as many on a 2 MiB thread stack
+static MAX_NESTED_CALLS:       usize = 64;
+
+pub fn interpret(root: ArenaRoot) -> RuntimeResult<ArenaRoot> {
 
     let mut vm  = Vm::new(root);
 
-    vm.run()
+    vm.run()?;
+
+    Ok(vm.root)
 }
 
 // TODO: String interning
@@ -56,16 +69,42 @@ pub struct Root<'gc> {
     stack:       Vec    <Value<'gc>>,
     constants:   Vec    <Value<'gc>>,
 
-    globals:     HashMap<String, Value<'gc>>,
+    /// The graph and native functions, visible from every module
+    builtins:    HashMap<String, Value<'gc>>,
+    modules:     Vec    <Module<'gc>>,
+    nodes:       Vec    <GcRefLock<'gc, ObjInstance<'gc>>>,
 
     ip:          BytecodeIndex,
 
+    /// The result of the last call to finish with an empty call stack
+    returned:    Value<'gc>,
+
+    /// The value of a `throw` on its way to a handler, other errors are caught as their message
+    thrown:      Option<Value<'gc>>,
+
+    /// Bounds on each run, and how much of them the current run has used, across every script it runs
+    limits:      Limits,
+    budget:      Budget,
+
+    /// How many calls from natives back into scripts are running, one inside the other
+    nested:      usize,
+
     capture_out: bool,
     out:         Vec<String>,
+
+    trace:       bool,
 }
 
 pub type ArenaRoot = Arena::<Rootable![Root<'_>]>;
 
+/// A script file, and the globals it defines
+#[derive(Collect)]
+#[collect(no_drop)]
+pub struct Module<'gc> {
+    pub name:    String,
+    pub globals: HashMap<String, Value<'gc>>,
+}
+
 #[derive(Collect)]
 #[collect(no_drop)]
 pub struct CallFrame<'gc> {
@@ -73,36 +112,160 @@ pub struct CallFrame<'gc> {
     pub ret_ip:       BytecodeIndex,
     pub closure:      GcRefLock<'gc, ObjClosure<'gc>>,
     pub arity:        usize,
+
+    /// The parameters the caller left out, which take their default
+    pub omitted:      Vec<bool>,
+
+    /// The try blocks in progress, innermost last
+    pub handlers:     Vec<Handler>,
+}
+
+/// Where a try block's handler starts, and the stack height to unwind to before it runs
+#[derive(Debug, Clone, Copy, Collect)]
+#[collect(require_static)]
+pub struct Handler {
+    pub catch_ip:  BytecodeIndex,
+    pub stack_len: StackIndex,
 }
 
 #[derive(Debug)]
 pub struct RuntimeError {
-    pub stack_trace: String,
+    pub stack_trace: Vec<TraceFrame>,
     pub msg:         String,
     pub line:        usize,
+    pub span:        Span,
+
+    /// The module whose source `span` points into
+    pub module:      ModuleIndex,
+}
+
+/// A call in progress when a runtime error occurred, innermost first
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceFrame {
+    pub function: String,
+    pub line:     usize,
+    pub module:   ModuleIndex,
+    pub file:     String,
+
+    /// The expression being evaluated in `function`
+    pub span:     Span,
 }
 
 pub type RuntimeResult<T> = Result<T, RuntimeError>;
 
+/// Bounds on a run of a script and the imports it runs, so that a runaway one ends with an error rather than hanging
+/// or running out of memory. `None` is unlimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Collect)]
+#[collect(require_static)]
+pub struct Limits {
+    pub instructions: Option<u64>,
+    pub time:         Option<Duration>,
+    pub call_depth:   usize,
+
+    /// In bytes, of the objects still reachable after a full collection, counting the text of strings,
+    /// the items of lists and the fields of instances and classes
+    pub heap:         Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            instructions: None,
+            time:         Some(Duration::from_secs(10)),
+            call_depth:   10_000,
+            heap:         Some(1 << 30),
+        }
+    }
+}
+
+impl Limits {
+    /// Only the call depth is limited, as the call stack has to end somewhere
+    pub fn none() -> Self {
+        Self {
+            instructions: None,
+            time:         None,
+            heap:         None,
+            ..Self::default()
+        }
+    }
+}
+
+/// What the current run has used of its limits, started by `Vm::start_budget`
+#[derive(Debug, Clone, Copy, Collect)]
+#[collect(require_static)]
+struct Budget {
+    instructions: u64,
+    started:      Instant,
+
+    /// Once a limit is exceeded nothing catches the error, which unwinds the whole script
+    exceeded:     bool,
+}
+
+impl Budget {
+    fn new() -> Self {
+        Self {
+            instructions: 0,
+            started:      Instant::now(),
+            exceeded:     false,
+        }
+    }
+}
+
 enum BinaryOp {
     Greater,
     Less,
     Sub,
     Mul,
-    Div
+    Div,
+    IntDiv,
+    Mod,
+    Pow,
+}
+
+// the bitwise operators, which work on whole numbers as 64 bit two's complement integers
+enum BitwiseOp {
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
 }
 
 enum JumpType {
     IfFalsey,
     IfTruthy,
+    IfNotNil,
 }
 
 
 impl Vm {
-    pub fn new(mut root: ArenaRoot) -> Self {
+    pub fn new(root: ArenaRoot) -> Self {
+        let mut vm = Self::init(root);
+
+        vm.load_script(0);
+        vm
+    }
+
+    /// A vm with the builtin globals defined, but no script loaded
+    pub fn init(mut root: ArenaRoot) -> Self {
 
         root.mutate_root(|ctx, root| {
-            let script_func = root.functions.first().expect("expect top level script function");
+            graph::def_graph(&mut root.builtins, ctx);
+            native::def_natives(&mut root.builtins, ctx);
+            stdlib::def_stdlib (&mut root.builtins, ctx);
+        });
+
+        Self {
+            root,
+        }
+    }
+
+    /// Sets up a call to the top level script function at `index` in `Root::functions`,
+    /// discarding whatever was left on the stack by the previous script
+    pub fn load_script(&mut self, index: usize) {
+
+        self.root.mutate_root(|ctx, root| {
+            let script_func = root.functions.get(index).expect("expect top level script function");
 
             let script_closure = ObjectMut::new_closure(*script_func, 0, vec![], ctx);
             let ObjectMut::Closure(cls) = script_closure else {
@@ -114,13 +277,13 @@ impl Vm {
                 ret_ip:       BytecodeIndex(0),
                 closure:      cls,
                 arity:        0,
+                omitted:      vec![],
+                handlers:     vec![],
             };
             let call_frame = Gc::new(ctx, RefLock::new(call_frame));
 
-            root.call_stack.push(call_frame);
-
-
-            def_natives(&mut root.globals, ctx);
+            root.call_stack = vec![call_frame];
+            root.ip         = BytecodeIndex(0);
 
 
             let mut stack = Vec::with_capacity(INITIAL_STACK_CAPACITY);
@@ -128,20 +291,42 @@ impl Vm {
 
             root.stack = stack;
         });
+    }
 
+    pub fn root(&mut self) -> &mut ArenaRoot {
+        &mut self.root
+    }
 
-        Self {
-            root,
+    /// Calls the global function `name` of the main module (or a builtin) with `args`, running it to completion
+    pub fn call(&mut self, name: &str, args: &[HostValue]) -> RuntimeResult<HostValue> {
+
+        self.start_budget();
+
+        let running = self.root.mutate_root(|ctx, root| {
+            root.call_from_host(name, args, ctx)
+        })?;
+
+        if running {
+            self.run()?;
         }
+
+        Ok(self.root.mutate(|_ctx, root| root.returned.to_host()))
+    }
+
+    /// Starts a run with the full budget of its limits, which every script it runs after shares
+    pub fn start_budget(&mut self) {
+        self.root.mutate_root(|_ctx, root| root.budget = Budget::new());
     }
 
-    fn run<'gc>(&mut self) -> RuntimeResult<()> {
+    /// Runs the loaded script to the end, within what's left of the budget
+    pub fn run(&mut self) -> RuntimeResult<()> {
 
         loop {
             self.root.collect_debt();
+            self.check_heap()?;
 
             let done = self.root.mutate_root(|ctx, root| {
-                root.run_instruction(ctx)
+                root.step(0, ctx)
             });
 
             if done? {
@@ -149,6 +334,25 @@ impl Vm {
             }
         }
     }
+
+    // garbage doesn't count, so the heap is only too big if it still is after a full collection
+    fn check_heap(&mut self) -> RuntimeResult<()> {
+        let Some(max) = self.root.mutate(|_ctx, root| root.limits.heap) else {
+            return Ok(());
+        };
+
+        if self.root.metrics().total_allocation() <= max {
+            return Ok(());
+        }
+
+        self.root.collect_all();
+
+        if self.root.metrics().total_allocation() <= max {
+            return Ok(());
+        }
+
+        Err(self.root.mutate_root(|_ctx, root| root.heap_error(max)))
+    }
 }
 
 impl<'gc> Root<'gc> {
@@ -161,12 +365,23 @@ impl<'gc> Root<'gc> {
             functions:   vec![],
             constants:   vec![],
 
-            globals:     HashMap::new(),
+            builtins:    HashMap::new(),
+            modules:     vec![Module::new("main".to_owned())],
+            nodes:       vec![],
 
             ip:          BytecodeIndex(0),
 
+            returned:    Value::Nil,
+            thrown:      None,
+
+            limits:      Limits::default(),
+            budget:      Budget::new(),
+            nested:      0,
+
             capture_out: false,
             out:         vec![],
+
+            trace:       DEBUG_TRACE_EXECUTION,
         }
     }
 
@@ -178,9 +393,106 @@ impl<'gc> Root<'gc> {
         x
     }
 
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Whether `print` also keeps what it prints, for `take_out`
+    pub fn set_capture_out(&mut self, capture_out: bool) {
+        self.capture_out = capture_out;
+    }
+
+    pub fn take_out(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.out)
+    }
+
+    /// Turns the per instruction debug trace on or off
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.trace
+    }
+
+    pub fn function_count(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// The names of the globals visible from the main module so far, sorted
+    pub fn global_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.builtins.keys()
+            .chain(self.modules[0].globals.keys())
+            .cloned()
+            .collect();
+
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// Defines a global visible from every module, such as a native function
+    pub fn define_builtin(&mut self, name: &str, value: Value<'gc>) {
+        self.builtins.insert(name.to_owned(), value);
+    }
+
+    /// The value of a global of the main module, or of a builtin
+    pub fn get_global(&self, name: &str) -> Option<Value<'gc>> {
+        self.modules[0].globals
+            .get(name)
+            .or_else(|| self.builtins.get(name))
+            .copied()
+    }
+
+    /// Converts a value to a string, as `print`, interpolation, `to_string` and `format` do.
+    ///
+    /// Strings are their contents, `nil`, `true` and `false` are spelled out and numbers have no fractional
+    /// part if they're whole (`3`, `0.25`, `NaN`, `inf`). Functions, classes and modules are `<fn f>`,
+    /// `<class Box>` and `<module lib.lox>`. An instance is `<Box instance>`, unless it has a `to_string`
+    /// field (its own, or a default set on its class) holding a function, which is called with the instance
+    pub fn stringify(&mut self, value: Value<'gc>, ctx: &'gc Mutation<'gc>) -> RuntimeResult<String> {
+        let Some(to_string) = self.to_string_field(value) else {
+            return Ok(value.to_string());
+        };
+
+        let result = self.call_nested(to_string, &[value], ctx)?;
+
+        result.as_str()
+            .map(|str| str.string.to_owned())
+            .ok_or_else(|| self.runtime_error(format!("'to_string' must return a String, found '{}'", stdlib::type_of(&result))))
+    }
+
+    fn to_string_field(&self, value: Value<'gc>) -> Option<Value<'gc>> {
+        let instance = value.as_obj()?.to_instance()?;
+        let instance = instance.borrow();
+
+        let field = instance.fields.get("to_string").copied()
+            .or_else(|| instance.class.borrow().get_field("to_string"))?;
+
+        (stdlib::type_of(&field) == "Function").then_some(field)
+    }
+
+    /// Adds an empty globals namespace for a script, the main script is always module 0
+    pub fn add_module(&mut self, name: String) -> ModuleIndex {
+        self.modules.push(Module::new(name));
+
+        ModuleIndex(self.modules.len() -1)
+    }
+
+    /// Names the main module after the script, for stack traces
+    pub fn set_main_name(&mut self, name: String) {
+        self.modules[0].name = name;
+    }
+
     pub fn dbg_funcs(&self) {
+        self.dbg_funcs_from(0);
+    }
 
-        for func in &self.functions {
+    /// Disassembles the functions compiled since `functions[start]`
+    pub fn dbg_funcs_from(&self, start: usize) {
+
+        for func in &self.functions[start..] {
 
             let chunk = func.chunk.borrow();
 
@@ -194,9 +506,48 @@ impl<'gc> Root<'gc> {
     }
 
 
-    fn run_instruction(&'gc mut self, ctx: &'gc Mutation<'gc>) -> RuntimeResult<bool> {
+    /// Runs an instruction, an error in it unwinds to the innermost handler in the call frames from `floor` up
+    fn step(&mut self, floor: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<bool> {
+        self.run_instruction(ctx).or_else(|err| {
+            self.catch(err, floor, ctx)?;
+            Ok(false)
+        })
+    }
+
+    fn catch(&mut self, err: RuntimeError, floor: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+        let frames = match self.budget.exceeded {
+            true  => &[],
+            false => self.call_stack.get(floor..).unwrap_or_default(),
+        };
+
+        let Some(depth) = frames.iter().rposition(|frame| !frame.borrow().handlers.is_empty()) else {
+            // a call further down may still catch a thrown value, unless this is the bottom of the stack
+            if floor == 0 {
+                self.thrown = None;
+            }
+
+            return Err(err);
+        };
+
+        self.call_stack.truncate(floor + depth + 1);
+
+        let handler = self.call_frame_mut(ctx).handlers.pop().expect("expect the frame to have a handler");
+
+        self.stack.truncate(*handler.stack_len);
+        self.ip = handler.catch_ip;
+
+        let value = self.thrown.take().unwrap_or_else(|| {
+            Value::new_obj(ObjPtr::new_string(err.msg, ctx))
+        });
+
+        self.push_stack(value);
+
+        Ok(())
+    }
+
+    fn run_instruction(&mut self, ctx: &'gc Mutation<'gc>) -> RuntimeResult<bool> {
 
-        if DEBUG_TRACE_EXECUTION {
+        if self.trace {
 
             let chunk = self.get_chunk();
             let chunk = chunk.borrow();
@@ -213,38 +564,48 @@ impl<'gc> Root<'gc> {
                 println!();
         }
 
-        match self.get_instruction() {
-            OpCode::GetConstant { index }            => self.op_constant    (index),
+        let op = self.get_instruction();
+        self.check_budget()?;
+
+        match op {
+            OpCode::GetConstant  { index }           => self.op_constant    (index),
 
-            OpCode::DefGlobal   { name_idx }         => self.op_def_global  (name_idx),
-            OpCode::GetGlobal   { name_idx }         => self.op_get_global  (name_idx)?,
-            OpCode::SetGlobal   { name_idx }         => self.op_set_global  (name_idx)?,
+            OpCode::DefGlobal    { name_idx }        => self.op_def_global  (name_idx),
+            OpCode::GetGlobal    { name_idx }        => self.op_get_global  (name_idx)?,
+            OpCode::SetGlobal    { name_idx }        => self.op_set_global  (name_idx)?,
 
-            OpCode::GetProperty { name_idx }         => self.op_get_property(name_idx, ctx)?,
-            OpCode::SetProperty { name_idx }         => self.op_set_property(name_idx, ctx),
+            OpCode::GetProperty  { name_idx }        => self.op_get_property(name_idx)?,
+            OpCode::SetProperty  { name_idx }        => self.op_set_property(name_idx, ctx)?,
 
-            OpCode::GetLocal    { offset }           => self.op_get_local   (offset),
-            OpCode::SetLocal    { offset }           => self.op_set_local   (offset),
+            OpCode::GetLocal     { offset }          => self.op_get_local   (offset),
+            OpCode::SetLocal     { offset }          => self.op_set_local   (offset, ctx),
 
-            OpCode::GetUpvalue  { index }            => self.op_get_upvalue (index),
-            OpCode::SetUpvalue  { index }            => self.op_set_upvalue (index, ctx),
-            OpCode::PushUpvalue { index }            => self.op_push_upvalue(index, ctx),
+            OpCode::GetUpvalue   { index }           => self.op_get_upvalue (index),
+            OpCode::SetUpvalue   { index }           => self.op_set_upvalue (index, ctx),
+            OpCode::CloseLocal   { offset }          => self.op_close_local (offset, ctx),
 
-            OpCode::JumpIfFalse { offset }           => self.op_jump_if     (JumpType::IfFalsey, offset),
-            OpCode::JumpIfTrue  { offset }           => self.op_jump_if     (JumpType::IfTruthy, offset),
-            OpCode::Jump        { offset }           => self.op_jump        (offset),
+            OpCode::JumpIfFalse  { offset }          => self.op_jump_if     (JumpType::IfFalsey, offset),
+            OpCode::JumpIfTrue   { offset }          => self.op_jump_if     (JumpType::IfTruthy, offset),
+            OpCode::JumpIfNotNil { offset }          => self.op_jump_if     (JumpType::IfNotNil, offset),
+            OpCode::JumpIfPassed { param, offset }   => self.op_jump_if_passed(param,  offset),
+            OpCode::Jump         { offset }          => self.op_jump        (offset),
 
-            OpCode::Loop        { offset }           => self.op_loop        (offset),
+            OpCode::Loop         { offset }          => self.op_loop        (offset),
+            OpCode::Try          { offset }          => self.op_try         (offset, ctx),
 
-            OpCode::Call        { arg_count }        => self.op_call        (arg_count, ctx)?,
-            OpCode::Class       { name_idx }         => self.op_class       (name_idx,  ctx),
-            OpCode::Closure     { func }             => self.op_closure     (func,      ctx),
+            OpCode::Call         { arg_count, named } => self.op_call       (arg_count, named, ctx)?,
+            OpCode::Concat       { count }           => self.op_concat      (count,     ctx)?,
+            OpCode::Class        { name_idx }        => self.op_class       (name_idx,  ctx),
+            OpCode::Closure      { func }            => self.op_closure     (func,      ctx),
+            OpCode::Import       { module }          => self.op_import      (module,    ctx),
 
             OpCode::Nil                              => self.push_stack     (Value::Nil),
             OpCode::True                             => self.push_stack     (Value::Bool(true)),
             OpCode::False                            => self.push_stack     (Value::Bool(false)),
 
             OpCode::Pop                              => self.op_pop         (),
+            OpCode::Dup                              => self.push_stack     (self.peek_stack(0)),
+            OpCode::Inherit                          => self.op_inherit     (ctx)?,
 
             OpCode::Equal                            => self.op_equal       (),
             OpCode::Greater                          => self.op_binary      (BinaryOp::Greater)?,
@@ -254,11 +615,22 @@ impl<'gc> Root<'gc> {
             OpCode::Subtract                         => self.op_binary      (BinaryOp::Sub)?,
             OpCode::Multiply                         => self.op_binary      (BinaryOp::Mul)?,
             OpCode::Divide                           => self.op_binary      (BinaryOp::Div)?,
+            OpCode::IntDivide                        => self.op_binary      (BinaryOp::IntDiv)?,
+            OpCode::Modulo                           => self.op_binary      (BinaryOp::Mod)?,
+            OpCode::Power                            => self.op_binary      (BinaryOp::Pow)?,
+
+            OpCode::BitAnd                           => self.op_bitwise     (BitwiseOp::And)?,
+            OpCode::BitOr                            => self.op_bitwise     (BitwiseOp::Or)?,
+            OpCode::BitXor                           => self.op_bitwise     (BitwiseOp::Xor)?,
+            OpCode::ShiftLeft                        => self.op_bitwise     (BitwiseOp::Shl)?,
+            OpCode::ShiftRight                       => self.op_bitwise     (BitwiseOp::Shr)?,
 
             OpCode::Not                              => self.op_not         (),
 
-            OpCode::Print                            => self.op_print       (),
+            OpCode::Print                            => self.op_print       (ctx)?,
             OpCode::Negate                           => self.op_negate      ()?,
+            OpCode::EndTry                           => { self.call_frame_mut(ctx).handlers.pop(); }
+            OpCode::Throw                            => self.op_throw       (ctx)?,
             OpCode::Return                           => {
                 let result = self.pop_stack();
                 let frame  = self.pop_call_stack().borrow();
@@ -266,6 +638,9 @@ impl<'gc> Root<'gc> {
                 let is_empty = self.call_stack.is_empty();
 
                 if is_empty {
+                    self.stack.truncate(*frame.stack_len);
+                    self.returned = result;
+
                     return Ok(true)
                 }
 
@@ -320,6 +695,22 @@ impl<'gc> Root<'gc> {
         )
     }
 
+    // a whole number that fits in a 64 bit integer, anything else is an error rather than being rounded or clamped
+    fn pop_integer(&mut self) -> RuntimeResult<i64> {
+        let number = self.pop_number()?;
+
+        if number.fract() != 0.0 || !number.is_finite() {
+            return Err(self.runtime_error(format!("Operand must be a whole number, found '{}'", Value::Number(number))));
+        }
+
+        // i64::MAX isn't a float, the bounds are -2^63 and 2^63
+        if number < i64::MIN as f64 || number >= -(i64::MIN as f64) {
+            return Err(self.runtime_error(format!("Operand '{}' doesn't fit in a 64 bit integer", Value::Number(number))));
+        }
+
+        Ok(number as i64)
+    }
+
     fn peek_stack(&self, index: usize) -> Value<'gc> {
         let index = self.from_stack_top(index);
         self.stack[index]
@@ -340,24 +731,28 @@ impl<'gc> Root<'gc> {
 
     // op codes
 
-    fn op_constant(&'gc mut self, index: ConstIndex) {
+    fn op_constant(&mut self, index: ConstIndex) {
         let constant = self.get_constant(index);
 
         self.push_stack(constant);
     }
 
     fn op_def_global(&mut self, name: ConstIndex) {
-        let name = self.get_constant_as_str(name);
-        let val  = self.pop_stack();
+        let name   = self.get_constant_as_str(name);
+        let val    = self.pop_stack();
+        let module = self.current_module();
 
-        self.globals.insert(name, val);
+        self.modules[*module].globals.insert(name, val);
     }
 
+    // a module's own globals shadow the builtins
     fn op_get_global(&mut self, index: ConstIndex) -> RuntimeResult<()> {
-        let name  = self.get_constant_as_str(index);
+        let name   = self.get_constant_as_str(index);
+        let module = self.current_module();
 
-        let value = self.globals
+        let value = self.modules[*module].globals
             .get(&name)
+            .or_else(|| self.builtins.get(&name))
             .ok_or_else(|| {
                 self.runtime_error(format!("Undefined variable '{name}'"))
             })
@@ -370,10 +765,16 @@ impl<'gc> Root<'gc> {
     }
 
     fn op_set_global(&mut self, index: ConstIndex) -> RuntimeResult<()> {
-        let name = self.get_constant_as_str(index);
-        let val  = self.peek_stack(0);
+        let name   = self.get_constant_as_str(index);
+        let val    = self.peek_stack(0);
+        let module = self.current_module();
+
+        let globals = match self.modules[*module].globals.contains_key(&name) {
+            true  => &mut self.modules[*module].globals,
+            false => &mut self.builtins,
+        };
 
-        self.globals
+        globals
             .get_mut ( &name )
             .and_then( |global| {
                 *global = val;
@@ -384,53 +785,69 @@ impl<'gc> Root<'gc> {
             })
     }
 
-    fn op_get_property(&mut self, index: ConstIndex, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
-        let name     = self.get_constant_as_str(index);
-        let val      = self.pop_stack();
+    fn op_get_property(&mut self, index: ConstIndex) -> RuntimeResult<()> {
+        let name = self.get_constant_as_str(index);
+        let val  = self.pop_stack();
 
-        let obj = val.to_obj().unwrap_or_else(|| {
-            panic!("Value not an object: '{val}'")
-        });
+        // instance fields shadow the fields of its class hierarchy
+        let value = match val.to_obj() {
+            Some(ObjPtr::ObjMut(ObjectMut::Instance(instance))) => {
+                let instance = instance.borrow();
 
-        let instance = obj.to_instance().unwrap_or_else(|| {
-            panic!("Object not an instance: '{}'", obj);
-        });
+                instance.fields
+                    .get(&name)
+                    .copied()
+                    .or_else(|| instance.class.borrow().get_field(&name))
+            },
+            Some(ObjPtr::ObjMut(ObjectMut::Class(class))) => class.borrow().get_field(&name),
 
-        let instance = instance.borrow_mut(ctx);
+            Some(ObjPtr::Obj(Object::List(list))) => (name == "len").then(|| Value::Number(list.items.len() as f64)),
 
-        let value = instance.fields
-            .get(&name)
-            .ok_or_else(|| {
-                self.runtime_error(format!("Undefined property '.{name}'"))
-            })
-            ?
-        ;
+            Some(ObjPtr::Obj(Object::Module(module))) => {
+                let value = self.modules[*module.index].globals.get(&name).copied();
 
-        self.push_stack(*value);
+                if value.is_none() {
+                    Err(self.runtime_error(format!("Module '{}' doesn't define '{name}'", module.name)))?
+                }
+
+                value
+            },
+
+            _ => Err(self.runtime_error(
+                format!("Only instances, classes, modules and lists have properties, found '{}'", stdlib::type_of(&val))
+            ))?,
+        };
+
+        let value = value.ok_or_else(|| {
+            self.runtime_error(format!("Undefined property '.{name}'"))
+        })?;
+
+        self.push_stack(value);
 
         Ok(())
     }
 
-    fn op_set_property(&mut self, index: ConstIndex, ctx: &Mutation<'gc>) {
+    fn op_set_property(&mut self, index: ConstIndex, ctx: &Mutation<'gc>) -> RuntimeResult<()> {
         let name = self.get_constant_as_str(index);
         let val  = self.pop_stack();
         let obj  = self.pop_stack();
 
-        let obj = obj.to_obj().unwrap_or_else(|| {
-            panic!("Value not an object: '{obj}'");
-        });
-
-        let obj = obj.to_instance().unwrap_or_else(|| {
-            panic!("Object not an instance: '{obj}'");
-        });
+        match obj.to_obj() {
+            Some(ObjPtr::ObjMut(ObjectMut::Instance(instance))) => { instance.borrow_mut(ctx).set_field(name, val); },
+            Some(ObjPtr::ObjMut(ObjectMut::Class   (class)))    => { class   .borrow_mut(ctx).set_field(name, val); },
 
-        let mut instance = obj.borrow_mut(ctx);
+            Some(ObjPtr::Obj(Object::Module(module))) => Err(self.runtime_error(
+                format!("Can't assign to '{name}' of module '{}', module globals are read only from outside", module.name)
+            ))?,
 
-        instance.fields
-            .insert(name, val)
-        ;
+            _ => Err(self.runtime_error(
+                format!("Only instances and classes have fields, found '{}'", stdlib::type_of(&obj))
+            ))?,
+        };
 
         self.push_stack(val);
+
+        Ok(())
     }
 
 
@@ -438,21 +855,24 @@ impl<'gc> Root<'gc> {
         let index = self.from_stack_top(*index);
         let val   = self.get_local(StackIndex(index));
 
-        self.push_stack(val);
+        self.push_stack(read_closed(val));
     }
 
-    fn op_set_local(&mut self, index: StackOffset) {
+    fn op_set_local(&mut self, index: StackOffset, ctx: &'gc Mutation<'gc>) {
         let value = self.peek_stack(0);
         let index = self.from_stack_top(*index);
 
-        self.stack[index] = value;
+        match self.stack[index] {
+            Value::Closed(obj) => write_closed(obj, value, ctx),
+            _                  => self.stack[index] = value,
+        }
     }
 
     fn op_get_upvalue(&mut self, index: UpvalueIndex) {
         let closure = self.call_frame().borrow().closure;
         let upvalue = closure.borrow().closed_vals[*index];
 
-        self.push_stack(upvalue);
+        self.push_stack(read_closed(upvalue));
     }
 
     fn op_set_upvalue(&self, index: UpvalueIndex, ctx: &'gc Mutation<'gc>) {
@@ -461,40 +881,41 @@ impl<'gc> Root<'gc> {
         let closure = self.call_frame().borrow().closure;
         let upvalue = closure.borrow().closed_vals[*index];
 
-        let obj   = upvalue.to_obj_mut().expect("expect mutable object");
-        let obj   = obj.to_value().unwrap_or_else(|| {
-            panic!("expect object of type Value: '{}'", obj)
-        });
-
-        let mut obj = obj.borrow_mut(ctx);
+        let Value::Closed(obj) = upvalue else {
+            panic!("expect upvalue to be closed over: '{}'", upvalue)
+        };
 
-        obj.value = value;
+        write_closed(obj, value, ctx);
     }
 
-    fn op_push_upvalue(&mut self, index: StackOffset, ctx: &'gc Mutation<'gc>) {
+    // moves a local captured by a closure into a box shared with the closure
+    fn op_close_local(&mut self, index: StackOffset, ctx: &Mutation<'gc>) {
         let index = StackIndex(self.from_stack_top(*index));
 
         let val = self.stack_swap(index, Value::Nil);
         let obj = ObjPtr::new_value(val, ctx);
-        let val = Value::Obj(obj);
-
-        let closure = self.call_frame().borrow().closure;
-        let mut closure = closure.borrow_mut(ctx);
 
-        closure.closed_vals.push(val);
-
-        self.stack_swap(index, val);
+        self.stack_swap(index, Value::Closed(obj));
     }
 
     fn op_jump_if(&mut self, jump_type: JumpType, offset: Offset) {
-        let is_falsey = self.peek_stack(0).is_falsey();
+        let value = self.peek_stack(0);
 
-        let jump_on_false = match jump_type {
-            JumpType::IfFalsey => true,
-            JumpType::IfTruthy => false,
+        let jump = match jump_type {
+            JumpType::IfFalsey =>  value.is_falsey(),
+            JumpType::IfTruthy => !value.is_falsey(),
+            JumpType::IfNotNil => !matches!(value, Value::Nil),
         };
 
-        if is_falsey == jump_on_false {
+        if jump {
+            *self.ip += *offset;
+        }
+    }
+
+    fn op_jump_if_passed(&mut self, param: usize, offset: Offset) {
+        let omitted = self.call_frame().borrow().omitted.get(param).copied().unwrap_or(false);
+
+        if !omitted {
             *self.ip += *offset;
         }
     }
@@ -507,9 +928,28 @@ impl<'gc> Root<'gc> {
         *self.ip -= *offset;
     }
 
-    fn op_call(&mut self, arg_count: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
-        let val = self.peek_stack(arg_count);
-        self.call_value(val, arg_count, ctx)
+    fn op_try(&mut self, offset: Offset, ctx: &'gc Mutation<'gc>) {
+        let handler = Handler {
+            catch_ip:  BytecodeIndex(*self.ip + *offset),
+            stack_len: StackIndex   (self.stack.len()),
+        };
+
+        self.call_frame_mut(ctx).handlers.push(handler);
+    }
+
+    // the message is only seen if nothing catches the value
+    fn op_throw(&mut self, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+        let value = self.pop_stack();
+        let text  = self.stringify(value, ctx)?;
+
+        self.thrown = Some(value);
+
+        Err(self.runtime_error(format!("Uncaught error: {}", text)))
+    }
+
+    fn op_call(&mut self, arg_count: usize, named: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+        let val = self.peek_stack(arg_count + named * 2);
+        self.call_value(val, arg_count, named, ctx)
     }
 
     fn op_class(&mut self, name_idx: ConstIndex, ctx: &Mutation<'gc>) {
@@ -519,13 +959,36 @@ impl<'gc> Root<'gc> {
         self.push_stack(Value::Obj(obj));
     }
 
+    fn op_inherit(&mut self, ctx: &Mutation<'gc>) -> RuntimeResult<()> {
+        let superclass = self.pop_stack();
+        let class      = self.peek_stack(0);
+
+        let superclass = superclass
+            .to_obj()
+            .and_then(|obj| obj.to_class())
+            .ok_or_else(|| self.runtime_error("Superclass must be a class".to_owned()))?
+        ;
+
+        let class = class
+            .to_obj()
+            .and_then(|obj| obj.to_class())
+            .expect("expect class object below superclass")
+        ;
+
+        class.borrow_mut(ctx).superclass = Some(superclass);
+
+        Ok(())
+    }
+
     fn op_closure(&mut self, func: Gc<'gc, ObjFunction<'gc>>, ctx: &Mutation<'gc>) {
         self.pop_stack();
 
-        let closed_vals = self.stack.iter()
-            .filter_map(|v| match v {
-                Value::Closed(_) => Some(v.clone()),
-                _                => None,
+        let closure = self.call_frame().borrow().closure;
+
+        let closed_vals = func.captures.iter()
+            .map(|capture| match capture {
+                Capture::Local  (offset) => self.peek_stack(**offset),
+                Capture::Upvalue(index)  => closure.borrow().closed_vals[**index],
             })
             .collect()
         ;
@@ -535,20 +998,23 @@ impl<'gc> Root<'gc> {
         self.push_stack(Value::Obj(closure));
     }
 
-    fn op_close_var(&mut self, index: StackIndex, ctx: &Mutation<'gc>) {
-        let val = self.stack_swap(index, Value::Nil);
-        let obj = ObjPtr::new_value(val, ctx);
-
-        let val = Value::Obj(obj);
+    fn op_import(&mut self, module: ModuleIndex, ctx: &Mutation<'gc>) {
+        let name = self.modules[*module].name.clone();
+        let obj  = ObjPtr::new_module(name, module, ctx);
 
-        self.stack_swap(index, val);
+        self.push_stack(Value::Obj(obj));
     }
 
-
     fn op_binary(&mut self, op: BinaryOp) -> RuntimeResult<()> {
         let b = self.pop_number()?;
         let a = self.pop_number()?;
 
+        if matches!(op, BinaryOp::Div | BinaryOp::IntDiv | BinaryOp::Mod) && b == 0.0 {
+            return Err(self.runtime_error("Division by zero".to_owned()));
+        }
+
+        // `~/` and `%` round towards negative infinity, so `a == (a ~/ b) * b + a % b`
+        // and `a % b` has the sign of `b`
         type B = BinaryOp;
         let val = match op {
             B::Greater => Value::Bool  (a > b),
@@ -557,6 +1023,9 @@ impl<'gc> Root<'gc> {
             B::Sub     => Value::Number(a - b),
             B::Mul     => Value::Number(a * b),
             B::Div     => Value::Number(a / b),
+            B::IntDiv  => Value::Number((a / b).floor()),
+            B::Mod     => Value::Number(a - b * (a / b).floor()),
+            B::Pow     => Value::Number(a.powf(b)),
         };
 
         self.push_stack(val);
@@ -564,6 +1033,31 @@ impl<'gc> Root<'gc> {
         Ok(())
     }
 
+    // Results wrap around on overflow, and shifts past the 64th bit give 0 (or -1 for `>>` of a negative number)
+    fn op_bitwise(&mut self, op: BitwiseOp) -> RuntimeResult<()> {
+        let b = self.pop_integer()?;
+        let a = self.pop_integer()?;
+
+        if b < 0 && matches!(op, BitwiseOp::Shl | BitwiseOp::Shr) {
+            return Err(self.runtime_error(format!("Can't shift by a negative number of bits, found '{}'", b)));
+        }
+
+        let shift = b.min(63) as u32;
+
+        type B = BitwiseOp;
+        let val = match op {
+            B::And => a & b,
+            B::Or  => a | b,
+            B::Xor => a ^ b,
+            B::Shl => if b > 63 { 0 }                          else { a.wrapping_shl(shift) },
+            B::Shr => if b > 63 { if a < 0 { -1 } else { 0 } } else { a >> shift },
+        };
+
+        self.push_stack(Value::Number(val as f64));
+
+        Ok(())
+    }
+
     fn op_add(&mut self, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
 
         let b = self.pop_stack();
@@ -587,15 +1081,38 @@ impl<'gc> Root<'gc> {
         Ok(())
     }
 
-    fn op_print(&mut self) {
-        let val = self.pop_stack();
+    fn op_print(&mut self, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+        let val = self.peek_stack(0);
+
+        let out = self.stringify(val, ctx)?;
+        self.pop_stack();
 
-        let out = format!("{}", val);
         if self.capture_out {
             self.out.push(out.clone());
         }
 
-        println!("{}", out)
+        println!("{}", out);
+
+        Ok(())
+    }
+
+    // joins the parts of an interpolated string
+    fn op_concat(&mut self, count: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+        let start = self.stack.len() - count;
+
+        // copied, as converting an instance calls back into the vm
+        let parts = self.stack[start..].to_vec();
+
+        let mut string = String::new();
+
+        for part in parts {
+            string.push_str(&self.stringify(part, ctx)?);
+        }
+
+        self.stack.truncate(start);
+        self.push_stack(Value::new_obj(ObjPtr::new_string(string, ctx)));
+
+        Ok(())
     }
 
     fn op_negate(&mut self) -> RuntimeResult<()> {
@@ -634,17 +1151,62 @@ impl<'gc> Root<'gc> {
         self.push_stack(val);
     }
 
+    // counted once the instruction is fetched, so the error points at it
+    fn check_budget(&mut self) -> RuntimeResult<()> {
+        self.budget.instructions += 1;
+
+        let count = self.budget.instructions;
+
+        if let Some(max) = self.limits.instructions.filter(|max| count > *max) {
+            Err(self.limit_error(format!("Exceeded the limit of {} instructions", max)))?
+        }
+
+        if !count.is_multiple_of(CLOCK_INTERVAL) {
+            return Ok(());
+        }
+
+        if let Some(max) = self.limits.time.filter(|max| self.budget.started.elapsed() > *max) {
+            Err(self.limit_error(format!("Exceeded the time limit of {:?}", max)))?
+        }
+
+        Ok(())
+    }
+
+    // a native's callback runs without collections, so its garbage counts too
+    fn check_heap_uncollected(&mut self, ctx: &Mutation<'gc>) -> RuntimeResult<()> {
+        if let Some(max) = self.limits.heap.filter(|max| ctx.metrics().total_allocation() > *max) {
+            Err(self.heap_error(max))?
+        }
+
+        Ok(())
+    }
+
+    fn heap_error(&mut self, max: usize) -> RuntimeError {
+        self.limit_error(format!("Exceeded the heap limit of {} bytes", max))
+    }
+
+    fn limit_error(&mut self, msg: String) -> RuntimeError {
+        self.budget.exceeded = true;
+        self.runtime_error(msg)
+    }
+
     // utils
 
     fn runtime_error(&self, msg: String) -> RuntimeError {
 
+        if self.call_stack.is_empty() {
+            return host_error(msg);
+        }
+
         let chunk = self.get_chunk();
         let chunk = chunk.borrow();
 
         RuntimeError {
             msg:         msg,
             stack_trace: self.stack_trace(),
-            line:        chunk.lines[*self.ip -1],
+            line:        chunk.lines[self.ip.saturating_sub(1)],
+            span:        chunk.spans[self.ip.saturating_sub(1)],
+            module:      self.current_module(),
         }
     }
 
@@ -659,6 +1221,13 @@ impl<'gc> Root<'gc> {
             .to_owned()
     }
 
+    fn current_module(&self) -> ModuleIndex {
+        let frame = self.call_frame();
+        let frame = frame.borrow();
+
+        frame.closure.borrow().function.module
+    }
+
     fn get_chunk(&self) -> GcRefLock<'gc, Chunk<'gc>> {
         let frame     = self.call_frame();
         let frame_ref = frame.borrow();
@@ -682,7 +1251,8 @@ impl<'gc> Root<'gc> {
         self.stack[*index]
     }
 
-    fn call_value(&mut self, value: Value<'gc>, arg_count: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+    /// Calls `value` with `arg_count` positional arguments, followed by `named` pairs of a name and a value
+    fn call_value(&mut self, value: Value<'gc>, arg_count: usize, named: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
 
         let obj = value.to_obj().ok_or_else(|| {
             self.runtime_error(
@@ -690,9 +1260,20 @@ impl<'gc> Root<'gc> {
             )
         })?;
 
+        // only script functions have parameter names
+        let name = match obj {
+            ObjPtr::Obj   (Object   ::NativeFn(func))  => Some(func.name.clone()),
+            ObjPtr::ObjMut(ObjectMut::Class   (class)) => Some(class.borrow().name.clone()),
+            _                                          => None,
+        };
+
+        if let Some(name) = name.filter(|_| named > 0) {
+            Err(self.runtime_error(format!("'{}' doesn't take named arguments", name)))?
+        }
+
         match obj {
-            ObjPtr::Obj   (Object   ::NativeFn(func))  => self.call_native(       arg_count, func.func.clone()),
-            ObjPtr::ObjMut(ObjectMut::Closure (cls))   => self.call       (cls,   arg_count, ctx)?,
+            ObjPtr::Obj   (Object   ::NativeFn(func))  => self.call_native(func,  arg_count, ctx)?,
+            ObjPtr::ObjMut(ObjectMut::Closure (cls))   => self.call       (cls,   arg_count, named, ctx)?,
             ObjPtr::ObjMut(ObjectMut::Class   (class)) => self.call_class (class, arg_count, ctx)?,
             _                                          => Err(self.runtime_error(
                 format!("Object of type '{:?}' is not callable", obj)
@@ -703,18 +1284,16 @@ impl<'gc> Root<'gc> {
         Ok(())
     }
 
-    fn call(&mut self, closure: GcRefLock<'gc, ObjClosure<'gc>>, arg_count: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+    fn call(&mut self, closure: GcRefLock<'gc, ObjClosure<'gc>>, arg_count: usize, named: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
 
         let func       = closure.borrow();
         let func       = func.function;
         let func_arity = func.arity;
 
-        if func_arity != arg_count {
-            Err(self.runtime_error(format!("Expected {} arguments but got {}", func_arity, arg_count)))?
-        }
+        let omitted = self.bind_arguments(func, arg_count, named, ctx)?;
 
-        if self.call_stack.len() > STACK_FRAMES_MAX {
-            Err(self.runtime_error("Stack overflow".to_owned()))?
+        if self.call_stack.len() >= self.limits.call_depth {
+            Err(self.limit_error(format!("Stack overflow, exceeded the call depth limit of {}", self.limits.call_depth)))?
         }
 
         let frame = CallFrame {
@@ -722,6 +1301,8 @@ impl<'gc> Root<'gc> {
             ret_ip:       self.ip,
             closure,
             arity:        func_arity,
+            omitted,
+            handlers:     vec![],
         };
 
 
@@ -731,16 +1312,107 @@ impl<'gc> Root<'gc> {
         Ok(())
     }
 
-    fn call_native(&mut self, func_arity: usize, func: NativeFn<'gc>) {
-        let stack_top = self.stack.len() - func_arity;
-        let result    = func.0(&self.stack[stack_top..]);
+    /// Replaces the arguments on the stack with one value per parameter, returning the parameters left out.
+    /// Named arguments fill the parameters by name, and a rest parameter gets the extra positional ones
+    fn bind_arguments(&mut self, func: Gc<'gc, ObjFunction<'gc>>, arg_count: usize, named: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<Vec<bool>> {
+        let positional = func.arity - func.rest as usize;
+
+        // the common case, every parameter passed in order
+        if named == 0 && arg_count == func.arity && !func.rest {
+            return Ok(vec![]);
+        }
+
+        let args          = self.stack.split_off(self.stack.len() - arg_count - named * 2);
+        let (args, pairs) = args.split_at(arg_count);
+
+        let mut params = vec![None; positional];
 
-        for _ in 0..func_arity {
-            self.stack.pop();
+        for (param, arg) in params.iter_mut().zip(args) {
+            *param = Some(*arg);
         }
 
-        self.stack.pop(); // remove the callee temporary
+        for pair in pairs.chunks(2) {
+            let name  = pair[0].as_str().expect("expect argument names to be strings");
+            let index = func.params[..positional].iter().position(|param| *param == name.string);
+
+            let Some(index) = index else {
+                Err(self.runtime_error(format!("'{}' has no parameter named '{}'", func.name, name.string)))?
+            };
+
+            if params[index].replace(pair[1]).is_some() {
+                Err(self.runtime_error(format!("Argument '{}' given more than once", name.string)))?
+            }
+        }
+
+        // after the names, so a repeated one isn't reported as too many arguments
+        func.accepts().check(&func.name, arg_count + named).map_err(|msg| self.runtime_error(msg))?;
+
+        let omitted: Vec<_> = params.iter().map(Option::is_none).collect();
+
+        if let Some(missing) = omitted[..func.required].iter().position(|omitted| *omitted) {
+            Err(self.runtime_error(format!("'{}' is missing the argument '{}'", func.name, func.params[missing])))?
+        }
+
+        self.stack.extend(params.into_iter().map(|param| param.unwrap_or(Value::Nil)));
+
+        if func.rest {
+            let extra = args.get(positional..).unwrap_or_default().to_vec();
+
+            self.push_stack(Value::new_obj(ObjPtr::new_list(extra, ctx)));
+        }
+
+        Ok(omitted)
+    }
+
+    fn call_native(&mut self, func: Gc<'gc, ObjNativeFn>, arg_count: usize, ctx: &'gc Mutation<'gc>) -> RuntimeResult<()> {
+        func.func.arity.check(&func.name, arg_count).map_err(|msg| self.runtime_error(msg))?;
+
+        // copied, as the native may call back into the vm, which grows the stack
+        let stack_top = self.stack.len() - arg_count;
+        let args      = self.stack[stack_top..].to_vec();
+
+        let native = func.func.func.clone();
+        let result = native(&mut NativeCtx::new(self, ctx), &args)?;
+
+        self.stack.truncate(stack_top -1); // including the callee temporary
         self.push_stack(result);
+
+        Ok(())
+    }
+
+    /// Calls `callee` from a native, running until it returns
+    fn call_nested(&mut self, callee: Value<'gc>, args: &[Value<'gc>], ctx: &'gc Mutation<'gc>) -> RuntimeResult<Value<'gc>> {
+        let max = self.limits.call_depth.min(MAX_NESTED_CALLS);
+
+        if self.nested >= max {
+            Err(self.limit_error(format!("Stack overflow, exceeded the call depth limit of {} for calls from natives", max)))?
+        }
+
+        self.nested += 1;
+        let result = self.run_nested(callee, args, ctx);
+        self.nested -= 1;
+
+        result
+    }
+
+    fn run_nested(&mut self, callee: Value<'gc>, args: &[Value<'gc>], ctx: &'gc Mutation<'gc>) -> RuntimeResult<Value<'gc>> {
+        let depth = self.call_stack.len();
+
+        self.push_stack(callee);
+        self.stack.extend_from_slice(args);
+
+        self.call_value(callee, args.len(), 0, ctx)?;
+
+        // there's no collection until the outermost instruction finishes
+        while self.call_stack.len() > depth {
+            self.check_heap_uncollected(ctx)?;
+
+            if self.step(depth, ctx)? {
+                return Ok(self.returned);
+            }
+        }
+
+        Ok(self.pop_stack())
     }
 
 
@@ -755,11 +1427,53 @@ impl<'gc> Root<'gc> {
         Ok(())
     }
 
-    fn stack_trace(&self) -> String {
+    /// Sets up a call from the host program, returning whether there's a script function to run.
+    /// Natives and classes are called straight away
+    fn call_from_host(&mut self, name: &str, args: &[HostValue], ctx: &'gc Mutation<'gc>) -> RuntimeResult<bool> {
+
+        // whatever a previous script or failed call left behind
+        self.call_stack.clear();
+        self.stack     .clear();
+        self.thrown = None;
+
+        let callee = self.get_global(name).ok_or_else(|| {
+            host_error(format!("Undefined function '{name}'"))
+        })?;
+
+        match callee.to_obj() {
+            Some(ObjPtr::ObjMut(ObjectMut::Closure(cls))) => {
+                let func = cls.borrow().function;
+                func.accepts().check(&func.name, args.len()).map_err(host_error)?
+            }
+            Some(ObjPtr::Obj   (Object   ::NativeFn(func))) => func.func.arity.check(&func.name, args.len()).map_err(host_error)?,
+            Some(ObjPtr::ObjMut(ObjectMut::Class   (_)))  => (),
+
+            _ => Err(host_error(format!("Value of type '{}' is not callable", callee.display_type())))?,
+        }
+
+        self.push_stack(callee);
+
+        for arg in args {
+            let arg = arg.to_value(self, ctx).map_err(host_error)?;
+            self.push_stack(arg);
+        }
+
+        self.call_value(callee, args.len(), 0, ctx)?;
+
+        if !self.call_stack.is_empty() {
+            return Ok(true);
+        }
+
+        self.returned = self.pop_stack();
+
+        Ok(false)
+    }
+
+    fn stack_trace(&self) -> Vec<TraceFrame> {
 
-        let mut results = String::new();
+        let mut results = vec![];
 
-        let mut prv_ip  = self.ip;
+        let mut prv_ip  = *self.ip;
 
         for frame in self.call_stack.iter().rev() {
 
@@ -768,11 +1482,19 @@ impl<'gc> Root<'gc> {
             let func  = frame.closure.borrow();
             let func  = func .function;
             let chunk = func .chunk  .borrow();
-            let line  = chunk.lines[*prv_ip];
 
-            writeln!(results, "  [line {}] in {}", line, func.name).unwrap();
+            // the ip has already moved past the op being executed (or the call, in the outer frames)
+            let index = prv_ip.saturating_sub(1);
+
+            results.push(TraceFrame {
+                function: func.name.to_owned(),
+                line:     chunk.lines[index],
+                module:   func .module,
+                file:     self.modules[*func.module].name.to_owned(),
+                span:     chunk.spans[index],
+            });
 
-            prv_ip = frame.ret_ip;
+            prv_ip = *frame.ret_ip;
         }
 
         results
@@ -781,33 +1503,67 @@ impl<'gc> Root<'gc> {
 
 
 
-fn concatenate<'gc>(val1: &str, val2: &str, ctx: &'gc Mutation<'gc>) -> Value<'gc> {
+impl<'gc> Module<'gc> {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            globals: HashMap::new(),
+        }
+    }
+}
 
-    let str = format!("{val1}{val2}");
+impl Display for TraceFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] in {}", self.line, self.function)
+    }
+}
 
-    let obj = ObjPtr::new_string(str, ctx);
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)?;
 
-    Value::new_obj(obj)
+        for frame in self.stack_trace.iter() {
+            write!(f, "\n{}", frame)?;
+        }
+
+        Ok(())
+    }
 }
 
-fn def_natives<'gc>(globals: &'gc mut HashMap<String, Value<'gc>>, ctx: &'gc Mutation<'gc>) {
+// an error outside of any script function, such as calling an undefined function from the host
+fn host_error(msg: String) -> RuntimeError {
+    RuntimeError {
+        msg,
+        stack_trace: vec![],
+        line:        0,
+        span:        Span::default(),
+        module:      ModuleIndex(0),
+    }
+}
 
-    let mut make_global = |name: &str, func| {
+fn concatenate<'gc>(val1: &str, val2: &str, ctx: &'gc Mutation<'gc>) -> Value<'gc> {
 
-        let obj = ObjPtr::new_native_fn(name.to_owned(), func, ctx);
+    let str = format!("{val1}{val2}");
 
-        globals.insert(name.to_owned(), Value::new_obj(obj))
-    };
+    let obj = ObjPtr::new_string(str, ctx);
 
-    make_global("clock", NativeFn(clock_native));
+    Value::new_obj(obj)
 }
 
-fn clock_native<'gc>(_: &[Value<'gc>]) -> Value<'gc> {
-    let start = SystemTime::now();
+// locals captured by a closure live in a box shared by the stack and the closure
+fn read_closed<'gc>(value: Value<'gc>) -> Value<'gc> {
+    match value {
+        Value::Closed(obj) => obj.to_value().expect("expect closed value to be boxed").borrow().value,
+        _                  => value,
+    }
+}
 
-    let time_since = start.duration_since(UNIX_EPOCH).unwrap();
+fn write_closed<'gc>(obj: ObjPtr<'gc>, value: Value<'gc>, ctx: &Mutation<'gc>) {
+    let obj = obj.to_value().unwrap_or_else(|| {
+        panic!("expect object of type Value: '{}'", obj)
+    });
 
-    Value::Number(time_since.as_millis() as f64 / 1000.0)
+    obj.borrow_mut(ctx).value = value;
 }
 
 
@@ -828,7 +1584,7 @@ mod tests {
 
         let     tokens = scanner::scan_tokens(&source).unwrap();
         let mut ast    = parser ::parse_ast  (tokens) .unwrap();
-        resolve(&mut ast);
+        resolve(&mut ast).unwrap();
 
         let mut root = ArenaRoot::new(|_ctx| Root::new_test());
 
@@ -876,6 +1632,19 @@ mod tests {
         assert_stack_empty(source("test_stack_functions_nested_2.lox"));
     }
 
+    #[test]
+    fn test_properties() {
+        let mut vm = init(source("test_properties.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec!["10", "6", "20"]);
+        });
+    }
+
     #[test]
     fn test_recursion_fib() {
         let mut vm = init(source("test_recursion_fib.lox"));
@@ -896,6 +1665,266 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_interpolation() {
+        let mut vm = init(source("test_interpolation.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec![
+                "Table users (3 rows)",
+                "6! {braces}",
+                "nested USERS and 5",
+                "nil true 0.5 -2 true",
+                "<Table instance> <class Table> <native fn len>",
+                "<orders>",
+                "<orders>",
+                "<orders>|  <orders>|",
+                "1, local, 2",
+            ]);
+        });
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut vm = init(source("test_arithmetic.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert_eq!(root.out, vec![
+                "row 0", "row 1", "row 2",
+                "1 2 -2 1.5",
+                "3 -4 7",
+                "1024 512 -4 0.5 NaN",
+                "2 7 5 16 -4 0 -1",
+                "true 4 8",
+                "-9223372036854776000 inf",
+            ]);
+        });
+
+        let error = |source: &str| init(source.to_owned()).run().unwrap_err().msg;
+
+        assert_eq!(error("print 1 / 0;"),    "Division by zero");
+        assert_eq!(error("print 1 % 0;"),    "Division by zero");
+        assert_eq!(error("print 1 ~/ 0;"),   "Division by zero");
+        assert_eq!(error("print 1.5 & 1;"),  "Operand must be a whole number, found '1.5'");
+        assert_eq!(error("print 2 ** 1024 | 1;"), "Operand must be a whole number, found 'inf'");
+        assert_eq!(error("print 1 << 2 ** 63;"),  "Operand '9223372036854776000' doesn't fit in a 64 bit integer");
+        assert_eq!(error("print 100000000000000000000 & 1;"), "Operand '100000000000000000000' doesn't fit in a 64 bit integer");
+        assert_eq!(error("print 1 << -1;"),  "Can't shift by a negative number of bits, found '-1'");
+        assert_eq!(error("print \"a\" % 2;"), "Operand must be a number");
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let mut vm = init(source("test_compound_assignment.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec![
+                "30 2",
+                "4 4",
+                "unnamed",
+                "false",
+                "3",
+                "negative zero positive",
+                "7",
+                "1",
+                "no",
+                "sum",
+            ]);
+        });
+    }
+
+    #[test]
+    fn test_params() {
+        let mut vm = init(source("test_params.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec![
+                "Hello, Ada!",
+                "Hi, Ada!",
+                "Hello, Ada?",
+                "Yo, Bob!",
+                "1",
+                "6",
+                "10",
+                "0",
+                "1",
+                "1",
+                "10",
+                "3",
+                "6",
+                "6",
+            ]);
+        });
+
+        let error = |source: &str| init(format!("fun f(a, b = 1) {{}}\n{}", source)).run().unwrap_err().msg;
+
+        assert_eq!(error("f();"),                     "'f' expects 1 to 2 arguments but got 0");
+        assert_eq!(error("f(1, 2, 3);"),              "'f' expects 1 to 2 arguments but got 3");
+        assert_eq!(error("f(b: 2);"),                 "'f' is missing the argument 'a'");
+        assert_eq!(error("f(1, c: 2);"),              "'f' has no parameter named 'c'");
+        assert_eq!(error("f(1, a: 2);"),              "Argument 'a' given more than once");
+        assert_eq!(error("f(1, 2, 3, a: 2);"),        "Argument 'a' given more than once");
+        assert_eq!(error("fun g(...r) {} g(r: 1);"),  "'g' has no parameter named 'r'");
+        assert_eq!(error("print clock(x: 1);"),       "'clock' doesn't take named arguments");
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let mut vm = init(source("test_exceptions.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec![
+                "caught bad record",
+                "3",
+                "fine",
+                "Operands must be two numbers or two strings",
+                "Undefined property '.missing'",
+                "parsed a",
+                "skipped: empty record",
+                "parsed c",
+                "2",
+                "2",
+                "150",
+                "failed: empty record",
+                "parsed x",
+                "kept",
+            ]);
+        });
+
+        let error = |source: &str| init(source.to_owned()).run().unwrap_err();
+
+        let err = error("fun f() {\n    throw \"oops\";\n}\nf();");
+        assert_eq!(err.msg,                     "Uncaught error: oops");
+        assert_eq!(err.line,                    2);
+        assert_eq!(err.stack_trace[0].function, "f");
+
+        // a handler is gone once its try block finishes
+        assert_eq!(error("try {} catch (e) {}\nthrow 1;").msg, "Uncaught error: 1");
+    }
+
+    #[test]
+    fn test_local_offsets() {
+        let mut vm = init(source("test_local_offsets.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec!["3", "28", "39", "49", "1-12-28", "30", "6", "7", "10"]);
+        });
+    }
+
+    #[test]
+    fn test_limits() {
+        let error = |source: &str, limits: Limits| {
+            let mut vm = init(source.to_owned());
+            vm.root.mutate_root(|_ctx, root| root.set_limits(limits));
+            vm.run().unwrap_err()
+        };
+
+        let limits = Limits { instructions: Some(100), ..Limits::none() };
+        let err    = error("while (true) {}", limits);
+        assert_eq!(err.msg,  "Exceeded the limit of 100 instructions");
+        assert_eq!(err.line, 1);
+
+        let limits = Limits { time: Some(Duration::from_millis(20)), ..Limits::none() };
+        assert_eq!(error("var i = 0;\nwhile (true) { i = i + 1; }", limits).msg, "Exceeded the time limit of 20ms");
+
+        let limits = Limits { call_depth: 20, ..Limits::none() };
+        let err    = error("fun f(n) {\n    return f(n + 1);\n}\nf(0);", limits);
+        assert_eq!(err.msg,                     "Stack overflow, exceeded the call depth limit of 20");
+        assert_eq!(err.stack_trace.len(),       20);
+        assert_eq!(err.stack_trace[0].function, "f");
+
+        // calls through natives recurse on the Rust stack, and stop well before it overflows
+        let err = error("class A {}\nA.to_string = fun (self) { return \"{self}\"; };\nprint A();", Limits::default());
+        assert_eq!(err.msg, "Stack overflow, exceeded the call depth limit of 64 for calls from natives");
+
+        let limits = Limits { heap: Some(1 << 20), ..Limits::none() };
+        let err    = error("class Node {}\nvar head = nil;\nwhile (true) {\n    var node = Node();\n    node.next = head;\n    head = node;\n}", limits);
+        assert_eq!(err.msg, "Exceeded the heap limit of 1048576 bytes");
+
+        // the text of a string counts, not just the object holding it
+        let err = error("var s = \"x\";\nvar i = 0;\nwhile (i < 28) {\n    s += s;\n    i += 1;\n}", limits);
+        assert_eq!(err.msg, "Exceeded the heap limit of 1048576 bytes");
+
+        // a limit can't be caught, even from inside a call
+        let limits = Limits { instructions: Some(1000), ..Limits::none() };
+        let err    = error("fun spin() { while (true) {} }\nwhile (true) {\n    try { spin(); } catch (e) {}\n}", limits);
+        assert_eq!(err.msg, "Exceeded the limit of 1000 instructions");
+
+        // a script that finishes within its limits is unaffected
+        let mut vm = init("var i = 0;\nwhile (i < 10) { i = i + 1; }".to_owned());
+        vm.root.mutate_root(|_ctx, root| root.set_limits(Limits { instructions: Some(200), ..Limits::none() }));
+        vm.run().unwrap();
+    }
+
+    #[test]
+    fn test_lambdas() {
+        let mut vm = init(source("test_lambdas.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(root.out, vec![
+                "42",
+                "3",
+                "2",
+                "called",
+                "12",
+                "hi!!",
+                "2",
+                "1",
+                "15",
+                "Ab",
+                "4",
+                "100",
+                "0",
+                "1",
+                "31",
+            ]);
+        });
+    }
+
+    #[test]
+    fn test_string_literals() {
+        let mut vm = init(source("test_string_literals.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert_eq!(root.out, vec![
+                "tab\tquote\" brace{ \u{2192} done",
+                "C:\\diagrams\\{table}.svg",
+                "label=\"{table}\"",
+                "select *\n  from users\n where id = 7",
+                "inline \"quotes\" and 5",
+                "raw {table}\n  indented",
+            ]);
+        });
+    }
+
     #[test]
     fn test_closure_mutation_1() {
         let mut vm = init(source("test_closure_mutation_1.lox"));
@@ -913,4 +1942,68 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_local_assignment() {
+        let mut vm = init(source("test_local_assignment.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(&root.out[0],  "2");
+            assert_eq!(&root.out[1],  "changed");
+        });
+    }
+
+    #[test]
+    fn test_graph_attrs() {
+        let mut vm = init(source("test_graph_attrs.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert_eq!(&root.out[0], "#336699");
+
+            let graph = root.graph();
+
+            assert_eq!(graph.attrs["theme"], graph::Attr::String("dark".to_owned()));
+            assert_eq!(graph.nodes.len(), 2);
+
+            assert_eq!(graph.nodes[0].class(),        "Table");
+            assert_eq!(graph.nodes[0].attrs["name"],  graph::Attr::String("users"  .to_owned()));
+            assert_eq!(graph.nodes[1].attrs["name"],  graph::Attr::String("orders" .to_owned()));
+            assert_eq!(graph.nodes[1].attrs["color"], graph::Attr::String("#336699".to_owned()));
+        });
+
+        let err = init("graph.add(\"users\");".to_owned()).run().unwrap_err();
+        assert_eq!(err.msg, "Only instances can be added to the graph, found 'String'");
+    }
+
+    #[test]
+    fn test_class_fields() {
+        let mut vm = init(source("test_class_fields.lox"));
+
+        vm.run().unwrap();
+
+        vm.root.mutate(|_ctx, root| {
+            assert!(root.stack.is_empty());
+
+            assert_eq!(&root.out[0], "blue");
+            assert_eq!(&root.out[1], "red");
+            assert_eq!(&root.out[2], "2");
+            assert_eq!(&root.out[3], "green");
+
+            let graph = root.graph();
+            let node  = &graph.nodes[0];
+
+            assert_eq!(node.class(), "Table");
+            assert_eq!(node.classes.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["Table", "Entity"]);
+
+            assert_eq!(node.classes[0].attrs["fill"],   graph::Attr::String("blue".to_owned()));
+            assert_eq!(node.classes[1].attrs["stroke"], graph::Attr::Number(2.0));
+            assert!   (!node.attrs.contains_key("fill"));
+        });
+    }
+
 }
@@ -0,0 +1,215 @@
+use std::{collections::HashMap, time::{SystemTime, UNIX_EPOCH}};
+
+use gc_arena::{lock::GcRefLock, Mutation};
+
+use crate::script::vm::{
+    object::{Arity, NativeFn, ObjInstance, ObjPtr},
+    value::{HostValue, Value},
+    Root, RuntimeError, RuntimeResult,
+};
+
+
+/// What a native function can do to the vm, while it's running
+pub struct NativeCtx<'a, 'gc> {
+    root:    &'a mut Root<'gc>,
+    pub ctx: &'gc Mutation<'gc>,
+}
+
+
+impl<'a, 'gc> NativeCtx<'a, 'gc> {
+
+    pub fn new(root: &'a mut Root<'gc>, ctx: &'gc Mutation<'gc>) -> Self {
+        Self {
+            root,
+            ctx,
+        }
+    }
+
+    /// A runtime error at the call to the native
+    pub fn error(&self, msg: impl Into<String>) -> RuntimeError {
+        self.root.runtime_error(msg.into())
+    }
+
+    pub fn new_string(&self, string: String) -> Value<'gc> {
+        Value::new_obj(ObjPtr::new_string(string, self.ctx))
+    }
+
+    pub fn new_list(&self, items: Vec<Value<'gc>>) -> Value<'gc> {
+        Value::new_obj(ObjPtr::new_list(items, self.ctx))
+    }
+
+    /// A value from the host program, see `HostValue::to_value`
+    pub fn from_host(&self, value: &HostValue) -> Result<Value<'gc>, String> {
+        value.to_value(self.root, self.ctx)
+    }
+
+    /// Converts a value to a string as `print` does, which can call an instance's `to_string`
+    pub fn stringify(&mut self, value: Value<'gc>) -> RuntimeResult<String> {
+        self.root.stringify(value, self.ctx)
+    }
+
+    /// Calls a closure, native or class with `args`, running it to completion before returning
+    pub fn call(&mut self, callee: Value<'gc>, args: &[Value<'gc>]) -> RuntimeResult<Value<'gc>> {
+        self.root.call_nested(callee, args, self.ctx)
+    }
+
+    /// The value of a global of the main module, or a builtin
+    pub fn global(&self, name: &str) -> Option<Value<'gc>> {
+        self.root.get_global(name)
+    }
+
+    pub fn add_node(&mut self, node: GcRefLock<'gc, ObjInstance<'gc>>) {
+        self.root.add_node(node);
+    }
+
+    /// The value of a builtin, which scripts can shadow but not change
+    pub fn builtin(&self, name: &str) -> Option<Value<'gc>> {
+        self.root.builtins.get(name).copied()
+    }
+}
+
+
+pub fn def_natives<'gc>(globals: &mut HashMap<String, Value<'gc>>, ctx: &'gc Mutation<'gc>) {
+
+    let mut make_global = |name: &str, func| {
+
+        let obj = ObjPtr::new_native_fn(name.to_owned(), func, ctx);
+
+        globals.insert(name.to_owned(), Value::new_obj(obj))
+    };
+
+    make_global("clock", NativeFn::new(Arity::Fixed(0), clock_native));
+}
+
+fn clock_native<'gc>(_: &mut NativeCtx<'_, 'gc>, _: &[Value<'gc>]) -> RuntimeResult<Value<'gc>> {
+    let start = SystemTime::now();
+
+    let time_since = start.duration_since(UNIX_EPOCH).unwrap();
+
+    Ok(Value::Number(time_since.as_millis() as f64 / 1000.0))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        multi_line,
+        script::{parser::parse_ast, resolver::resolve, scanner::scan_tokens, vm::{compiler::compile, ArenaRoot, Limits, Vm}},
+    };
+
+    use super::*;
+
+    // `apply(f, args...)` calls `f` with the rest of its arguments
+    fn apply<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> RuntimeResult<Value<'gc>> {
+        native.call(args[0], &args[1..])
+    }
+
+    fn shout<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> RuntimeResult<Value<'gc>> {
+        let str = args[0].as_str().ok_or_else(|| native.error("Expected a string"))?;
+
+        Ok(native.new_string(str.string.to_uppercase()))
+    }
+
+    fn run(source: &str) -> (RuntimeResult<()>, Vec<String>) {
+        run_with(source, Limits::default())
+    }
+
+    fn run_with(source: &str, limits: Limits) -> (RuntimeResult<()>, Vec<String>) {
+        let mut ast = parse_ast(scan_tokens(source).unwrap()).unwrap();
+        resolve(&mut ast).unwrap();
+
+        let mut root = ArenaRoot::new(|_ctx| Root::new_test());
+
+        root.mutate_root(|ctx, root| {
+            for (name, func) in [
+                ("apply", NativeFn::new(Arity::Variadic(1), apply)),
+                ("shout", NativeFn::new(Arity::Fixed(1),    shout)),
+            ] {
+                root.define_builtin(name, Value::new_obj(ObjPtr::new_native_fn(name.to_owned(), func, ctx)));
+            }
+
+            root.set_limits(limits);
+            compile(ast, root, ctx).unwrap();
+        });
+
+        let mut vm     = Vm::new(root);
+        let     result = vm.run();
+
+        (result, vm.root().mutate_root(|_ctx, root| root.take_out()))
+    }
+
+    #[test]
+    fn arity() {
+        let (result, _) = run("clock(1);");
+        assert_eq!(result.unwrap_err().msg, "'clock' expects 0 arguments but got 1");
+
+        let (result, _) = run("apply();");
+        assert_eq!(result.unwrap_err().msg, "'apply' expects at least 1 argument but got 0");
+    }
+
+    #[test]
+    fn errors() {
+        let (result, _) = run("fun f() {\n    return shout(1);\n}\nf();");
+        let err = result.unwrap_err();
+
+        assert_eq!(err.msg,                         "Expected a string");
+        assert_eq!(err.stack_trace[0].function,     "f");
+        assert_eq!(err.stack_trace[0].line,         2);
+    }
+
+    #[test]
+    fn callbacks() {
+        let (result, out) = run(&multi_line!(
+            "fun greet(greeting, name) {",
+            "    return shout(greeting + \" \" + name);",
+            "}",
+            "print apply(greet, \"hello\", \"world\");",
+            "print apply(apply, shout, \"nested\");",
+            "print apply(clock) > 0;",
+            "",
+            "// the stack is left as it was, so the locals around a callback are where they were",
+            "fun around() {",
+            "    var before = 1;",
+            "    var shouted = apply(greet, \"a\", \"b\");",
+            "    var after = 2;",
+            "    return before + len(shouted) + after;",
+            "}",
+            "var x = 10;",
+            "print x + around() + apply(len, \"abcd\");",
+        ));
+
+        result.unwrap();
+        assert_eq!(out, vec!["HELLO WORLD", "NESTED", "true", "20"]);
+
+        let (result, _) = run("fun f(x) { return x + nil; }\napply(f, 1);");
+        let err = result.unwrap_err();
+
+        // errors in a callback unwind through the native
+        assert_eq!(err.msg, "Operands must be two numbers or two strings");
+        assert_eq!(err.stack_trace.iter().map(|frame| frame.function.as_str()).collect::<Vec<_>>(), vec!["f", "script"]);
+
+        // callbacks are held to the heap limit too
+        let grow = "fun grow(s) {\n    while (true) { s += s; }\n}\napply(grow, \"x\");";
+        let (result, _) = run_with(grow, Limits { heap: Some(1 << 20), ..Limits::none() });
+
+        assert_eq!(result.unwrap_err().msg, "Exceeded the heap limit of 1048576 bytes");
+    }
+
+    #[test]
+    fn catch_through_natives() {
+        let (result, out) = run(&multi_line!(
+            "fun fail(x) { throw x; }",
+            "fun safe(x) {",
+            "    try { fail(x); } catch (e) { return \"inner \" + e; }",
+            "}",
+            "",
+            "try { apply(fail, \"outer\"); } catch (e) { print e; }",
+            "try { shout(1); } catch (e) { print e; }",
+            "print apply(safe, \"x\");",
+            "print apply(shout, \"after\");",
+        ));
+
+        result.unwrap();
+        assert_eq!(out, vec!["outer", "Expected a string", "inner x", "AFTER"]);
+    }
+}
@@ -0,0 +1,66 @@
+use std::{fmt::Debug, mem::size_of};
+
+use gc_arena::{metrics::Metrics, Collect, Mutation};
+
+use crate::script::vm::{object::Fields, value::Value};
+
+
+/// Memory an object owns outside the gc heap, such as the text of a string or the items of a list.
+/// It's counted as external allocation in the arena's metrics, and so towards the heap limit, until the object is collected
+#[derive(Collect)]
+#[collect(require_static)]
+pub struct HeapBytes {
+    metrics: Metrics,
+    bytes:   usize,
+}
+
+impl HeapBytes {
+    pub fn new(bytes: usize, ctx: &Mutation) -> Self {
+        Self::counted(ctx.metrics().clone(), bytes)
+    }
+
+    fn counted(metrics: Metrics, bytes: usize) -> Self {
+        metrics.mark_external_allocation(bytes);
+
+        Self {
+            metrics,
+            bytes,
+        }
+    }
+
+    /// Sets a field, counting the name if it's new and the table if it grows
+    pub fn insert_field<'gc>(&mut self, fields: &mut Fields<'gc>, name: String, value: Value<'gc>) {
+        let capacity = fields.capacity();
+        let name_len = name.len();
+
+        if fields.insert(name, value).is_none() {
+            self.grow(name_len);
+        }
+
+        self.grow((fields.capacity() - capacity) * size_of::<(String, Value)>());
+    }
+
+    fn grow(&mut self, bytes: usize) {
+        self.metrics.mark_external_allocation(bytes);
+        self.bytes += bytes;
+    }
+}
+
+impl Drop for HeapBytes {
+    fn drop(&mut self) {
+        self.metrics.mark_external_deallocation(self.bytes);
+    }
+}
+
+// a copy of an object owns a copy of its memory
+impl Clone for HeapBytes {
+    fn clone(&self) -> Self {
+        Self::counted(self.metrics.clone(), self.bytes)
+    }
+}
+
+impl Debug for HeapBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bytes", self.bytes)
+    }
+}
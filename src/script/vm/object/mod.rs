@@ -9,6 +9,9 @@ mod obj_class;
 mod obj_instance;
 mod obj_closure;
 mod obj_value;
+mod obj_module;
+mod obj_list;
+mod heap;
 
 pub use obj_native  ::*;
 pub use obj_string  ::*;
@@ -17,6 +20,9 @@ pub use obj_class   ::*;
 pub use obj_instance::*;
 pub use obj_closure ::*;
 pub use obj_value   ::*;
+pub use obj_module  ::*;
+pub use obj_list    ::*;
+pub use heap        ::*;
 
 
 #[derive(Debug, Clone, Copy, Collect)]
@@ -31,7 +37,9 @@ pub enum ObjPtr<'gc> {
 pub enum Object<'gc> {
     String  (Gc<'gc, ObjString>),
     Function(Gc<'gc, ObjFunction<'gc>>),
-    NativeFn(Gc<'gc, ObjNativeFn<'gc>>),
+    NativeFn(Gc<'gc, ObjNativeFn>),
+    Module  (Gc<'gc, ObjModule>),
+    List    (Gc<'gc, ObjList<'gc>>),
 }
 
 #[derive(Debug, Clone, Copy, Collect)]
@@ -49,6 +57,29 @@ const ID: Cell<usize> = Cell::new(0);
 
 
 
+/// Strings are equal if they have the same contents, every other object only to itself
+impl<'gc> PartialEq for ObjPtr<'gc> {
+    fn eq(&self, other: &Self) -> bool {
+        use {Object as O, ObjectMut as M};
+
+        match (self, other) {
+            (ObjPtr::Obj(O::String  (a)), ObjPtr::Obj(O::String  (b))) => a.string == b.string,
+            (ObjPtr::Obj(O::Function(a)), ObjPtr::Obj(O::Function(b))) => Gc::ptr_eq(*a, *b),
+            (ObjPtr::Obj(O::NativeFn(a)), ObjPtr::Obj(O::NativeFn(b))) => Gc::ptr_eq(*a, *b),
+            (ObjPtr::Obj(O::Module  (a)), ObjPtr::Obj(O::Module  (b))) => Gc::ptr_eq(*a, *b),
+            (ObjPtr::Obj(O::List    (a)), ObjPtr::Obj(O::List    (b))) => Gc::ptr_eq(*a, *b),
+
+            (ObjPtr::ObjMut(M::Class   (a)), ObjPtr::ObjMut(M::Class   (b))) => Gc::ptr_eq(*a, *b),
+            (ObjPtr::ObjMut(M::Instance(a)), ObjPtr::ObjMut(M::Instance(b))) => Gc::ptr_eq(*a, *b),
+            (ObjPtr::ObjMut(M::Closure (a)), ObjPtr::ObjMut(M::Closure (b))) => Gc::ptr_eq(*a, *b),
+            (ObjPtr::ObjMut(M::Value   (a)), ObjPtr::ObjMut(M::Value   (b))) => Gc::ptr_eq(*a, *b),
+
+            _ => false,
+        }
+    }
+}
+
+
 impl<'gc> Display for ObjPtr<'gc> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
@@ -65,6 +96,12 @@ impl<'gc> Display for Object<'gc> {
             Object::String  (str)   => write!(f, "{}",             str.string),
             Object::Function(func)  => write!(f, "<fn {}>",        func .name),
             Object::NativeFn(func)  => write!(f, "<native fn {}>", func .name),
+            Object::Module  (module) => write!(f, "<module {}>",   module.name),
+            Object::List    (list)   => {
+                let items: Vec<_> = list.items.iter().map(|item| item.to_string()).collect();
+
+                write!(f, "[{}]", items.join(", "))
+            },
         }
     }
 }
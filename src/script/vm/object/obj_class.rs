@@ -1,24 +1,54 @@
-use std::{marker::PhantomData};
+use std::collections::HashMap;
 
 use gc_arena::{Collect, Gc, Mutation, lock::{GcRefLock, RefLock}};
 
-use crate::script::vm::object::{ObjPtr, ObjectMut};
+use crate::script::vm::{object::{Fields, HeapBytes, ObjPtr, ObjectMut}, value::Value};
 
 
 
 #[derive(Debug, Clone, Collect)]
 #[collect(no_drop)]
 pub struct ObjClass<'gc> {
-    pub name: String,
-    p:        PhantomData<&'gc ()>
+    pub name:       String,
+    pub superclass: Option<GcRefLock<'gc, ObjClass<'gc>>>,
+
+    /// Class level fields, which act as defaults for every instance of the class and its subclasses
+    pub fields:     Fields<'gc>,
+    heap:           HeapBytes,
 }
 
 impl<'gc> ObjClass<'gc> {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, ctx: &Mutation<'gc>) -> Self {
         Self {
             name,
-            p: PhantomData
+            superclass: None,
+            fields:     HashMap::new(),
+            heap:       HeapBytes::new(0, ctx),
+        }
+    }
+
+    pub fn set_field(&mut self, name: String, value: Value<'gc>) {
+        self.heap.insert_field(&mut self.fields, name, value);
+    }
+
+    /// Looks up a class field, walking up the superclass chain
+    pub fn get_field(&self, name: &str) -> Option<Value<'gc>> {
+        if let Some(value) = self.fields.get(name) {
+            return Some(*value);
         }
+
+        self.superclass?.borrow().get_field(name)
+    }
+
+    /// The class itself, followed by its superclasses (nearest first)
+    pub fn chain(class: GcRefLock<'gc, ObjClass<'gc>>) -> Vec<GcRefLock<'gc, ObjClass<'gc>>> {
+        let mut chain = vec![class];
+
+        while let Some(superclass) = chain.last().unwrap().borrow().superclass {
+            chain.push(superclass);
+        }
+
+        chain
     }
 }
 
@@ -29,7 +59,7 @@ impl<'gc> ObjectMut<'gc> {
             Gc::new(
                 ctx,
                 RefLock::new(
-                    ObjClass::new(name)
+                    ObjClass::new(name, ctx)
                 )
             )
         )
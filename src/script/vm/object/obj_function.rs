@@ -1,7 +1,7 @@
 
 use gc_arena::{Collect, Gc, Mutation, lock::GcRefLock};
 
-use crate::script::vm::{chunk::Chunk, object::{ObjPtr, Object}};
+use crate::script::vm::{chunk::{Capture, Chunk, ModuleIndex}, object::{Arity, ObjPtr, Object}};
 
 
 #[derive(Debug, Clone, Collect, PartialEq, Eq)]
@@ -10,25 +10,58 @@ pub struct ObjFunction<'gc> {
     pub arity: usize,
     pub chunk: GcRefLock<'gc, Chunk<'gc>>,
     pub name:  String,
+
+    /// The module whose globals the function reads and writes
+    pub module: ModuleIndex,
+
+    /// The variables a closure of the function closes over
+    pub captures: Vec<Capture>,
+
+    /// The parameter names, for binding named arguments
+    pub params:   Vec<String>,
+
+    /// The number of leading parameters without a default
+    pub required: usize,
+
+    /// Whether the last parameter collects the extra arguments in a `List`
+    pub rest:     bool,
 }
 
 
 // TODO: Macro this
 impl<'gc> ObjFunction<'gc> {
-    pub fn new(name: String, arity: usize, chunk: GcRefLock<'gc, Chunk<'gc>>) -> Self {
+    pub fn new(name: String, arity: usize, chunk: GcRefLock<'gc, Chunk<'gc>>, module: ModuleIndex) -> Self {
 
         Self {
             arity,
             chunk,
             name:  name,
+            module,
+            captures: vec![],
+            params:   vec![],
+            required: arity,
+            rest:     false,
+        }
+    }
+
+    /// The number of arguments a call may pass
+    pub fn accepts(&self) -> Arity {
+        let positional = self.arity - self.rest as usize;
+
+        if self.rest {
+            Arity::Variadic(self.required)
+        } else if self.required < positional {
+            Arity::Range(self.required, positional)
+        } else {
+            Arity::Fixed(self.arity)
         }
     }
 }
 
 
 impl<'gc> Object<'gc> {
-    pub fn new_func(name: String, arity: usize, chunk: GcRefLock<'gc, Chunk<'gc>>, ctx: &Mutation<'gc>) -> Self {
-        Object::Function(Gc::new(ctx, ObjFunction::new(name, arity, chunk)))
+    pub fn new_func(name: String, arity: usize, chunk: GcRefLock<'gc, Chunk<'gc>>, module: ModuleIndex, ctx: &Mutation<'gc>) -> Self {
+        Object::Function(Gc::new(ctx, ObjFunction::new(name, arity, chunk, module)))
     }
 
     pub fn to_func(&self) -> Option<Gc<'gc, ObjFunction<'gc>>> {
@@ -40,8 +73,8 @@ impl<'gc> Object<'gc> {
 }
 
 impl<'gc> ObjPtr<'gc> {
-    pub fn new_func(name: String, arity: usize, chunk: GcRefLock<'gc, Chunk<'gc>>, ctx: &Mutation<'gc>) -> Self {
-        ObjPtr::Obj(Object::new_func(name, arity, chunk, ctx))
+    pub fn new_func(name: String, arity: usize, chunk: GcRefLock<'gc, Chunk<'gc>>, module: ModuleIndex, ctx: &Mutation<'gc>) -> Self {
+        ObjPtr::Obj(Object::new_func(name, arity, chunk, module, ctx))
     }
 
     pub fn to_func(&self) -> Option<Gc<'gc, ObjFunction<'gc>>> {
@@ -2,7 +2,7 @@ use std::{collections::HashMap};
 
 use gc_arena::{Collect, Gc, Mutation, lock::{GcRefLock, RefLock}};
 
-use crate::script::vm::{object::{ObjPtr, ObjClass, ObjectMut}, value::Value};
+use crate::script::vm::{object::{HeapBytes, ObjPtr, ObjClass, ObjectMut}, value::Value};
 
 pub type Fields<'gc> = HashMap<String, Value<'gc>>;
 
@@ -11,15 +11,21 @@ pub type Fields<'gc> = HashMap<String, Value<'gc>>;
 pub struct ObjInstance<'gc> {
     pub class:  GcRefLock<'gc, ObjClass<'gc>>,
     pub fields: Fields<'gc>,
+    heap:       HeapBytes,
 }
 
 impl<'gc> ObjInstance<'gc> {
-    pub fn new(class: GcRefLock<'gc, ObjClass<'gc>>) -> Self {
+    pub fn new(class: GcRefLock<'gc, ObjClass<'gc>>, ctx: &Mutation<'gc>) -> Self {
         Self {
             class,
             fields: HashMap::new(),
+            heap:   HeapBytes::new(0, ctx),
         }
     }
+
+    pub fn set_field(&mut self, name: String, value: Value<'gc>) {
+        self.heap.insert_field(&mut self.fields, name, value);
+    }
 }
 
 // TODO: Macro this
@@ -29,7 +35,7 @@ impl<'gc> ObjectMut<'gc> {
             Gc::new(
                 ctx,
                 RefLock::new(
-                    ObjInstance::new(class)
+                    ObjInstance::new(class, ctx)
                 )
             )
         )
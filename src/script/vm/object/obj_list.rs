@@ -0,0 +1,52 @@
+use std::mem::size_of;
+
+use gc_arena::{Collect, Gc, Mutation};
+
+use crate::script::vm::{object::{HeapBytes, ObjPtr, Object}, value::Value};
+
+
+/// A list of values, as returned by `split` and `fields_of`. Lists can't be changed once made
+#[derive(Debug, Clone, Collect)]
+#[collect(no_drop)]
+pub struct ObjList<'gc> {
+    pub items: Vec<Value<'gc>>,
+    heap:      HeapBytes,
+}
+
+
+impl<'gc> ObjList<'gc> {
+    pub fn new(items: Vec<Value<'gc>>, ctx: &Mutation<'gc>) -> Self {
+        Self {
+            heap: HeapBytes::new(items.capacity() * size_of::<Value>(), ctx),
+            items,
+        }
+    }
+}
+
+
+// TODO: Macro this
+impl<'gc> Object<'gc> {
+    pub fn new_list(items: Vec<Value<'gc>>, ctx: &Mutation<'gc>) -> Self {
+        Object::List(Gc::new(ctx, ObjList::new(items, ctx)))
+    }
+
+    pub fn to_list(&self) -> Option<Gc<'gc, ObjList<'gc>>> {
+        match self {
+            Object::List(list) => Some(*list),
+            _                  => None,
+        }
+    }
+}
+
+impl<'gc> ObjPtr<'gc> {
+    pub fn new_list(items: Vec<Value<'gc>>, ctx: &Mutation<'gc>) -> Self {
+        ObjPtr::Obj(Object::new_list(items, ctx))
+    }
+
+    pub fn to_list(&self) -> Option<Gc<'gc, ObjList<'gc>>> {
+        match self {
+            ObjPtr::Obj   (obj) => Some(obj.to_list()?),
+            ObjPtr::ObjMut(_)   => None
+        }
+    }
+}
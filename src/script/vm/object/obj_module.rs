@@ -0,0 +1,50 @@
+use gc_arena::{Collect, Gc, Mutation};
+
+use crate::script::vm::{chunk::ModuleIndex, object::{ObjPtr, Object}};
+
+
+/// The value bound by `import "path" as name;`, its globals live in `Root::modules`
+#[derive(Debug, Clone, PartialEq, Eq, Collect)]
+#[collect(no_drop)]
+pub struct ObjModule {
+    pub name:  String,
+    pub index: ModuleIndex,
+}
+
+
+impl ObjModule {
+    pub fn new(name: String, index: ModuleIndex) -> Self {
+        Self {
+            name,
+            index,
+        }
+    }
+}
+
+
+// TODO: Macro this
+impl<'gc> Object<'gc> {
+    pub fn new_module(name: String, index: ModuleIndex, ctx: &Mutation<'gc>) -> Self {
+        Object::Module(Gc::new(ctx, ObjModule::new(name, index)))
+    }
+
+    pub fn to_module(&self) -> Option<Gc<'gc, ObjModule>> {
+        match self {
+            Object::Module(module) => Some(*module),
+            _                      => None,
+        }
+    }
+}
+
+impl<'gc> ObjPtr<'gc> {
+    pub fn new_module(name: String, index: ModuleIndex, ctx: &Mutation<'gc>) -> Self {
+        ObjPtr::Obj(Object::new_module(name, index, ctx))
+    }
+
+    pub fn to_module(&self) -> Option<Gc<'gc, ObjModule>> {
+        match self {
+            ObjPtr::Obj   (obj) => Some(obj.to_module()?),
+            ObjPtr::ObjMut(_)   => None
+        }
+    }
+}
@@ -1,21 +1,70 @@
 
+use std::{fmt::Debug, rc::Rc};
+
 use gc_arena::{Collect, Gc, Mutation};
 
-use crate::script::vm::{object::{ObjPtr, Object}, value::Value};
+use crate::script::vm::{native::NativeCtx, object::{ObjPtr, Object}, value::Value, RuntimeResult};
+
+/// A function implemented in rust, which may capture state of the host program
+#[derive(Clone)]
+pub struct NativeFn {
+    pub arity: Arity,
+    pub func:  Rc<NativeFnPtr>,
+}
+
+type NativeFnPtr = dyn for<'a, 'gc> Fn(&mut NativeCtx<'a, 'gc>, &[Value<'gc>]) -> RuntimeResult<Value<'gc>>;
 
-#[derive(Debug, Clone)]
-pub struct NativeFn<'gc>(pub fn(&[Value<'gc>]) -> Value<'gc>);
+/// The number of arguments a native function takes, checked before it's called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Fixed   (usize),
+    Variadic(usize), // at least this many
+    Range   (usize, usize),
+}
 
 #[derive(Debug, Clone, Collect)]
 #[collect(no_drop)]
-pub struct ObjNativeFn<'gc> {
-    pub func: NativeFn<'gc>,
+pub struct ObjNativeFn {
+    pub func: NativeFn,
     pub name: String,
 }
 
 
-impl<'gc> ObjNativeFn<'gc> {
-    pub fn new(name: String, func: NativeFn<'gc>) -> Self {
+impl NativeFn {
+    pub fn new<F>(arity: Arity, func: F) -> Self
+        where F: for<'a, 'gc> Fn(&mut NativeCtx<'a, 'gc>, &[Value<'gc>]) -> RuntimeResult<Value<'gc>> + 'static
+    {
+        Self {
+            arity,
+            func: Rc::new(func),
+        }
+    }
+}
+
+impl Arity {
+    /// The arity error for a call of `name` with `arg_count` arguments, if there is one
+    pub fn check(&self, name: &str, arg_count: usize) -> Result<(), String> {
+        let expected = match *self {
+            Arity::Fixed   (arity)    if arg_count != arity                    => arguments(arity),
+            Arity::Variadic(arity)    if arg_count <  arity                    => format!("at least {}", arguments(arity)),
+            Arity::Range   (min, max) if arg_count <  min || arg_count > max   => format!("{} to {} arguments", min, max),
+
+            _ => return Ok(()),
+        };
+
+        Err(format!("'{}' expects {} but got {}", name, expected, arg_count))
+    }
+}
+
+fn arguments(count: usize) -> String {
+    match count {
+        1 => "1 argument".to_owned(),
+        _ => format!("{} arguments", count),
+    }
+}
+
+impl ObjNativeFn {
+    pub fn new(name: String, func: NativeFn) -> Self {
         Self {
             func,
             name,
@@ -25,11 +74,11 @@ impl<'gc> ObjNativeFn<'gc> {
 
 // TODO: Macro this
 impl<'gc> Object<'gc> {
-    pub fn new_native_fn(name: String, func: NativeFn<'gc>, ctx: &Mutation<'gc>) -> Self {
+    pub fn new_native_fn(name: String, func: NativeFn, ctx: &Mutation<'gc>) -> Self {
         Object::NativeFn(Gc::new(ctx, ObjNativeFn::new(name, func)))
     }
 
-    pub fn to_native_fn(&self) -> Option<Gc<'gc, ObjNativeFn<'gc>>> {
+    pub fn to_native_fn(&self) -> Option<Gc<'gc, ObjNativeFn>> {
         match self {
             Object::NativeFn(func) => Some(*func),
             _                      => None,
@@ -38,11 +87,11 @@ impl<'gc> Object<'gc> {
 }
 
 impl<'gc> ObjPtr<'gc> {
-    pub fn new_native_fn(name: String, func: NativeFn<'gc>, ctx: &Mutation<'gc>) -> Self {
+    pub fn new_native_fn(name: String, func: NativeFn, ctx: &Mutation<'gc>) -> Self {
         ObjPtr::Obj(Object::new_native_fn(name, func, ctx))
     }
 
-    pub fn to_native_fn(&self) -> Option<Gc<'gc, ObjNativeFn<'gc>>> {
+    pub fn to_native_fn(&self) -> Option<Gc<'gc, ObjNativeFn>> {
         match self {
             ObjPtr::Obj   (obj) => Some(obj.to_native_fn()?),
             ObjPtr::ObjMut(_)   => None
@@ -50,19 +99,25 @@ impl<'gc> ObjPtr<'gc> {
     }
 }
 
-impl<'gc> PartialEq for ObjNativeFn<'gc> {
+impl PartialEq for ObjNativeFn {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
     }
 }
 
-impl<'gc> Eq for ObjNativeFn<'gc> {}
+impl Eq for ObjNativeFn {}
+
+impl Debug for NativeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFn({:?})", self.arity)
+    }
+}
 
 // SAFETY: It's not possible for this function to squirrel away a GC reference outside of
-//         the current GC mutation, because of lifetime branding.
+//         the current GC mutation, because the closure is `'static` and generic over the branding lifetime.
 //         And it's not possible for collection to run during function execution, because a mutation
 //         immutably borrows the GC context.
-unsafe impl<'gc> Collect for NativeFn<'gc> {
+unsafe impl Collect for NativeFn {
     #[inline]
     fn needs_trace() -> bool where Self: Sized {
         false
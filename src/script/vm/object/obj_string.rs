@@ -1,19 +1,21 @@
 
 use gc_arena::{Collect, Gc, Mutation};
 
-use crate::script::vm::object::{ObjPtr, Object};
+use crate::script::vm::object::{HeapBytes, ObjPtr, Object};
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Collect)]
+#[derive(Debug, Clone, Collect)]
 #[collect(no_drop)]
 pub struct ObjString {
     pub string: String,
+    heap:       HeapBytes,
 }
 
 
 impl ObjString {
-    pub fn new(string: String) -> ObjString {
+    pub fn new(string: String, ctx: &Mutation) -> ObjString {
         Self {
+            heap: HeapBytes::new(string.capacity(), ctx),
             string,
         }
     }
@@ -23,7 +25,7 @@ impl ObjString {
 // TODO: Macro this
 impl<'gc> Object<'gc> {
     pub fn new_string(string: String, ctx: &Mutation<'gc>) -> Self {
-        Object::String(Gc::new(ctx, ObjString::new(string)))
+        Object::String(Gc::new(ctx, ObjString::new(string, ctx)))
     }
 
     pub fn to_string(&self) -> Option<Gc<'gc, ObjString>> {
@@ -0,0 +1,490 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use gc_arena::{lock::GcRefLock, Gc, Mutation};
+
+use crate::script::vm::{
+    native::NativeCtx,
+    object::{Arity, NativeFn, ObjInstance, ObjList, ObjPtr, Object, ObjectMut},
+    value::Value,
+    RuntimeResult,
+};
+
+type Result<'gc> = RuntimeResult<Value<'gc>>;
+type Native       = for<'gc> fn(&mut NativeCtx<'_, 'gc>, &[Value<'gc>]) -> Result<'gc>;
+
+
+/// Defines the math, string and reflection natives
+pub fn def_stdlib<'gc>(globals: &mut HashMap<String, Value<'gc>>, ctx: &'gc Mutation<'gc>) {
+
+    let natives: [(&str, Arity, Native); 30] = [
+        // math
+        ("sqrt",         Arity::Fixed(1),    |native, args| math(native, args, f64::sqrt)),
+        ("floor",        Arity::Fixed(1),    |native, args| math(native, args, f64::floor)),
+        ("ceil",         Arity::Fixed(1),    |native, args| math(native, args, f64::ceil)),
+        ("round",        Arity::Fixed(1),    |native, args| math(native, args, f64::round)),
+        ("abs",          Arity::Fixed(1),    |native, args| math(native, args, f64::abs)),
+        ("sin",          Arity::Fixed(1),    |native, args| math(native, args, f64::sin)),
+        ("cos",          Arity::Fixed(1),    |native, args| math(native, args, f64::cos)),
+        ("tan",          Arity::Fixed(1),    |native, args| math(native, args, f64::tan)),
+        ("asin",         Arity::Fixed(1),    |native, args| math(native, args, f64::asin)),
+        ("acos",         Arity::Fixed(1),    |native, args| math(native, args, f64::acos)),
+        ("atan",         Arity::Fixed(1),    |native, args| math(native, args, f64::atan)),
+        ("atan2",        Arity::Fixed(2),    atan2_native),
+        ("min",          Arity::Variadic(1), |native, args| fold(native, args, f64::min)),
+        ("max",          Arity::Variadic(1), |native, args| fold(native, args, f64::max)),
+
+        // strings
+        ("len",          Arity::Fixed(1),    len_native),
+        ("substr",       Arity::Fixed(3),    substr_native),
+        ("split",        Arity::Fixed(2),    split_native),
+        ("join",         Arity::Fixed(2),    join_native),
+        ("upper",        Arity::Fixed(1),    |native, args| map_str(native, args, str::to_uppercase)),
+        ("lower",        Arity::Fixed(1),    |native, args| map_str(native, args, str::to_lowercase)),
+        ("trim",         Arity::Fixed(1),    |native, args| map_str(native, args, |str| str.trim().to_owned())),
+        ("contains",     Arity::Fixed(2),    contains_native),
+        ("replace",      Arity::Fixed(3),    replace_native),
+        ("to_string",    Arity::Fixed(1),    to_string_native),
+        ("format",       Arity::Variadic(1), format_native),
+        ("parse_number", Arity::Fixed(1),    parse_number_native),
+
+        // lists
+        ("at",           Arity::Fixed(2),    at_native),
+
+        // reflection
+        ("type_of",      Arity::Fixed(1),    |native, args| Ok(native.new_string(type_of(&args[0]).to_owned()))),
+        ("fields_of",    Arity::Fixed(1),    fields_of_native),
+        ("class_of",     Arity::Fixed(1),    class_of_native),
+    ];
+
+    for (name, arity, func) in natives {
+        let obj = ObjPtr::new_native_fn(name.to_owned(), NativeFn::new(arity, func), ctx);
+
+        globals.insert(name.to_owned(), Value::new_obj(obj));
+    }
+
+    globals.insert("pi".to_owned(), Value::Number(PI));
+}
+
+
+/// The name of a value's type, as `type_of` returns it
+pub fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Nil       => "Nil",
+        Value::Bool  (_) => "Boolean",
+        Value::Number(_) => "Number",
+        Value::Closed(_) => "Closed",
+
+        Value::Obj(ObjPtr::Obj(obj)) => match obj {
+            Object::String  (_) => "String",
+            Object::Function(_) => "Function",
+            Object::NativeFn(_) => "Function",
+            Object::Module  (_) => "Module",
+            Object::List    (_) => "List",
+        },
+
+        Value::Obj(ObjPtr::ObjMut(obj)) => match obj {
+            ObjectMut::Class   (_) => "Class",
+            ObjectMut::Instance(_) => "Instance",
+            ObjectMut::Closure (_) => "Function",
+            ObjectMut::Value   (_) => "Value",
+        },
+    }
+}
+
+
+fn number(native: &NativeCtx, value: &Value) -> RuntimeResult<f64> {
+    value.as_number()
+        .ok_or_else(|| native.error(format!("Expected a Number, found '{}'", type_of(value))))
+}
+
+fn string(native: &NativeCtx, value: &Value) -> RuntimeResult<String> {
+    value.as_str()
+        .map(|str| str.string.to_owned())
+        .ok_or_else(|| native.error(format!("Expected a String, found '{}'", type_of(value))))
+}
+
+// a whole number of characters, for indexing into strings and lists
+fn index(native: &NativeCtx, value: &Value) -> RuntimeResult<usize> {
+    let x = number(native, value)?;
+
+    if x < 0.0 || x.fract() != 0.0 {
+        return Err(native.error(format!("Expected a positive whole number, found '{}'", x)));
+    }
+
+    Ok(x as usize)
+}
+
+fn instance<'gc>(native: &NativeCtx<'_, 'gc>, value: &Value<'gc>) -> RuntimeResult<GcRefLock<'gc, ObjInstance<'gc>>> {
+    value.as_obj()
+        .and_then(|obj| obj.to_instance())
+        .ok_or_else(|| native.error(format!("Expected an Instance, found '{}'", type_of(value))))
+}
+
+fn list<'gc>(native: &NativeCtx<'_, 'gc>, value: &Value<'gc>) -> RuntimeResult<Gc<'gc, ObjList<'gc>>> {
+    value.as_obj()
+        .and_then(|obj| obj.to_list())
+        .ok_or_else(|| native.error(format!("Expected a List, found '{}'", type_of(value))))
+}
+
+
+fn math<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>], f: fn(f64) -> f64) -> Result<'gc> {
+    Ok(Value::Number(f(number(native, &args[0])?)))
+}
+
+fn fold<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>], f: fn(f64, f64) -> f64) -> Result<'gc> {
+    let first = number(native, &args[0])?;
+
+    args[1..].iter()
+        .try_fold(first, |acc, arg| Ok(f(acc, number(native, arg)?)))
+        .map(Value::Number)
+}
+
+fn atan2_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let y = number(native, &args[0])?;
+    let x = number(native, &args[1])?;
+
+    Ok(Value::Number(y.atan2(x)))
+}
+
+
+fn map_str<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>], f: impl Fn(&str) -> String) -> Result<'gc> {
+    let str = string(native, &args[0])?;
+
+    Ok(native.new_string(f(&str)))
+}
+
+// the length of a string in characters, or of a list
+fn len_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    if let Some(list) = args[0].as_obj().and_then(|obj| obj.to_list()) {
+        return Ok(Value::Number(list.items.len() as f64));
+    }
+
+    let str = string(native, &args[0])?;
+
+    Ok(Value::Number(str.chars().count() as f64))
+}
+
+// `substr(string, start, length)`, in characters, clamped to the end of the string
+fn substr_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let str    = string(native, &args[0])?;
+    let start  = index (native, &args[1])?;
+    let length = index (native, &args[2])?;
+
+    Ok(native.new_string(str.chars().skip(start).take(length).collect()))
+}
+
+fn split_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let str       = string(native, &args[0])?;
+    let separator = string(native, &args[1])?;
+
+    let parts: Vec<_> = match separator.is_empty() {
+        true  => str.chars().map(|char| native.new_string(char.to_string())).collect(),
+        false => str.split(&separator).map(|part| native.new_string(part.to_owned())).collect(),
+    };
+
+    Ok(native.new_list(parts))
+}
+
+fn join_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let list      = list  (native, &args[0])?;
+    let separator = string(native, &args[1])?;
+
+    // as `print` shows them, calling an instance's `to_string`
+    let parts = list.items.iter()
+        .map(|item| native.stringify(*item))
+        .collect::<RuntimeResult<Vec<_>>>()?;
+
+    Ok(native.new_string(parts.join(&separator)))
+}
+
+fn contains_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let str     = string(native, &args[0])?;
+    let pattern = string(native, &args[1])?;
+
+    Ok(Value::Bool(str.contains(&pattern)))
+}
+
+fn replace_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let str  = string(native, &args[0])?;
+    let from = string(native, &args[1])?;
+    let to   = string(native, &args[2])?;
+
+    if from.is_empty() {
+        return Err(native.error("Can't replace an empty string"));
+    }
+
+    Ok(native.new_string(str.replace(&from, &to)))
+}
+
+fn to_string_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let str = native.stringify(args[0])?;
+
+    Ok(native.new_string(str))
+}
+
+// `format("{:>8} {:.2}", name, size)`, the placeholders being replaced by the arguments in order,
+// or by the argument at an index (`{0}`), each optionally with a spec of `[[fill]align][0][width][.precision]`
+fn format_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let template = string(native, &args[0])?;
+    let values   = &args[1..];
+
+    let mut out   = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next  = 0;
+    let mut used  = vec![false; values.len()];
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' | '}' if chars.peek() == Some(&ch) => {
+                chars.next();
+                out.push(ch);
+            }
+
+            '}' => return Err(native.error("Unmatched '}' in format string, use '}}' for a brace")),
+
+            '{' => {
+                let mut placeholder = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch)  => placeholder.push(ch),
+                        None      => return Err(native.error("Unclosed '{' in format string, use '{{' for a brace")),
+                    }
+                }
+
+                let (index, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+
+                let index = match index.trim() {
+                    ""    => { next += 1; next - 1 },
+                    index => index.parse().map_err(|_| native.error(format!("Invalid placeholder '{{{}}}'", placeholder)))?,
+                };
+
+                let Some(&value) = values.get(index) else {
+                    return Err(native.error(format!("There's no argument {} for the format string, which was given {}", index, values.len())));
+                };
+
+                let spec = FormatSpec::parse(spec).map_err(|msg| native.error(msg))?;
+
+                used[index] = true;
+                out.push_str(&spec.format(native, value)?);
+            }
+
+            _ => out.push(ch),
+        }
+    }
+
+    if let Some(unused) = used.iter().position(|used| !used) {
+        return Err(native.error(format!("Argument {} isn't used by the format string", unused)));
+    }
+
+    Ok(native.new_string(out))
+}
+
+// the widest padding and most digits a placeholder can ask for, past which `format` would run out of memory
+const MAX_FORMAT_WIDTH: usize = u16::MAX as usize;
+
+#[derive(Debug, PartialEq)]
+struct FormatSpec {
+    fill:      char,
+    align:     Option<char>, // `<`, `^` or `>`
+    zero:      bool,
+    width:     usize,
+    precision: Option<usize>,
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> std::result::Result<Self, String> {
+        let chars: Vec<_> = spec.chars().collect();
+        let is_align      = |ch: Option<&char>| matches!(ch, Some('<' | '^' | '>'));
+        let invalid       = || format!("Invalid format spec '{}'", spec);
+
+        let (fill, align, rest) = if is_align(chars.get(1)) {
+            (chars[0], Some(chars[1]), &chars[2..])
+        } else if is_align(chars.first()) {
+            (' ',      Some(chars[0]), &chars[1..])
+        } else {
+            (' ',      None,           &chars[..])
+        };
+
+        let rest: String = rest.iter().collect();
+
+        let (zero, rest) = match rest.strip_prefix('0') {
+            Some(rest) => (true,  rest),
+            None       => (false, rest.as_str()),
+        };
+
+        let (width, precision) = rest.split_once('.').unwrap_or((rest, ""));
+
+        let width     = if width.is_empty() { 0 } else { width.parse().map_err(|_| invalid())? };
+        let precision = if precision.is_empty() && !rest.contains('.') { None } else { Some(precision.parse().map_err(|_| invalid())?) };
+
+        if width > MAX_FORMAT_WIDTH {
+            return Err(format!("Format width {} is too large, the most is {}", width, MAX_FORMAT_WIDTH));
+        }
+
+        if let Some(precision) = precision.filter(|&precision| precision > MAX_FORMAT_WIDTH) {
+            return Err(format!("Format precision {} is too large, the most is {}", precision, MAX_FORMAT_WIDTH));
+        }
+
+        Ok(Self { fill, align, zero, width, precision })
+    }
+
+    // numbers are right aligned by default, and everything else left aligned
+    fn format<'gc>(&self, native: &mut NativeCtx<'_, 'gc>, value: Value<'gc>) -> RuntimeResult<String> {
+        let number = value.as_number();
+
+        let text = match (number, self.precision) {
+            (Some(x), Some(precision)) => format!("{:.*}", precision, x),
+            (None,    Some(precision)) => native.stringify(value)?.chars().take(precision).collect(),
+            (_,       None)            => native.stringify(value)?,
+        };
+
+        let padding = self.width.saturating_sub(text.chars().count());
+
+        // zeros go between the sign and the digits
+        if self.zero && number.is_some() && self.align.is_none() {
+            let (sign, digits) = text.split_at(if text.starts_with('-') { 1 } else { 0 });
+
+            return Ok(format!("{}{}{}", sign, "0".repeat(padding), digits));
+        }
+
+        let fill = |n: usize| self.fill.to_string().repeat(n);
+
+        let align = self.align.unwrap_or(if number.is_some() { '>' } else { '<' });
+
+        Ok(match align {
+            '>' => format!("{}{}", fill(padding), text),
+            '^' => format!("{}{}{}", fill(padding / 2), text, fill(padding - padding / 2)),
+            _   => format!("{}{}", text, fill(padding)),
+        })
+    }
+}
+
+// nil if the string isn't a number, so scripts can check
+fn parse_number_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let str = string(native, &args[0])?;
+
+    Ok(str.trim().parse().map(Value::Number).unwrap_or(Value::Nil))
+}
+
+
+fn at_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let list = list (native, &args[0])?;
+    let i    = index(native, &args[1])?;
+
+    list.items.get(i)
+        .copied()
+        .ok_or_else(|| native.error(format!("Index {} is out of range for a List of length {}", i, list.items.len())))
+}
+
+
+// the instance's own fields, in alphabetical order, not the defaults set on its class
+fn fields_of_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let instance = instance(native, &args[0])?;
+
+    let mut names: Vec<_> = instance.borrow().fields.keys().cloned().collect();
+    names.sort();
+
+    let names = names.into_iter()
+        .map(|name| native.new_string(name))
+        .collect();
+
+    Ok(native.new_list(names))
+}
+
+fn class_of_native<'gc>(native: &mut NativeCtx<'_, 'gc>, args: &[Value<'gc>]) -> Result<'gc> {
+    let class = instance(native, &args[0])?.borrow().class;
+
+    Ok(Value::new_obj(ObjPtr::ObjMut(ObjectMut::Class(class))))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::script::{parser::parse_ast, resolver::resolve, scanner::scan_tokens, vm::{compiler::compile, ArenaRoot, Root, Vm}};
+
+    use super::*;
+
+    fn run(source: &str) -> (RuntimeResult<()>, Vec<String>) {
+        let mut ast = parse_ast(scan_tokens(source).unwrap()).unwrap();
+        resolve(&mut ast).unwrap();
+
+        let mut root = ArenaRoot::new(|_ctx| Root::new_test());
+
+        root.mutate_root(|ctx, root| {
+            compile(ast, root, ctx).unwrap();
+        });
+
+        let mut vm     = Vm::new(root);
+        let     result = vm.run();
+
+        (result, vm.root().mutate_root(|_ctx, root| root.take_out()))
+    }
+
+    #[test]
+    fn stdlib() {
+        let source = fs::read_to_string("./test_scripts/unit_tests/vm/test_stdlib.lox").unwrap();
+
+        let (result, out) = run(&source);
+        result.unwrap();
+
+        assert_eq!(out, vec![
+            "4", "5", "4", "2", "1", "true",
+            "Users Table", "USERS TABLE!", "5", "weaver", "true", "a.b.c", "12true", "5", "nil",
+            "3", "name", "id | name | email", "3",
+            "Number String Nil Instance", "Class Function Function", "height,width",
+            "<class Table>", "true", "List", "[id, name, email]", "Instance",
+            "height", "width",
+            "box 1 nil",
+        ]);
+    }
+
+    #[test]
+    fn format() {
+        let format = |args: &str| {
+            let (result, out) = run(&format!("print format({});", args));
+            result.map(|_| out[0].clone()).map_err(|err| err.msg)
+        };
+
+        assert_eq!(format("\"{} has {} rows\", \"users\", 3"),    Ok("users has 3 rows".to_owned()));
+        assert_eq!(format("\"{1} {0} {1}\", \"a\", \"b\""),       Ok("b a b".to_owned()));
+        assert_eq!(format("\"|{:6}|{:6}|\", \"ab\", 12"),          Ok("|ab    |    12|".to_owned()));
+        assert_eq!(format("\"|{:>6}|{:^6}|{:*<6}|\", 1, 2, 3"),    Ok("|     1|  2   |3*****|".to_owned()));
+        assert_eq!(format("\"{:.2} {:8.3} {:.0}\", pi, pi, 2.5"),  Ok("3.14    3.142 2".to_owned()));
+        assert_eq!(format("\"{:06.1} {:05}\", -2.25, 42"),        Ok("-002.2 00042".to_owned()));
+        assert_eq!(format("\"{:.3}\", \"lightweaver\""),          Ok("lig".to_owned()));
+        // the scanner unescapes the braces once, and format again
+        assert_eq!(format("\"{{{{}}}} {:}\", nil"),              Ok("{} nil".to_owned()));
+
+        assert_eq!(format("\"{} {}\", 1"),     Err("There's no argument 1 for the format string, which was given 1".to_owned()));
+        assert_eq!(format("\"{}\", 1, 2"),     Err("Argument 1 isn't used by the format string".to_owned()));
+        assert_eq!(format("\"{:x}\", 1"),      Err("Invalid format spec 'x'".to_owned()));
+        assert_eq!(format("\"{:999999999999}\", 1"),  Err("Format width 999999999999 is too large, the most is 65535".to_owned()));
+        assert_eq!(format("\"{:.99999999999}\", 1.5"), Err("Format precision 99999999999 is too large, the most is 65535".to_owned()));
+        assert_eq!(format("\"{:\", 1"),        Err("Unclosed '{' in format string, use '{{' for a brace".to_owned()));
+        assert_eq!(format("\"}\""),            Err("Unmatched '}' in format string, use '}}' for a brace".to_owned()));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| run(source).0.unwrap_err().msg;
+
+        assert_eq!(error("sqrt(\"4\");"),                    "Expected a Number, found 'String'");
+        assert_eq!(error("upper(1);"),                       "Expected a String, found 'Number'");
+        assert_eq!(error("min();"),                          "'min' expects at least 1 argument but got 0");
+        assert_eq!(error("substr(\"abc\", -1, 2);"),         "Expected a positive whole number, found '-1'");
+        assert_eq!(error("at(split(\"a,b\", \",\"), 2);"),   "Index 2 is out of range for a List of length 2");
+        assert_eq!(error("join(\"abc\", \",\");"),           "Expected a List, found 'String'");
+        assert_eq!(error("fields_of(1);"),                   "Expected an Instance, found 'Number'");
+        assert_eq!(error("class_of(split(\"a\", \",\"));"), "Expected an Instance, found 'List'");
+        assert_eq!(error("split(\"a\", \",\").len = 2;"),   "Only instances and classes have fields, found 'List'");
+        assert_eq!(error("\"abc\".len = 2;"),               "Only instances and classes have fields, found 'String'");
+        assert_eq!(error("print \"abc\".len;"),             "Only instances, classes, modules and lists have properties, found 'String'");
+        assert_eq!(error("print split(\"a\", \",\").size;"), "Undefined property '.size'");
+        assert_eq!(error("class List {}\njoin(List(), \",\");"), "Expected a List, found 'Instance'");
+        assert_eq!(error("replace(\"abc\", \"\", \"x\");"),  "Can't replace an empty string");
+    }
+}
@@ -1,9 +1,9 @@
 
-use std::{fmt::Display};
+use std::{collections::BTreeMap, fmt::Display};
 
-use gc_arena::{Collect, Gc};
+use gc_arena::{Collect, Gc, Mutation};
 
-use crate::script::vm::object::{ObjString, ObjectMut};
+use crate::script::vm::{object::{ObjString, ObjectMut}, Root};
 
 use super::object::{ObjPtr};
 
@@ -18,6 +18,24 @@ pub enum Value<'gc> {
     Nil,
 }
 
+/// A value passed between a script and the host program, which lives outside the vm
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostValue {
+    Nil,
+    Bool  (bool),
+    Number(f64),
+    String(String),
+    List  (Vec<HostValue>),
+
+    /// A copy of an instance's own fields, which becomes a new instance of the global class `class` when
+    /// passed back into a script
+    Instance { class: String, fields: BTreeMap<String, HostValue> },
+
+    /// Functions, classes and modules stay in the vm, this is how `print` displays them.
+    /// So is an instance that contains itself, where it's met again
+    Object(String),
+}
+
 impl<'gc> Value<'gc> {
 
     pub fn new_obj(obj: ObjPtr<'gc>) -> Self {
@@ -77,6 +95,56 @@ impl<'gc> Value<'gc> {
     }
 
 
+    /// A copy of the value for the host, lists and instances are copied all the way down
+    pub fn to_host(&self) -> HostValue {
+        self.host_within(&mut vec![])
+    }
+
+    /// `within` are the instances being copied, which contain this value
+    fn host_within(&self, within: &mut Vec<*const ()>) -> HostValue {
+        match self {
+            Value::Nil       => HostValue::Nil,
+            Value::Bool  (x) => HostValue::Bool  (*x),
+            Value::Number(x) => HostValue::Number(*x),
+
+            Value::Obj(ObjPtr::Obj(obj)) => {
+                if let Some(str) = obj.to_string() {
+                    return HostValue::String(str.string.to_owned());
+                }
+
+                match obj.to_list() {
+                    Some(list) => HostValue::List(list.items.iter().map(|item| item.host_within(within)).collect()),
+                    None       => HostValue::Object(self.to_string()),
+                }
+            }
+
+            Value::Obj(ObjPtr::ObjMut(obj)) => {
+                let Some(instance) = obj.to_instance() else {
+                    return HostValue::Object(self.to_string());
+                };
+
+                let ptr = Gc::as_ptr(instance) as *const ();
+
+                if within.contains(&ptr) {
+                    return HostValue::Object(self.to_string());
+                }
+
+                within.push(ptr);
+
+                let instance = instance.borrow();
+                let fields   = instance.fields.iter()
+                    .map(|(name, value)| (name.to_owned(), value.host_within(within)))
+                    .collect();
+
+                within.pop();
+
+                HostValue::Instance { class: instance.class.borrow().name.to_owned(), fields }
+            }
+
+            Value::Closed(obj) => HostValue::Object(format!("{}", obj)),
+        }
+    }
+
     pub fn display_type(&self) -> String {
         match self {
             Value::Obj   (_) => "Object" .to_owned(),
@@ -89,12 +157,76 @@ impl<'gc> Value<'gc> {
 }
 
 
+impl HostValue {
+
+    /// The value in the vm. Lists and instances are new objects, an instance's class is looked up among the
+    /// globals of `root`. Objects can't be passed back into a script
+    pub fn to_value<'gc>(&self, root: &Root<'gc>, ctx: &Mutation<'gc>) -> Result<Value<'gc>, String> {
+        let value = match self {
+            HostValue::Nil         => Value::Nil,
+            HostValue::Bool  (x)   => Value::Bool  (*x),
+            HostValue::Number(x)   => Value::Number(*x),
+            HostValue::String(str) => Value::new_obj(ObjPtr::new_string(str.to_owned(), ctx)),
+
+            HostValue::List(items) => {
+                let items = items.iter()
+                    .map(|item| item.to_value(root, ctx))
+                    .collect::<Result<_, _>>()?;
+
+                Value::new_obj(ObjPtr::new_list(items, ctx))
+            }
+
+            HostValue::Instance { class, fields } => {
+                let found = root.get_global(class)
+                    .and_then(|value| value.to_obj_mut())
+                    .and_then(|obj| obj.to_class());
+
+                let Some(found) = found else {
+                    Err(format!("Can't pass an instance of '{}' into a script, there's no class '{}'", class, class))?
+                };
+
+                let obj = ObjPtr::new_instance(found, ctx);
+
+                let instance = obj.to_instance().unwrap();
+                let mut instance = instance.borrow_mut(ctx);
+
+                for (name, value) in fields {
+                    instance.set_field(name.to_owned(), value.to_value(root, ctx)?);
+                }
+
+                Value::new_obj(obj)
+            }
+
+            HostValue::Object(obj) => Err(format!("Can't pass the object '{}' into a script", obj))?,
+        };
+
+        Ok(value)
+    }
+
+    pub fn display_type(&self) -> &'static str {
+        match self {
+            HostValue::Nil              => "Nil",
+            HostValue::Bool  (_)        => "Boolean",
+            HostValue::Number(_)        => "Number",
+            HostValue::String(_)        => "String",
+            HostValue::List  (_)        => "List",
+            HostValue::Instance { .. }  => "Instance",
+            HostValue::Object(_)        => "Object",
+        }
+    }
+
+    fn expected<T>(&self, type_: &str) -> Result<T, String> {
+        Err(format!("Expected a {}, found '{}'", type_, self.display_type()))
+    }
+}
+
+
 impl<'gc> PartialEq for Value<'gc> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Number(a), Value::Number(b)) => a == b,
             (Value::Bool  (a), Value::Bool  (b)) => a == b,
-            (Value::Obj   (_), Value::Obj   (_)) => todo!(),
+            (Value::Obj   (a), Value::Obj   (b)) => a == b,
             (Value::Nil,       Value::Nil)       => true,
             _                                    => false,
         }
@@ -112,3 +244,75 @@ impl<'gc> Display for Value<'gc> {
         }
     }
 }
+
+impl Display for HostValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostValue::Nil         => f.write_str("nil"),
+            HostValue::Bool  (x)   => x.fmt(f),
+            HostValue::Number(x)   => x.fmt(f),
+            HostValue::String(str) => str.fmt(f),
+            HostValue::Object(obj) => obj.fmt(f),
+
+            HostValue::List(items) => {
+                f.write_str("[")?;
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 { f.write_str(", ")?; }
+                    item.fmt(f)?;
+                }
+
+                f.write_str("]")
+            }
+
+            HostValue::Instance { class, .. } => write!(f, "<{} instance>", class),
+        }
+    }
+}
+
+impl From<()>     for HostValue { fn from(_: ())      -> Self { HostValue::Nil } }
+impl From<bool>   for HostValue { fn from(x: bool)    -> Self { HostValue::Bool  (x) } }
+impl From<f64>    for HostValue { fn from(x: f64)     -> Self { HostValue::Number(x) } }
+impl From<i32>    for HostValue { fn from(x: i32)     -> Self { HostValue::Number(x as f64) } }
+impl From<usize>  for HostValue { fn from(x: usize)   -> Self { HostValue::Number(x as f64) } }
+impl From<&str>   for HostValue { fn from(x: &str)    -> Self { HostValue::String(x.to_owned()) } }
+impl From<String> for HostValue { fn from(x: String)  -> Self { HostValue::String(x) } }
+
+impl<T: Into<HostValue>> From<Option<T>> for HostValue {
+    fn from(x: Option<T>) -> Self {
+        x.map_or(HostValue::Nil, Into::into)
+    }
+}
+
+impl TryFrom<HostValue> for bool {
+    type Error = String;
+
+    fn try_from(value: HostValue) -> Result<Self, Self::Error> {
+        match value {
+            HostValue::Bool(x) => Ok(x),
+            _                  => value.expected("Boolean"),
+        }
+    }
+}
+
+impl TryFrom<HostValue> for f64 {
+    type Error = String;
+
+    fn try_from(value: HostValue) -> Result<Self, Self::Error> {
+        match value {
+            HostValue::Number(x) => Ok(x),
+            _                    => value.expected("Number"),
+        }
+    }
+}
+
+impl TryFrom<HostValue> for String {
+    type Error = String;
+
+    fn try_from(value: HostValue) -> Result<Self, Self::Error> {
+        match value {
+            HostValue::String(x) => Ok(x),
+            _                    => value.expected("String"),
+        }
+    }
+}
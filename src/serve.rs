@@ -0,0 +1,322 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{diagram::Diagram, script::diagnostic::DiagnosticReport, watch::{render_png, watch_with}, Limits};
+
+
+static HOST: &str = "127.0.0.1";
+
+// a comment is sent on idle event streams, to notice clients that have gone away
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+static PAGE: &str = include_str!("serve.html");
+
+
+/// The latest run of the script, as seen by the browser
+#[derive(Debug, Default)]
+struct Preview {
+    version: u64,
+    png:     Option<Vec<u8>>, // from the last successful run
+    error:   Option<String>,  // from the latest run, if it failed
+}
+
+#[derive(Default)]
+struct Shared {
+    preview: Mutex<Preview>,
+    updated: Condvar,
+}
+
+struct Response {
+    status:       &'static str,
+    content_type: &'static str,
+    body:         Vec<u8>,
+}
+
+
+/// Serves a page on localhost showing the diagram built by `script`, which reloads whenever
+/// the script changes, and shows the errors on top of the last good diagram when it fails
+pub fn serve(script: &Path, port: u16, limits: Limits) -> io::Result<()> {
+
+    let listener = TcpListener::bind((HOST, port))?;
+    let shared   = Arc::new(Shared::default());
+
+    eprintln!("[serve] previewing {} at http://{}", script.display(), listener.local_addr()?);
+
+    let watched = shared.clone();
+    let script  = script.to_owned();
+
+    thread::spawn(move || {
+        watch_with(&script, limits, |diagram, report| watched.update(diagram, report))
+    });
+
+    accept(listener, shared);
+    Ok(())
+}
+
+fn accept(listener: TcpListener, shared: Arc<Shared>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+
+        let shared = shared.clone();
+
+        thread::spawn(move || {
+            // the client hanging up isn't worth reporting
+            let _ = handle(stream, &shared);
+        });
+    }
+}
+
+fn handle(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    // skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let method    = parts.next().unwrap_or("");
+    let target    = parts.next().unwrap_or("");
+    let path      = target.split('?').next().unwrap_or("");
+
+    let response = match (method, path) {
+        ("GET", "/")            => Response::ok("text/html; charset=utf-8", PAGE.as_bytes().to_vec()),
+        ("GET", "/diagram.png") => shared.png(),
+        ("GET", "/events")      => return shared.stream_events(stream),
+
+        ("GET", _)              => Response::text("404 Not Found",          "Not found"),
+        _                       => Response::text("405 Method Not Allowed", "Method not allowed"),
+    };
+
+    response.write(&mut stream)
+}
+
+
+impl Shared {
+
+    fn update(&self, diagram: Option<&Diagram>, report: &DiagnosticReport) {
+        eprint!("{}", report);
+
+        let mut preview = self.preview.lock().unwrap();
+
+        match diagram.map(render_png) {
+            Some(Ok(png)) => {
+                preview.png   = Some(png);
+                preview.error = None;
+            }
+            Some(Err(err)) => preview.error = Some(format!("Unable to render the diagram: {}", err)),
+            None           => preview.error = Some(report.to_string()),
+        }
+
+        preview.version += 1;
+        self.updated.notify_all();
+    }
+
+    fn png(&self) -> Response {
+        let preview = self.preview.lock().unwrap();
+
+        match &preview.png {
+            Some(png) => Response::ok("image/png", png.clone()),
+            None      => Response::text("503 Service Unavailable", "The script hasn't run successfully yet"),
+        }
+    }
+
+    /// Sends the state of the preview as server sent events, starting with the current state,
+    /// then every time it changes
+    fn stream_events(&self, mut stream: TcpStream) -> io::Result<()> {
+
+        write!(stream, "HTTP/1.1 200 OK\r\n")?;
+        write!(stream, "Content-Type: text/event-stream\r\n")?;
+        write!(stream, "Cache-Control: no-cache\r\n")?;
+        write!(stream, "Connection: keep-alive\r\n\r\n")?;
+        stream.flush()?;
+
+        let mut seen = 0;
+
+        loop {
+            let event = {
+                let preview = self.preview.lock().unwrap();
+
+                let (preview, timeout) = self.updated
+                    .wait_timeout_while(preview, KEEP_ALIVE, |preview| preview.version == seen)
+                    .unwrap();
+
+                seen = preview.version;
+
+                match (timeout.timed_out(), &preview.error) {
+                    (true,  _)           => ": keep-alive\n\n".to_owned(),
+                    (false, Some(error)) => event("error",  error),
+                    (false, None)        => event("reload", &seen.to_string()),
+                }
+            };
+
+            stream.write_all(event.as_bytes())?;
+            stream.flush()?;
+        }
+    }
+}
+
+// multi line data is sent as one `data:` field per line
+fn event(name: &str, data: &str) -> String {
+    let mut event = format!("event: {}\n", name);
+
+    for line in data.trim_end().lines() {
+        event.push_str(&format!("data: {}\n", line));
+    }
+
+    event + "\n"
+}
+
+
+impl Response {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn text(status: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body:         body.as_bytes().to_vec(),
+        }
+    }
+
+    fn write(&self, stream: &mut TcpStream) -> io::Result<()> {
+        write!(stream, "HTTP/1.1 {}\r\n", self.status)?;
+        write!(stream, "Content-Type: {}\r\n", self.content_type)?;
+        write!(stream, "Content-Length: {}\r\n", self.body.len())?;
+        write!(stream, "Cache-Control: no-cache\r\n")?;
+        write!(stream, "Connection: close\r\n\r\n")?;
+
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::{color::Theme, script::diagnostic::Diagnostic};
+
+    use super::*;
+
+    fn start() -> (u16, Arc<Shared>) {
+        let listener = TcpListener::bind((HOST, 0)).unwrap();
+        let port     = listener.local_addr().unwrap().port();
+        let shared   = Arc::new(Shared::default());
+
+        let accepted = shared.clone();
+        thread::spawn(move || accept(listener, accepted));
+
+        (port, shared)
+    }
+
+    fn connect(port: u16, method: &str, path: &str) -> TcpStream {
+        let mut stream = TcpStream::connect((HOST, port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n", method, path).unwrap();
+        stream
+    }
+
+    fn get(port: u16, path: &str) -> (String, Vec<u8>) {
+        let mut response = vec![];
+        connect(port, "GET", path).read_to_end(&mut response).unwrap();
+
+        let split  = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head   = String::from_utf8_lossy(&response[..split]).into_owned();
+        let status = head.lines().next().unwrap().to_owned();
+
+        (status, response[split + 4 ..].to_vec())
+    }
+
+    // reads up to the end of the next event
+    fn next_event(reader: &mut BufReader<TcpStream>) -> String {
+        let mut event = String::new();
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+
+            if line == "\n" && event.contains("event:") {
+                return event;
+            }
+
+            event.push_str(&line);
+        }
+    }
+
+    fn diagram() -> Diagram {
+        Diagram {
+            theme: Theme::default(),
+            nodes: vec![],
+            files: vec![],
+        }
+    }
+
+    fn failed() -> DiagnosticReport {
+        let error = Diagnostic::error("test.lox", "Expect expression".to_owned());
+        DiagnosticReport::new(String::new(), vec![error])
+    }
+
+    #[test]
+    fn routes() {
+        let (port, shared) = start();
+
+        let (status, body) = get(port, "/");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(String::from_utf8(body).unwrap().contains("EventSource"));
+
+        assert_eq!(get(port, "/diagram.png").0, "HTTP/1.1 503 Service Unavailable");
+        assert_eq!(get(port, "/missing")    .0, "HTTP/1.1 404 Not Found");
+
+        shared.update(Some(&diagram()), &DiagnosticReport::new(String::new(), vec![]));
+
+        let (status, body) = get(port, "/diagram.png?v=1");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(&body[1..4], b"PNG");
+
+        // a failed run keeps the last good image
+        shared.update(None, &failed());
+        assert_eq!(get(port, "/diagram.png").1, body);
+    }
+
+    #[test]
+    fn events() {
+        let (port, shared) = start();
+
+        shared.update(Some(&diagram()), &DiagnosticReport::new(String::new(), vec![]));
+
+        let mut reader = BufReader::new(connect(port, "GET", "/events"));
+
+        let head = next_event(&mut reader);
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("Content-Type: text/event-stream"));
+        assert!(head.ends_with("event: reload\ndata: 1\n"));
+
+        shared.update(None, &failed());
+        assert_eq!(next_event(&mut reader), "event: error\ndata: error: Expect expression\ndata:  --> test.lox\n");
+
+        shared.update(Some(&diagram()), &DiagnosticReport::new(String::new(), vec![]));
+        assert_eq!(next_event(&mut reader), "event: reload\ndata: 3\n");
+    }
+}
@@ -0,0 +1,83 @@
+use super::{BoundingBox, Dimensions, Ellipse, GetBounding, Location, Rect};
+
+
+/// An upright cylinder, as drawn for databases.
+///
+/// `cap_height` is the full height of the elliptical caps on the top and bottom
+#[derive(Clone)]
+pub struct Cylinder {
+    pub center:     Location,
+    pub dim:        Dimensions,
+    pub cap_height: f64,
+}
+
+impl Cylinder {
+    pub fn new(center: Location, dim: Dimensions, cap_height: f64) -> Cylinder {
+        let cap_height = cap_height.clamp(0.0, dim.height);
+
+        Cylinder {
+            center,
+            dim,
+            cap_height,
+        }
+    }
+
+    /// The side walls, between the centers of the two caps
+    pub fn body(&self) -> Rect {
+        Rect::new(
+            self.center.clone(),
+            Dimensions::new(self.dim.width, self.dim.height - self.cap_height),
+        )
+    }
+
+    pub fn top_cap(&self) -> Ellipse {
+        self.cap(1.0)
+    }
+
+    pub fn bottom_cap(&self) -> Ellipse {
+        self.cap(-1.0)
+    }
+
+    fn cap(&self, sign: f64) -> Ellipse {
+        let offset = (self.dim.height - self.cap_height) / 2.0;
+
+        Ellipse::new(
+            Location  ::new(self.center.x, self.center.y + offset * sign),
+            Dimensions::new(self.dim.width, self.cap_height),
+        )
+    }
+}
+
+impl GetBounding for Cylinder {
+    fn bounding_box(&self) -> BoundingBox {
+        let rect = Rect::new(self.center.clone(), self.dim.clone());
+
+        rect.bounding_box()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps() {
+        let cylinder = Cylinder::new(
+            Location  ::new(0.0, 0.0),
+            Dimensions::new(2.0, 4.0),
+            1.0,
+        );
+
+        let top    = cylinder.top_cap()   .bounding_box();
+        let bottom = cylinder.bottom_cap().bounding_box();
+        let body   = cylinder.body()      .bounding_box();
+
+        // the caps sit flush with the bounding box
+        assert_eq!(top   .top(),     2.0);
+        assert_eq!(bottom.bottom(), -2.0);
+
+        assert_eq!(body  .top(),     1.5);
+        assert_eq!(body  .bottom(), -1.5);
+    }
+}
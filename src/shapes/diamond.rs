@@ -0,0 +1,65 @@
+use super::{BoundingBox, Dimensions, GetBounding, Location};
+
+
+/// A rhombus, with its vertices at the midpoints of the sides of its bounding box
+#[derive(Clone)]
+pub struct Diamond {
+    pub center: Location,
+    pub dim:    Dimensions,
+}
+
+impl Diamond {
+    pub fn new(center: Location, dim: Dimensions) -> Diamond {
+        Diamond {
+            center,
+            dim,
+        }
+    }
+
+    /// Clockwise, starting from the top
+    pub fn vertices(&self) -> Vec<Location> {
+        let half_width  = self.dim.width  / 2.0;
+        let half_height = self.dim.height / 2.0;
+
+        let Location { x, y, .. } = self.center;
+
+        vec![
+            Location::new(x,              y + half_height),
+            Location::new(x + half_width, y),
+            Location::new(x,              y - half_height),
+            Location::new(x - half_width, y),
+        ]
+    }
+}
+
+impl GetBounding for Diamond {
+    fn bounding_box(&self) -> BoundingBox {
+        let vertices = self.vertices();
+
+        let x = vertices.iter().map(|v| v.x).collect();
+        let y = vertices.iter().map(|v| v.y).collect();
+
+        BoundingBox::from((&x, &y))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounding_box() {
+        let diamond = Diamond::new(
+            Location  ::new(0.0, 1.0),
+            Dimensions::new(2.0, 4.0),
+        );
+
+        let bounding_box = diamond.bounding_box();
+
+        assert_eq!(bounding_box.left(),   -1.0);
+        assert_eq!(bounding_box.right(),   1.0);
+        assert_eq!(bounding_box.top(),     3.0);
+        assert_eq!(bounding_box.bottom(), -1.0);
+    }
+}
@@ -0,0 +1,54 @@
+use super::{BoundingBox, Dimensions, GetBounding, Location};
+
+
+/// An axis aligned ellipse, `dim` being the full width and height
+#[derive(Clone)]
+pub struct Ellipse {
+    pub center: Location,
+    pub dim:    Dimensions,
+}
+
+impl Ellipse {
+    pub fn new(center: Location, dim: Dimensions) -> Ellipse {
+        Ellipse {
+            center,
+            dim,
+        }
+    }
+
+    pub fn radii(&self) -> (f64, f64) {
+        (self.dim.width / 2.0, self.dim.height / 2.0)
+    }
+}
+
+impl GetBounding for Ellipse {
+    fn bounding_box(&self) -> BoundingBox {
+        let (rx, ry) = self.radii();
+
+        BoundingBox {
+            top_left:     Location::new(self.center.x - rx, self.center.y + ry),
+            bottom_right: Location::new(self.center.x + rx, self.center.y - ry),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounding_box() {
+        let ellipse = Ellipse::new(
+            Location  ::new(1.0, -1.0),
+            Dimensions::new(4.0,  2.0),
+        );
+
+        let bounding_box = ellipse.bounding_box();
+
+        assert_eq!(bounding_box.left(),   -1.0);
+        assert_eq!(bounding_box.right(),   3.0);
+        assert_eq!(bounding_box.top(),     0.0);
+        assert_eq!(bounding_box.bottom(), -2.0);
+    }
+}
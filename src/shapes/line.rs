@@ -1,6 +1,7 @@
 use super::{BoundingBox, GetBounding, Location};
 
 
+#[derive(Clone)]
 pub struct Line {
     pub start: Location,
     pub end:   Location,
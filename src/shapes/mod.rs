@@ -1,13 +1,24 @@
 mod rect;
 mod line;
 mod triangle;
+mod ellipse;
+mod rounded_rect;
+mod diamond;
+mod cylinder;
+mod polygon;
 mod bounding_box;
 
 pub use rect::Rect;
 pub use line::Line;
 pub use triangle::Triangle;
+pub use ellipse::Ellipse;
+pub use rounded_rect::RoundedRect;
+pub use diamond::Diamond;
+pub use cylinder::Cylinder;
+pub use polygon::Polygon;
 pub use bounding_box::{{BoundingBox, GetBounding}};
 
+#[derive(Clone)]
 pub struct Dimensions {
     pub width:  f64,
     pub height: f64,
@@ -42,10 +53,29 @@ impl Location {
     }
 }
 
-// TODO:
 #[allow(unused)]
 pub enum ShapeType {
-    Rect    (Rect),
-    Line    (Line),
-    Triangle(Triangle),
+    Rect       (Rect),
+    Line       (Line),
+    Triangle   (Triangle),
+    Ellipse    (Ellipse),
+    RoundedRect(RoundedRect),
+    Diamond    (Diamond),
+    Cylinder   (Cylinder),
+    Polygon    (Polygon),
+}
+
+impl GetBounding for ShapeType {
+    fn bounding_box(&self) -> BoundingBox {
+        match self {
+            ShapeType::Rect       (shape) => shape.bounding_box(),
+            ShapeType::Line       (shape) => shape.bounding_box(),
+            ShapeType::Triangle   (shape) => shape.bounding_box(),
+            ShapeType::Ellipse    (shape) => shape.bounding_box(),
+            ShapeType::RoundedRect(shape) => shape.bounding_box(),
+            ShapeType::Diamond    (shape) => shape.bounding_box(),
+            ShapeType::Cylinder   (shape) => shape.bounding_box(),
+            ShapeType::Polygon    (shape) => shape.bounding_box(),
+        }
+    }
 }
\ No newline at end of file
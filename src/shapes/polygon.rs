@@ -0,0 +1,73 @@
+use std::f64::consts::PI;
+
+use super::{BoundingBox, Dimensions, GetBounding, Location};
+
+
+#[derive(Clone)]
+pub struct Polygon {
+    pub vertices: Vec<Location>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Location>) -> Polygon {
+        assert!(vertices.len() >= 3, "a polygon needs at least 3 vertices");
+
+        Polygon {
+            vertices,
+        }
+    }
+
+    /// A regular polygon inscribed in the ellipse filling `dim`, with its first vertex at the top
+    pub fn regular(center: Location, dim: Dimensions, sides: usize) -> Polygon {
+        let rx = dim.width  / 2.0;
+        let ry = dim.height / 2.0;
+
+        let vertices = (0..sides)
+            .map(|i| {
+                let angle = PI / 2.0 - 2.0 * PI * i as f64 / sides as f64;
+
+                Location::new(
+                    center.x + rx * angle.cos(),
+                    center.y + ry * angle.sin(),
+                )
+            })
+            .collect()
+        ;
+
+        Self::new(vertices)
+    }
+}
+
+impl GetBounding for Polygon {
+    fn bounding_box(&self) -> BoundingBox {
+        let x = self.vertices.iter().map(|v| v.x).collect();
+        let y = self.vertices.iter().map(|v| v.y).collect();
+
+        BoundingBox::from((&x, &y))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regular() {
+        let square = Polygon::regular(
+            Location  ::new(0.0, 0.0),
+            Dimensions::new(2.0, 2.0),
+            4,
+        );
+
+        assert_eq!(square.vertices.len(), 4);
+
+        let bounding_box = square.bounding_box();
+
+        // Note: float compare, may not yield stable results
+        assert!((bounding_box.top()    -  1.0).abs() < 1e-9);
+        assert!((bounding_box.bottom() - -1.0).abs() < 1e-9);
+        assert!((bounding_box.left()   - -1.0).abs() < 1e-9);
+        assert!((bounding_box.right()  -  1.0).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,7 @@
 use super::{BoundingBox, Dimensions, GetBounding, Location};
 
 
+#[derive(Clone)]
 pub struct Rect {
     pub center: Location,
     pub dim:    Dimensions,
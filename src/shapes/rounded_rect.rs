@@ -0,0 +1,61 @@
+use super::{BoundingBox, Dimensions, GetBounding, Location, Rect};
+
+
+/// A rect with circular corners of `radius`
+#[derive(Clone)]
+pub struct RoundedRect {
+    pub center: Location,
+    pub dim:    Dimensions,
+    pub radius: f64,
+}
+
+impl RoundedRect {
+    pub fn new(center: Location, dim: Dimensions, radius: f64) -> RoundedRect {
+        RoundedRect {
+            center,
+            dim,
+            radius,
+        }
+    }
+
+    pub fn from_rect(rect: Rect, radius: f64) -> RoundedRect {
+        Self::new(rect.center, rect.dim, radius)
+    }
+
+    /// The corner radius, limited so that opposite corners never overlap
+    pub fn clamped_radius(&self) -> f64 {
+        let max = self.dim.width.min(self.dim.height) / 2.0;
+
+        self.radius.clamp(0.0, max)
+    }
+}
+
+impl GetBounding for RoundedRect {
+    fn bounding_box(&self) -> BoundingBox {
+        let rect = Rect::new(self.center.clone(), self.dim.clone());
+
+        rect.bounding_box()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamped_radius() {
+        let rect = RoundedRect::new(
+            Location  ::new(0.0, 0.0),
+            Dimensions::new(2.0, 1.0),
+            5.0,
+        );
+
+        assert_eq!(rect.clamped_radius(), 0.5);
+
+        let bounding_box = rect.bounding_box();
+
+        assert_eq!(bounding_box.left(), -1.0);
+        assert_eq!(bounding_box.top(),   0.5);
+    }
+}
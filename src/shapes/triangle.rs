@@ -1,18 +1,15 @@
 #![allow(dead_code)]
 
-use super::{BoundingBox, Location};
+use super::{BoundingBox, GetBounding, Location};
 
 
 type Vertices = (Location, Location, Location);
 
+#[derive(Clone)]
 pub struct Triangle {
     pub vertices: Vertices,
 }
 
-pub trait GetBounding {
-    fn bounding_box(&self) -> BoundingBox;
-}
-
 
 impl Triangle {
     pub fn new(vertices: Vertices) -> Triangle {
@@ -20,6 +17,10 @@ impl Triangle {
             vertices,
         }
     }
+
+    pub fn to_vec(&self) -> Vec<Location> {
+        vec![self.vertices.0.clone(), self.vertices.1.clone(), self.vertices.2.clone()]
+    }
 }
 
 impl GetBounding for Triangle {
@@ -0,0 +1,264 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{diagram::Diagram, render::DataBuff, script::{self, diagnostic::DiagnosticReport}, Limits};
+
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static IMAGE_SIZE: usize = 1000;
+
+
+/// Polls the modification times of a set of files
+struct Watcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+
+/// Re-runs `script` and re-renders `out` whenever the script, or a file it was built from, changes.
+///
+/// Errors go to stderr, and leave the image from the last successful run in place
+pub fn watch(script: &Path, out: &Path, limits: Limits) -> ! {
+    watch_with(script, limits, |diagram, report| write_output(out, diagram, report))
+}
+
+/// Runs `script` and hands the result to `on_run`, then again every time
+/// the script or a file the last run read changes
+pub fn watch_with(script: &Path, limits: Limits, mut on_run: impl FnMut(Option<&Diagram>, &DiagnosticReport)) -> ! {
+
+    let mut watcher = Watcher::new(vec![script.to_owned()]);
+    watcher.changed();
+
+    loop {
+        watcher.set_files(run(script, limits, &mut on_run));
+
+        while !watcher.changed() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Runs the script, returning the files it read, whether or not it succeeded
+fn run(script: &Path, limits: Limits, on_run: &mut impl FnMut(Option<&Diagram>, &DiagnosticReport)) -> Vec<PathBuf> {
+
+    let (diagram, report) = script::run_file(script, limits);
+    on_run(diagram.as_ref(), &report);
+
+    report.files
+}
+
+fn write_output(out: &Path, diagram: Option<&Diagram>, report: &DiagnosticReport) {
+    eprint!("{}", report);
+
+    let Some(diagram) = diagram else {
+        eprintln!("[watch] script failed, keeping the previous output");
+        return;
+    };
+
+    let result = render_png(diagram)
+        .map_err(io::Error::other)
+        .and_then(|png| write_atomic(out, |w| w.write_all(&png)));
+
+    match result {
+        Ok (()) => eprintln!("[watch] wrote {}", out.display()),
+        Err(err) => eprintln!("[watch] unable to write {}: {}", out.display(), err),
+    }
+}
+
+pub fn render_png(diagram: &Diagram) -> Result<Vec<u8>, png::EncodingError> {
+
+    let mut buff = DataBuff::new(IMAGE_SIZE, IMAGE_SIZE);
+    buff.render_diagram(diagram);
+
+    let mut png = vec![];
+    buff.encode_png(&mut png)?;
+
+    Ok(png)
+}
+
+/// Writes to a temporary file next to `path`, then renames it over `path`,
+/// so that anything reading `path` never sees a partially written file
+fn write_atomic(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>) -> io::Result<()> {
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(".tmp");
+
+    let tmp = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp)
+        .and_then(|file| {
+            let mut w = BufWriter::new(file);
+
+            write(&mut w)?;
+            w.flush()?;
+            w.get_ref().sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+
+    result
+}
+
+
+impl Watcher {
+
+    fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            files: files.into_iter().map(|file| (file, None)).collect(),
+        }
+    }
+
+    /// Whether any file was modified, created or deleted since the last call
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+
+        for (path, seen) in self.files.iter_mut() {
+            let modified = modified(path);
+
+            if modified != *seen {
+                *seen   = modified;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Replaces the watched files, files that were already being watched keep their last seen time
+    fn set_files(&mut self, files: Vec<PathBuf>) {
+        self.files = files.into_iter()
+            .map(|file| {
+                let seen = self.files.iter()
+                    .find(|(path, _)| *path == file)
+                    .map_or_else(|| modified(&file), |(_, seen)| *seen);
+
+                (file, seen)
+            })
+            .collect();
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebuild(script: &Path, out: &Path) -> Vec<PathBuf> {
+        run(script, Limits::default(), &mut |diagram, report| write_output(out, diagram, report))
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lightweaver_watch_{}_{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn atomic_write() {
+        let dir  = temp_dir("atomic_write");
+        let path = dir.join("out.txt");
+
+        write_atomic(&path, |w| w.write_all(b"first")).unwrap();
+        write_atomic(&path, |w| w.write_all(b"second")).unwrap();
+
+        let failed = write_atomic(&path, |_| Err(io::Error::other("failed")));
+        assert!(failed.is_err());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn keeps_last_good_output() {
+        let dir    = temp_dir("keeps_last_good_output");
+        let script = dir.join("test.lox");
+        let out    = dir.join("out").join("diagram.png");
+
+        fs::write(&script, "class Node {}\nNode();\n").unwrap();
+
+        let files = rebuild(&script, &out);
+        assert_eq!(files, vec![script.clone()]);
+
+        let image = fs::read(&out).unwrap();
+
+        fs::write(&script, "class Node {\n").unwrap();
+        assert_eq!(rebuild(&script, &out), vec![script.clone()]);
+
+        assert_eq!(fs::read(&out).unwrap(), image);
+    }
+
+    #[test]
+    fn watches_theme_files() {
+        let dir    = temp_dir("watches_theme_files");
+        let script = dir.join("test.lox");
+        let theme  = dir.join("team.theme");
+
+        fs::write(&theme,  "base: dark\n").unwrap();
+        fs::write(&script, "graph.theme = \"team.theme\";\n").unwrap();
+
+        let files = rebuild(&script, &dir.join("diagram.png"));
+        assert_eq!(files, vec![script, theme]);
+    }
+
+    #[test]
+    fn watches_imports_of_failed_runs() {
+        let dir    = fs::canonicalize(temp_dir("watches_imports_of_failed_runs")).unwrap();
+        let script = dir.join("test.lox");
+        let lib    = dir.join("lib.lox");
+        let out    = dir.join("diagram.png");
+
+        fs::write(&script, "import \"lib.lox\" as lib;\nimport \"missing.lox\" as missing;\n").unwrap();
+        fs::write(&lib,    "var x = ;\n").unwrap();
+
+        // the import that failed to parse is watched, though the run stopped before the missing one
+        assert_eq!(rebuild(&script, &out), vec![script.clone(), lib.clone()]);
+        assert!(!out.exists());
+
+        fs::write(&lib, "var x = 1;\n").unwrap();
+        assert_eq!(rebuild(&script, &out), vec![script.clone(), lib.clone(), dir.join("missing.lox")]);
+
+        fs::write(&script, "import \"lib.lox\" as lib;\n").unwrap();
+        assert_eq!(rebuild(&script, &out), vec![script, lib]);
+        assert!(out.exists());
+    }
+
+    #[test]
+    fn detects_changes() {
+        let dir  = temp_dir("detects_changes");
+        let path = dir.join("test.lox");
+
+        fs::write(&path, "print 1;").unwrap();
+
+        let mut watcher = Watcher::new(vec![path.clone()]);
+        assert!( watcher.changed());
+        assert!(!watcher.changed());
+
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+
+        assert!( watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::remove_file(&path).unwrap();
+        assert!( watcher.changed());
+    }
+}